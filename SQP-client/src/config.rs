@@ -0,0 +1,375 @@
+use std::str::FromStr;
+use SQP_common::error::{Error, ProtocolError};
+use SQP_common::logger::LogRetention;
+
+pub(crate) const USAGE: &str = "Usage: worker <server_address> [-smart | --smart] \
+[--strategy <simple|smart|explore|hand>] [--legend] [--color] [--minimap-interval <moves>] \
+[--seek-goal] [--explain] [--log-retain <sessions>] [--log-max-mb <mb>] [--json-logs] [--players <n|name,name...>] \
+[--name-prefix <prefix>]\n\
+       worker --validate <radar_file>";
+
+/**
+ * The Strategy enum lists the ways a player can look for the exit.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Strategy {
+    /// Follow the right-hand rule.
+    Simple,
+    /// Walk toward the nearest open passage of the accumulated map.
+    Smart,
+    /// Walk toward unexplored cells of the accumulated map first, then open passages, then checked ones.
+    Explore,
+    /// Read the moves from the terminal.
+    Hand,
+}
+
+impl FromStr for Strategy {
+    type Err = Error;
+
+    /**
+     * The from_str function reads a strategy name given with --strategy.
+     *
+     * @param value: &str - simple, smart, explore or hand (case insensitive)
+     * @return Result<Strategy, Error> - The strategy, or InvalidArguments for another name
+     */
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "simple" => Ok(Strategy::Simple),
+            "smart" => Ok(Strategy::Smart),
+            "explore" => Ok(Strategy::Explore),
+            "hand" => Ok(Strategy::Hand),
+            _ => Err(ProtocolError::InvalidArguments.into()),
+        }
+    }
+}
+
+/**
+ * The ClientConfig struct holds the options given on the command line.
+ * It is cloned into every player thread.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ClientConfig {
+    pub(crate) server_address: String,
+    pub(crate) strategy: Strategy,
+    pub(crate) show_legend: bool,
+    pub(crate) color: bool,
+    pub(crate) minimap_interval: Option<u64>,
+    /// With the simple strategy, step onto the exit as soon as the radar shows it next to the player.
+    pub(crate) seek_goal: bool,
+    /// With the simple strategy, print why each move was chosen.
+    pub(crate) explain: bool,
+    /// How much of the previous sessions the log files keep.
+    pub(crate) log_retention: LogRetention,
+    /// Write the log files as one JSON object per entry instead of plain text.
+    pub(crate) json_logs: bool,
+    /// Number of players to ask the server for, the server's team size when None.
+    pub(crate) players: Option<usize>,
+    /// Names of the players given with --players, which also set their number.
+    pub(crate) player_names: Option<Vec<String>>,
+    /// Name the players "<prefix>-1", "<prefix>-2"... instead of the default names.
+    pub(crate) name_prefix: Option<String>,
+}
+
+/**
+ * The parse_args function builds the ClientConfig from the command line arguments.
+ * The first argument is the program name, the second the server address, then the flags.
+ *
+ * @param args: &[String] - The command line arguments
+ * @return Result<ClientConfig, Error> - The parsed configuration
+ */
+pub(crate) fn parse_args(args: &[String]) -> Result<ClientConfig, Error> {
+    if args.len() < 2 {
+        return Err(ProtocolError::InvalidArguments.into());
+    }
+
+    let server_address = args[1].clone();
+    if !server_address.contains(':') {
+        return Err(ProtocolError::InvalidAddressFormat.into());
+    }
+
+    let mut config = ClientConfig {
+        server_address,
+        strategy: Strategy::Simple,
+        show_legend: false,
+        color: false,
+        minimap_interval: None,
+        seek_goal: false,
+        explain: false,
+        log_retention: LogRetention::default(),
+        json_logs: false,
+        players: None,
+        player_names: None,
+        name_prefix: None,
+    };
+
+    let mut flags = args[2..].iter();
+    while let Some(arg) = flags.next() {
+        match arg.as_str() {
+            "-smart" | "--smart" => config.strategy = Strategy::Smart,
+            "--strategy" => {
+                config.strategy = flags
+                    .next()
+                    .ok_or(ProtocolError::InvalidArguments)?
+                    .parse()?;
+            }
+            "--legend" => config.show_legend = true,
+            "--color" => config.color = true,
+            "--seek-goal" => config.seek_goal = true,
+            "--explain" => config.explain = true,
+            "--json-logs" => config.json_logs = true,
+            "--minimap-interval" => {
+                let interval = flags
+                    .next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .filter(|&interval| interval > 0)
+                    .ok_or(ProtocolError::InvalidArguments)?;
+                config.minimap_interval = Some(interval);
+            }
+            "--log-retain" => {
+                let sessions = flags
+                    .next()
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .ok_or(ProtocolError::InvalidArguments)?;
+                config.log_retention.max_sessions = Some(sessions);
+            }
+            "--log-max-mb" => {
+                let bytes = flags
+                    .next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .and_then(|megabytes| megabytes.checked_mul(1024 * 1024))
+                    .ok_or(ProtocolError::InvalidArguments)?;
+                config.log_retention.max_bytes = Some(bytes);
+            }
+            "--players" => {
+                let value = flags.next().ok_or(ProtocolError::InvalidArguments)?;
+                match value.parse::<usize>() {
+                    Ok(players) if players > 0 => config.players = Some(players),
+                    Ok(_) => return Err(ProtocolError::InvalidArguments.into()),
+                    Err(_) => {
+                        let names = parse_player_names(value)?;
+                        config.players = Some(names.len());
+                        config.player_names = Some(names);
+                    }
+                }
+            }
+            "--name-prefix" => {
+                let prefix = flags
+                    .next()
+                    .filter(|prefix| !prefix.is_empty())
+                    .ok_or(ProtocolError::InvalidArguments)?;
+                config.name_prefix = Some(prefix.clone());
+            }
+            _ => return Err(ProtocolError::InvalidArguments.into()),
+        }
+    }
+
+    Ok(config)
+}
+
+/**
+ * The parse_player_names function reads the player names given with --players.
+ *
+ * @param value: &str - Comma-separated names, e.g. "Nino,Lea,Max"
+ * @return Result<Vec<String>, Error> - The trimmed names, or InvalidArguments for an empty or
+ * repeated name, as the server refuses a name already taken in the team
+ */
+fn parse_player_names(value: &str) -> Result<Vec<String>, Error> {
+    let names: Vec<String> = value
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .collect();
+    let has_duplicates = names
+        .iter()
+        .enumerate()
+        .any(|(index, name)| names[..index].contains(name));
+    if names.iter().any(String::is_empty) || has_duplicates {
+        return Err(ProtocolError::InvalidArguments.into());
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_defaults() {
+        let config = parse_args(&args(&["worker", "127.0.0.1:8778"])).unwrap();
+        assert_eq!(config.server_address, "127.0.0.1:8778");
+        assert_eq!(config.strategy, Strategy::Simple);
+        assert!(!config.show_legend);
+        assert!(!config.color);
+        assert_eq!(config.minimap_interval, None);
+        assert!(!config.seek_goal);
+        assert!(!config.explain);
+        assert_eq!(config.log_retention, LogRetention::default());
+        assert!(!config.json_logs);
+        assert_eq!(config.players, None);
+        assert_eq!(config.player_names, None);
+        assert_eq!(config.name_prefix, None);
+    }
+
+    #[test]
+    fn test_parse_args_flags() {
+        let config = parse_args(&args(&[
+            "worker",
+            "127.0.0.1:8778",
+            "--legend",
+            "--color",
+            "-smart",
+            "--minimap-interval",
+            "5",
+            "--seek-goal",
+            "--explain",
+            "--log-retain",
+            "3",
+            "--log-max-mb",
+            "2",
+            "--players",
+            "5",
+            "--json-logs",
+            "--name-prefix",
+            "Bot",
+        ]))
+        .unwrap();
+        assert_eq!(config.strategy, Strategy::Smart);
+        assert!(config.show_legend);
+        assert!(config.color);
+        assert_eq!(config.minimap_interval, Some(5));
+        assert!(config.seek_goal);
+        assert!(config.explain);
+        assert_eq!(config.log_retention.max_sessions, Some(3));
+        assert_eq!(config.log_retention.max_bytes, Some(2 * 1024 * 1024));
+        assert_eq!(config.players, Some(5));
+        assert!(config.json_logs);
+        assert_eq!(config.name_prefix.as_deref(), Some("Bot"));
+    }
+
+    #[test]
+    fn test_parse_args_player_names() {
+        let config = parse_args(&args(&[
+            "worker",
+            "127.0.0.1:8778",
+            "--players",
+            "Nino, Lea,Max",
+        ]))
+        .unwrap();
+        assert_eq!(
+            config.player_names,
+            Some(vec![
+                "Nino".to_string(),
+                "Lea".to_string(),
+                "Max".to_string()
+            ])
+        );
+        assert_eq!(config.players, Some(3));
+
+        for names in ["Nino,,Max", "Nino,Lea,Nino", ""] {
+            assert_eq!(
+                parse_args(&args(&["worker", "127.0.0.1:8778", "--players", names])),
+                Err(ProtocolError::InvalidArguments.into()),
+                "--players {:?}",
+                names
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_args_log_max_mb_overflow() {
+        let max_bytes = |megabytes: &str| {
+            parse_args(&args(&[
+                "worker",
+                "127.0.0.1:8778",
+                "--log-max-mb",
+                megabytes,
+            ]))
+            .map(|config| config.log_retention.max_bytes)
+        };
+        let largest = u64::MAX / (1024 * 1024);
+        assert_eq!(
+            max_bytes(&largest.to_string()),
+            Ok(Some(largest * 1024 * 1024))
+        );
+        assert_eq!(
+            max_bytes(&(largest + 1).to_string()),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+        assert_eq!(
+            max_bytes(&u64::MAX.to_string()),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+    }
+
+    #[test]
+    fn test_parse_args_smart_flag() {
+        let smart = |flags: &[&str]| {
+            let mut values = vec!["worker", "127.0.0.1:8778"];
+            values.extend_from_slice(flags);
+            parse_args(&args(&values)).unwrap().strategy == Strategy::Smart
+        };
+        assert!(smart(&["--smart"]));
+        assert!(smart(&["-smart"]));
+        assert!(smart(&["--color", "--smart", "--players", "2"]));
+        // The wall follower by default
+        assert!(!smart(&[]));
+        assert!(!smart(&["--color"]));
+    }
+
+    #[test]
+    fn test_parse_args_strategy() {
+        let config = parse_args(&args(&[
+            "worker",
+            "127.0.0.1:8778",
+            "--strategy",
+            "Explore",
+        ]))
+        .unwrap();
+        assert_eq!(config.strategy, Strategy::Explore);
+        assert_eq!(
+            parse_args(&args(&["worker", "127.0.0.1:8778", "--strategy", "hand"]))
+                .unwrap()
+                .strategy,
+            Strategy::Hand
+        );
+        assert_eq!(
+            parse_args(&args(&["worker", "127.0.0.1:8778", "--strategy", "fast"])),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert_eq!(
+            parse_args(&args(&["worker"])),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+        assert_eq!(
+            parse_args(&args(&["worker", "localhost"])),
+            Err(ProtocolError::InvalidAddressFormat.into())
+        );
+        assert_eq!(
+            parse_args(&args(&["worker", "127.0.0.1:8778", "--unknown"])),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+        assert_eq!(
+            parse_args(&args(&["worker", "127.0.0.1:8778", "--minimap-interval"])),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+        assert_eq!(
+            parse_args(&args(&["worker", "127.0.0.1:8778", "--log-retain", "-1"])),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+        assert_eq!(
+            parse_args(&args(&["worker", "127.0.0.1:8778", "--players", "0"])),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+        assert_eq!(
+            parse_args(&args(&["worker", "127.0.0.1:8778", "--name-prefix", ""])),
+            Err(ProtocolError::InvalidArguments.into())
+        );
+    }
+}
@@ -1,25 +1,65 @@
 extern crate core;
 
+mod config;
 mod decoder;
 mod models;
 mod player;
 mod request_models;
+mod validate;
 
+use config::{parse_args, ClientConfig, USAGE};
 use player::start_player_thread;
 use request_models::{Message, RegisterTeam};
 use std::collections::HashMap;
-use std::net::TcpStream;
+use std::net::{Shutdown, TcpStream};
 use std::sync::{Arc, OnceLock, RwLock};
 use std::{env, thread};
-use SQP_common::error::{Error, NetworkError, ProtocolError};
+use validate::validate_radar_file;
+use SQP_common::error::{Error, NetworkError, PlayerError, ProtocolError};
 use SQP_common::logger;
-use SQP_common::server_utils::{parse_token_from_response, receive_message, send_message};
+use SQP_common::logger::LogFormat;
+use SQP_common::server_utils::{
+    parse_token_from_response, receive_message, send_message, PROTOCOL_VERSION,
+};
 
 static SECRET_MAP: OnceLock<Arc<RwLock<HashMap<String, u64>>>> = OnceLock::new();
 
+/// Names of the first players of the team; the next ones are numbered.
+const PLAYER_NAMES: [&str; 3] = ["Nino", "Paul", "Loriane"];
+
 fn main() -> Result<(), Error> {
+    // Step 1: Get server address and options from command line arguments
+    let args: Vec<String> = env::args().collect();
+
+    // Check a file of recorded radar views instead of playing
+    if let [_, flag, path] = args.as_slice() {
+        if flag == "--validate" {
+            validate_radars(path);
+        }
+    }
+
+    let config = match parse_args(&args) {
+        Ok(config) => config,
+        Err(Error::Protocol(ProtocolError::InvalidAddressFormat)) => {
+            eprintln!(
+                "Error: Invalid server address. Use <host:port> format (e.g., 127.0.0.1:8778)."
+            );
+            return Err(ProtocolError::InvalidAddressFormat.into());
+        }
+        Err(e) => {
+            eprintln!("{}", USAGE);
+            return Err(e);
+        }
+    };
+    let server_address = &config.server_address;
+
     // Setup logging
-    logger::init_logging(
+    let log_format = if config.json_logs {
+        LogFormat::Json
+    } else {
+        LogFormat::Text
+    };
+    logger::init_logging_with_format(
         "log",
         &[
             "main",
@@ -28,86 +68,403 @@ fn main() -> Result<(), Error> {
             "challenge",
             "hint",
             "server_message",
+            "decision",
         ],
+        config.log_retention,
+        log_format,
     )?;
 
-    // Step 1: Get server address from command line arguments
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: worker <server_address> [-smart]");
-        return Err(ProtocolError::InvalidArguments.into());
+    // Initialize the global map
+    SECRET_MAP
+        .set(Arc::new(RwLock::new(HashMap::new())))
+        .unwrap();
+
+    // Step 2: Register the team, on a connection of its own
+    let team_name = format!("Team {}", rand::random::<u32>());
+    let Some((registration_token, granted_players)) =
+        register_team(server_address, &team_name, config.players)?
+    else {
+        eprintln!("Team already registered, skipping token parsing");
+        return Ok(());
+    };
+
+    // Step 3: Spawn threads for as many players as the server granted, each opening its own connection
+    let mut handles = vec![];
+    for player_name in team_player_names(&config, granted_players)? {
+        let registration_token = registration_token.clone();
+        let config = config.clone();
+        // Spawn a new thread for each player, name the thread with the player's name
+        handles.push(
+            thread::Builder::new()
+                .name(player_name.clone())
+                .spawn(move || start_player_thread(player_name, registration_token, config))
+                .map_err(|e| PlayerError::ThreadFailed(e.to_string()))?,
+        );
     }
-    let server_address = &args[1];
-    let use_smart_mode = args.iter().any(|arg| arg == "-smart");
 
-    // Validate the address format
-    if !server_address.contains(':') {
-        eprintln!("Error: Invalid server address. Use <host:port> format (e.g., 127.0.0.1:8778).");
-        return Err(ProtocolError::InvalidAddressFormat.into());
+    // Wait for all threads to complete, then sum up the game of each player
+    for handle in handles {
+        let player_name = handle.thread().name().unwrap_or_default().to_string();
+        let result = handle
+            .join()
+            .map_err(|_| PlayerError::ThreadFailed(format!("player {} panicked", player_name)))?;
+        match result {
+            Ok(result) => println!("Player {} {}", player_name, result),
+            Err(e) => eprintln!("Player {} failed: {}", player_name, e),
+        }
     }
+    println!("All players have exited the labyrinth. Program completed.");
+
+    Ok(())
+}
 
-    // Step 2: Connect to the server
+/**
+ * The register_team function registers the team and returns its registration token.
+ * Registration and play use separate connections: the registration connection is shut down
+ * as soon as the answer is received, before any player connects with the token.
+ *
+ * @param server_address: &str - The server address, host:port
+ * @param team_name: &str - The name of the team to register
+ * @param players: Option<usize> - The team size to ask for, the server's size when None
+ * @return Result<Option<(String, usize)>, Error> - The registration token and the number of players
+ * granted by the server, None if the team is already registered, VersionMismatch if the server
+ * speaks another protocol version (a server that does not say is assumed to speak ours)
+ */
+fn register_team(
+    server_address: &str,
+    team_name: &str,
+    players: Option<usize>,
+) -> Result<Option<(String, usize)>, Error> {
     let mut team_stream = TcpStream::connect(server_address)
         .map_err(|e| NetworkError::ConnectionFailed(e.to_string()))?;
     println!("Connected to server at {}", server_address);
 
-    // Initialize the global map
-    SECRET_MAP
-        .set(Arc::new(RwLock::new(HashMap::new())))
-        .unwrap();
-
-    // Step 3: Register the team
-    let team_name = format!("Team {}", rand::random::<u32>());
-
     let register_team_message = Message::RegisterTeam(RegisterTeam {
         name: team_name.to_string(),
+        expected_players: players,
+        protocol_version: Some(PROTOCOL_VERSION),
     });
     send_message(&mut team_stream, &register_team_message)?;
     println!("Registered team: {}", team_name);
 
-    // Step 4: Receive the registration token
+    // Receive the registration token, then close the registration connection
     let response = receive_message(&mut team_stream)?;
+    if let Err(e) = team_stream.shutdown(Shutdown::Both) {
+        eprintln!("Failed to close the registration connection: {}", e);
+    }
     println!("Server response: {}", response);
-    println!("Raw server response: {:?}", response);
+
+    let response_value = serde_json::from_str::<serde_json::Value>(&response).ok();
+    let server_version = response_value
+        .as_ref()
+        .and_then(|value| value["protocol_version"].as_u64());
+    if let Some(theirs) = server_version.filter(|&version| version != u64::from(PROTOCOL_VERSION)) {
+        return Err(ProtocolError::VersionMismatch {
+            ours: PROTOCOL_VERSION,
+            theirs: theirs as u32,
+        }
+        .into());
+    }
 
     eprintln!("Parsing token from response");
-    if response.contains("AlreadyRegistered") {
-        eprintln!("Team already registered, skipping token parsing");
-        return Ok(());
+    let token = match parse_token_from_response(&response) {
+        Ok(token) => token,
+        Err(Error::Protocol(ProtocolError::RegistrationFailed(reason)))
+            if reason == "AlreadyRegistered" =>
+        {
+            return Ok(None)
+        }
+        Err(e) => return Err(e),
+    };
+    // The server may grant fewer players than asked for
+    let granted_players = response_value
+        .and_then(|value| value["RegisterTeamResult"]["Ok"]["expected_players"].as_u64())
+        .map_or(PLAYER_NAMES.len(), |granted| granted as usize);
+    Ok(Some((token, granted_players)))
+}
+
+/**
+ * The team_player_names function picks the names of the players to start once the team is
+ * registered: the names given with --players, or else generated ones.
+ *
+ * @param config: &ClientConfig - The command line options
+ * @param granted: usize - The number of players the server granted to the team
+ * @return Result<Vec<String>, Error> - The names, or RegistrationFailed if more names were given
+ * than the server granted players
+ */
+fn team_player_names(config: &ClientConfig, granted: usize) -> Result<Vec<String>, Error> {
+    match &config.player_names {
+        Some(names) if names.len() > granted => Err(ProtocolError::RegistrationFailed(format!(
+            "{} players named but the server granted {}",
+            names.len(),
+            granted
+        ))
+        .into()),
+        Some(names) => Ok(names.clone()),
+        None => Ok(player_names(granted, config.name_prefix.as_deref())),
     }
+}
 
-    let registration_token = parse_token_from_response(&response)?;
+/**
+ * The player_names function names the players of the team.
+ *
+ * @param count: usize - The number of players
+ * @param prefix: Option<&str> - The prefix given with --name-prefix, if any
+ * @return Vec<String> - The names generated from the prefix, or else the names of PLAYER_NAMES
+ * first, then "Player 4", "Player 5"...
+ */
+fn player_names(count: usize, prefix: Option<&str>) -> Vec<String> {
+    if let Some(prefix) = prefix {
+        return generate_player_names(count, prefix);
+    }
+    (0..count)
+        .map(|index| match PLAYER_NAMES.get(index) {
+            Some(name) => name.to_string(),
+            None => format!("Player {}", index + 1),
+        })
+        .collect()
+}
 
-    // Step 5: Spawn threads for each player
-    let players = ["Nino", "Paul", "Loriane"];
-    let mut handles = vec![];
-    for player in players.iter() {
-        let player_name = player.to_string();
-        let registration_token = registration_token.clone();
-        let server_address = server_address.clone();
-        // Spawn a new thread for each player, name the thread with the player's name
-        handles.push(
-            thread::Builder::new()
-                .name(player_name.clone())
-                .spawn(move || {
-                    start_player_thread(
-                        player_name,
-                        registration_token,
-                        server_address,
-                        use_smart_mode,
-                    )
-                })
-                .map_err(|_| ProtocolError::RegistrationFailed)?,
+/**
+ * The generate_player_names function numbers the players after a prefix, so that the names
+ * are distinct within the team whatever its size.
+ *
+ * @param count: usize - The number of players
+ * @param prefix: &str - The start of every name
+ * @return Vec<String> - "<prefix>-1", "<prefix>-2"... up to "<prefix>-<count>"
+ */
+fn generate_player_names(count: usize, prefix: &str) -> Vec<String> {
+    (1..=count)
+        .map(|number| format!("{}-{}", prefix, number))
+        .collect()
+}
+
+/**
+ * The validate_radars function runs the --validate mode: it checks every radar view of a file,
+ * prints the failing lines and exits with a nonzero code if any line fails.
+ *
+ * @param path: &str - The file of recorded radar views, one per line
+ */
+fn validate_radars(path: &str) -> ! {
+    let failures = match validate_radar_file(path) {
+        Ok(failures) => failures,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    for (line, error) in &failures {
+        println!("Line {}: {}", line, error);
+    }
+    if failures.is_empty() {
+        println!("All radar views of {} are valid", path);
+        std::process::exit(0);
+    }
+    println!("{} invalid radar view(s) in {}", failures.len(), path);
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_registration_connection_is_closed_before_players_connect() {
+        let log_dir = tempfile::tempdir().unwrap();
+        logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"]).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let server = thread::spawn(move || {
+            let (mut team_stream, _) = listener.accept().unwrap();
+            let request = receive_message(&mut team_stream).unwrap();
+            assert!(request.contains("RegisterTeam"));
+            send_message(
+                &mut team_stream,
+                &serde_json::json!({"RegisterTeamResult": {"Ok": {
+                    "expected_players": 3,
+                    "registration_token": "abc123"
+                }}}),
+            )
+            .unwrap();
+
+            // The client closed its side: the next read is the end of the stream
+            let mut buffer = [0; 1];
+            assert_eq!(team_stream.read(&mut buffer).unwrap(), 0);
+        });
+
+        let token = register_team(&address, "Team Test", None).unwrap();
+        assert_eq!(token, Some(("abc123".to_string(), 3)));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_server_of_another_protocol_version_is_refused() {
+        let log_dir = tempfile::tempdir().unwrap();
+        logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"]).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // A server of the next version refusing the team
+        let server = thread::spawn(move || {
+            let (mut team_stream, _) = listener.accept().unwrap();
+            let request: serde_json::Value =
+                serde_json::from_str(&receive_message(&mut team_stream).unwrap()).unwrap();
+            assert_eq!(
+                request["RegisterTeam"]["protocol_version"],
+                PROTOCOL_VERSION
+            );
+            send_message(
+                &mut team_stream,
+                &serde_json::json!({
+                    "RegisterTeamResult": {"Error": "VersionMismatch"},
+                    "protocol_version": PROTOCOL_VERSION + 1
+                }),
+            )
+            .unwrap();
+        });
+
+        assert_eq!(
+            register_team(&address, "Team Test", None),
+            Err(ProtocolError::VersionMismatch {
+                ours: PROTOCOL_VERSION,
+                theirs: PROTOCOL_VERSION + 1,
+            }
+            .into())
         );
+        server.join().unwrap();
     }
 
-    // Wait for all threads to complete
-    for handle in handles {
-        handle
-            .join()
-            .map_err(|_| ProtocolError::RegistrationFailed)?;
+    #[test]
+    fn test_already_registered_team_is_told_from_other_refusals() {
+        let log_dir = tempfile::tempdir().unwrap();
+        logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"]).unwrap();
+
+        // A server refusing the registration with each reason in turn
+        let refuse = |reason: &'static str| {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let server = thread::spawn(move || {
+                let (mut team_stream, _) = listener.accept().unwrap();
+                receive_message(&mut team_stream).unwrap();
+                send_message(
+                    &mut team_stream,
+                    &serde_json::json!({ "RegisterTeamResult": { "Error": reason } }),
+                )
+                .unwrap();
+            });
+            (address, server)
+        };
+
+        let (address, server) = refuse("AlreadyRegistered");
+        assert_eq!(register_team(&address, "Team Test", None), Ok(None));
+        server.join().unwrap();
+
+        // A team name merely mentioning the error is not taken for it
+        let (address, server) = refuse("Team AlreadyRegistered is full");
+        assert_eq!(
+            register_team(&address, "Team Test", None),
+            Err(
+                ProtocolError::RegistrationFailed("Team AlreadyRegistered is full".to_string())
+                    .into()
+            )
+        );
+        server.join().unwrap();
     }
-    println!("All players have exited the labyrinth. Program completed.");
 
-    Ok(())
+    #[test]
+    fn test_messages_are_framed_with_a_little_endian_length() {
+        let log_dir = tempfile::tempdir().unwrap();
+        logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"]).unwrap();
+
+        let mut buffer: Vec<u8> = Vec::new();
+        send_message(&mut buffer, &Message::Action(request_models::Action::Noop)).unwrap();
+        let json = r#"{"Action":"Noop"}"#;
+        assert_eq!(&buffer[..4], &[17, 0, 0, 0]);
+        assert_eq!(&buffer[4..], json.as_bytes());
+
+        // Read back the way the server reads it
+        let received = receive_message(&mut std::io::Cursor::new(buffer)).unwrap();
+        assert_eq!(received, json);
+    }
+
+    #[test]
+    fn test_client_spawns_the_players_granted_by_the_server() {
+        let log_dir = tempfile::tempdir().unwrap();
+        logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"]).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // A server with teams of at most 3 players
+        let server = thread::spawn(move || {
+            let (mut team_stream, _) = listener.accept().unwrap();
+            let request: serde_json::Value =
+                serde_json::from_str(&receive_message(&mut team_stream).unwrap()).unwrap();
+            let requested = request["RegisterTeam"]["expected_players"]
+                .as_u64()
+                .unwrap();
+            assert_eq!(requested, 5);
+            send_message(
+                &mut team_stream,
+                &serde_json::json!({"RegisterTeamResult": {"Ok": {
+                    "expected_players": requested.min(3),
+                    "registration_token": "abc123"
+                }}}),
+            )
+            .unwrap();
+        });
+
+        let (token, granted) = register_team(&address, "Team Test", Some(5))
+            .unwrap()
+            .unwrap();
+        server.join().unwrap();
+        assert_eq!(token, "abc123");
+        assert_eq!(granted, 3);
+        assert_eq!(player_names(granted, None), ["Nino", "Paul", "Loriane"]);
+        assert_eq!(player_names(5, None)[3..], ["Player 4", "Player 5"]);
+    }
+
+    #[test]
+    fn test_named_players_must_fit_the_granted_team() {
+        let args: Vec<String> = ["worker", "127.0.0.1:8778", "--players", "Nino,Lea,Max"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let config = parse_args(&args).unwrap();
+        assert_eq!(
+            team_player_names(&config, 3).unwrap(),
+            ["Nino", "Lea", "Max"]
+        );
+        assert_eq!(
+            team_player_names(&config, 2),
+            Err(ProtocolError::RegistrationFailed(
+                "3 players named but the server granted 2".to_string()
+            )
+            .into())
+        );
+
+        // Without names, as many players as granted
+        let args = &args[..2];
+        let config = parse_args(args).unwrap();
+        assert_eq!(team_player_names(&config, 2).unwrap(), ["Nino", "Paul"]);
+    }
+
+    #[test]
+    fn test_generated_player_names_are_distinct() {
+        let names = generate_player_names(12, "Nino");
+        assert_eq!(names.len(), 12);
+        assert!(names.iter().all(|name| name.starts_with("Nino-")));
+        let distinct: std::collections::HashSet<&String> = names.iter().collect();
+        assert_eq!(distinct.len(), names.len());
+        assert_eq!(names[..2], ["Nino-1", "Nino-2"]);
+
+        assert_eq!(player_names(2, Some("Bot")), ["Bot-1", "Bot-2"]);
+        assert!(generate_player_names(0, "Nino").is_empty());
+    }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 /**
  * The Direction enum represents the different directions the player can face.
@@ -11,6 +12,49 @@ pub(crate) enum Direction {
     Right,
 }
 
+impl Direction {
+    /**
+     * The all function returns every direction, clockwise starting from the front.
+     *
+     * @return [Direction; 4] - Front, Right, Back and Left
+     */
+    pub(crate) fn all() -> [Direction; 4] {
+        [
+            Direction::Front,
+            Direction::Right,
+            Direction::Back,
+            Direction::Left,
+        ]
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    /**
+     * The from_str function parses a direction given on the command line.
+     * It is case-insensitive and accepts the full name or its first letter ("front" or "f").
+     *
+     * @param s: &str - The direction to parse
+     * @return Result<Direction, String> - The direction, or a message listing the accepted values
+     */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "front" | "f" => Ok(Direction::Front),
+            "back" | "b" => Ok(Direction::Back),
+            "left" | "l" => Ok(Direction::Left),
+            "right" | "r" => Ok(Direction::Right),
+            _ => Err(format!(
+                "unknown direction '{}', expected front/f, back/b, left/l or right/r",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The MapDirection enum represents the cardinal directions of the map.
+ */
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub(crate) enum MapDirection {
     North,
@@ -19,6 +63,86 @@ pub(crate) enum MapDirection {
     West,
 }
 
+impl MapDirection {
+    /**
+     * The all function returns every cardinal direction, clockwise starting from the north.
+     *
+     * @return [MapDirection; 4] - North, East, South and West
+     */
+    pub(crate) fn all() -> [MapDirection; 4] {
+        [
+            MapDirection::North,
+            MapDirection::East,
+            MapDirection::South,
+            MapDirection::West,
+        ]
+    }
+}
+
+/**
+ * The quarter_turns function counts the clockwise quarter turns a relative direction stands for,
+ * from 0 for Front to 3 for Left.
+ */
+fn quarter_turns(turn: &Direction) -> usize {
+    match turn {
+        Direction::Front => 0,
+        Direction::Right => 1,
+        Direction::Back => 2,
+        Direction::Left => 3,
+    }
+}
+
+/**
+ * The bearing function counts the clockwise quarter turns from the north to a cardinal direction.
+ */
+fn bearing(direction: MapDirection) -> usize {
+    match direction {
+        MapDirection::North => 0,
+        MapDirection::East => 1,
+        MapDirection::South => 2,
+        MapDirection::West => 3,
+    }
+}
+
+/**
+ * The to_relative function gives the move that takes a player to a cardinal direction,
+ * e.g. Left to go North while facing East.
+ *
+ * @param facing: MapDirection - The direction the player is facing
+ * @param target: MapDirection - The direction the player wants to go
+ * @return Direction - The move to send to the server
+ */
+pub(crate) fn to_relative(facing: MapDirection, target: MapDirection) -> Direction {
+    Direction::all()[(bearing(target) + 4 - bearing(facing)) % 4].clone()
+}
+
+/**
+ * The travel_direction function gives the cardinal direction a move takes the player to, the inverse of to_relative,
+ * e.g. West for a Back move while facing East.
+ *
+ * @param facing: MapDirection - The direction the player is facing
+ * @param turn: &Direction - The move sent to the server
+ * @return MapDirection - The direction the player goes to
+ */
+pub(crate) fn travel_direction(facing: MapDirection, turn: &Direction) -> MapDirection {
+    MapDirection::all()[(bearing(facing) + quarter_turns(turn)) % 4]
+}
+
+/**
+ * The apply_turn function gives the direction a player faces after a move: the way they moved,
+ * except after a Back move which keeps the facing, as the server does, e.g. East after moving Back while facing East.
+ *
+ * @param facing: MapDirection - The direction the player was facing
+ * @param turn: &Direction - The move sent to the server
+ * @return MapDirection - The direction the player faces after the move
+ */
+pub(crate) fn apply_turn(facing: MapDirection, turn: &Direction) -> MapDirection {
+    match turn {
+        Direction::Back => facing,
+        _ => travel_direction(facing, turn),
+    }
+}
+
 /**
  * The turn_right function turns the player to the right.
  *
@@ -115,4 +239,124 @@ mod tests {
         let deserialized: Direction = serde_json::from_str(&json).unwrap();
         assert_eq!(&deserialized, &direction);
     }
+
+    #[test]
+    fn test_all_directions_order() {
+        let directions = Direction::all();
+        assert_eq!(&directions[0], &Direction::Front);
+        assert_eq!(&directions[1], &Direction::Right);
+        assert_eq!(&directions[2], &Direction::Back);
+        assert_eq!(&directions[3], &Direction::Left);
+
+        assert_eq!(
+            MapDirection::all(),
+            [
+                MapDirection::North,
+                MapDirection::East,
+                MapDirection::South,
+                MapDirection::West
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_relative_for_every_facing_and_target() {
+        use MapDirection::{East, North, South, West};
+        let expected = [
+            (
+                North,
+                [
+                    Direction::Front,
+                    Direction::Right,
+                    Direction::Back,
+                    Direction::Left,
+                ],
+            ),
+            (
+                East,
+                [
+                    Direction::Left,
+                    Direction::Front,
+                    Direction::Right,
+                    Direction::Back,
+                ],
+            ),
+            (
+                South,
+                [
+                    Direction::Back,
+                    Direction::Left,
+                    Direction::Front,
+                    Direction::Right,
+                ],
+            ),
+            (
+                West,
+                [
+                    Direction::Right,
+                    Direction::Back,
+                    Direction::Left,
+                    Direction::Front,
+                ],
+            ),
+        ];
+        for (facing, moves) in expected {
+            for (target, turn) in MapDirection::all().into_iter().zip(moves) {
+                assert_eq!(
+                    &to_relative(facing, target),
+                    &turn,
+                    "{:?} to {:?}",
+                    facing,
+                    target
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_turn_for_every_facing_and_turn() {
+        use MapDirection::{East, North, South, West};
+        // Turns in the order of Direction::all(): Front, Right, Back, Left
+        // A Back move keeps the facing
+        let expected = [
+            (North, [North, East, North, West]),
+            (East, [East, South, East, North]),
+            (South, [South, West, South, East]),
+            (West, [West, North, West, South]),
+        ];
+        for (facing, faced) in expected {
+            for (turn, after) in Direction::all().iter().zip(faced) {
+                assert_eq!(
+                    apply_turn(facing, turn),
+                    after,
+                    "{:?} then {:?}",
+                    facing,
+                    turn
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_travel_direction_undoes_to_relative() {
+        for facing in MapDirection::all() {
+            for target in MapDirection::all() {
+                assert_eq!(
+                    travel_direction(facing, &to_relative(facing, target)),
+                    target
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_direction_from_str() {
+        assert_eq!(&"front".parse::<Direction>().unwrap(), &Direction::Front);
+        assert_eq!(&"B".parse::<Direction>().unwrap(), &Direction::Back);
+        assert_eq!(&"Left".parse::<Direction>().unwrap(), &Direction::Left);
+        assert_eq!(&"r".parse::<Direction>().unwrap(), &Direction::Right);
+
+        let error = "up".parse::<Direction>().unwrap_err();
+        assert!(error.contains("unknown direction 'up'"));
+    }
 }
@@ -1,18 +1,24 @@
+use crate::config::{ClientConfig, Strategy};
 use crate::decoder::decode;
 use crate::logger::log_message;
-use crate::models::{turn_left, Direction, MapDirection};
-use crate::request_models::{Action, Answer, Message, SubscribePlayer};
+use crate::models::{
+    apply_turn, to_relative, travel_direction, turn_left, Direction, MapDirection,
+};
+use crate::request_models::{Action, Answer, Message, ServerMessage, ShareSecret, SubscribePlayer};
 use crate::SECRET_MAP;
 use log::{debug, error, info, warn};
 use serde_json::json;
 use std::cmp::PartialEq;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
 use std::fmt::Debug;
-use std::io::{Read, Write};
+use std::io::{BufRead, Read, Write};
 use std::net::TcpStream;
 use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
-use SQP_common::error::{Error, NetworkError, PlayerError};
+use SQP_common::error::{DecodeError, Error, NetworkError, PlayerError};
+use SQP_common::radar::{render_radar, render_radar_colored};
 use SQP_common::server_utils::{receive_message, send_message};
 
 /**
@@ -46,12 +52,14 @@ enum Item {
     None,
     Hint,
     Goal,
+    // Cell the player already stood on, sent by servers running with --mark-visited
+    Visited,
 }
 
 /**
  * The RadarCell struct represents a cell in the radar view.
  * It contains an item and an entity.
- * The item represents the type of item in the cell (None, Hint, Goal).
+ * The item represents the type of item in the cell (None, Hint, Goal, Visited).
  * The entity represents the type of entity in the cell (None, Ally, Enemy, Monster).
  */
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
@@ -70,6 +78,29 @@ pub(crate) struct Coordinates {
     position_y: usize,
 }
 
+impl Coordinates {
+    /**
+     * The neighbor function gives the coordinates next to these ones in a direction.
+     * position_x is the row (growing to the south) and position_y the column (growing to the east).
+     *
+     * @param direction: MapDirection - The side to look at
+     * @return Option<Coordinates> - The adjacent coordinates, or None past the north or west edge
+     */
+    pub(crate) fn neighbor(&self, direction: MapDirection) -> Option<Coordinates> {
+        let (row, column) = (self.position_x, self.position_y);
+        let (row, column) = match direction {
+            MapDirection::North => (row.checked_sub(1)?, column),
+            MapDirection::East => (row, column + 1),
+            MapDirection::South => (row + 1, column),
+            MapDirection::West => (row, column.checked_sub(1)?),
+        };
+        Some(Coordinates {
+            position_x: row,
+            position_y: column,
+        })
+    }
+}
+
 /**
  * The MapCell struct represents a cell in the map.
  * It contains all possibilites for all its 4 boundaries.
@@ -84,6 +115,63 @@ pub(crate) struct MapCell {
     is_player_here: bool,
 }
 
+impl MapCell {
+    /**
+     * The boundary function returns the boundary of the cell on the given side.
+     *
+     * @param direction: &MapDirection - The side of the cell
+     * @return &Boundary - The boundary on that side
+     */
+    fn boundary(&self, direction: &MapDirection) -> &Boundary {
+        match direction {
+            MapDirection::North => &self.north,
+            MapDirection::East => &self.east,
+            MapDirection::South => &self.south,
+            MapDirection::West => &self.west,
+        }
+    }
+
+    /**
+     * The boundary_mut function returns the boundary of the cell on the given side, to change it.
+     *
+     * @param direction: &MapDirection - The side of the cell
+     * @return &mut Boundary - The boundary on that side
+     */
+    fn boundary_mut(&mut self, direction: &MapDirection) -> &mut Boundary {
+        match direction {
+            MapDirection::North => &mut self.north,
+            MapDirection::East => &mut self.east,
+            MapDirection::South => &mut self.south,
+            MapDirection::West => &mut self.west,
+        }
+    }
+
+    /**
+     * The open_count function counts the boundaries of the cell a player can go through.
+     *
+     * @return usize - The number of open or checked boundaries
+     */
+    fn open_count(&self) -> usize {
+        MapDirection::all()
+            .iter()
+            .filter(|direction| {
+                matches!(self.boundary(direction), Boundary::Open | Boundary::Checked)
+            })
+            .count()
+    }
+
+    /**
+     * The is_fully_explored function tells if all four boundaries of the cell are known.
+     *
+     * @return bool - True if no boundary is undefined
+     */
+    fn is_fully_explored(&self) -> bool {
+        MapDirection::all()
+            .iter()
+            .all(|direction| self.boundary(direction) != &Boundary::Undefined)
+    }
+}
+
 /**
  * The NextDirection struct the next Direction the player needs to go and the number of steps he is going to need.
  */
@@ -93,21 +181,158 @@ pub(crate) struct NextDirection {
     steps: u64,
 }
 
+/**
+ * The EndReason enum tells why a player stopped searching for the exit.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EndReason {
+    FoundExit,
+    /// The server closed the connection or stopped answering the player's actions
+    ConnectionLost(String),
+    /// The player played by hand was told to quit, or the input ended
+    Quit,
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EndReason::FoundExit => write!(f, "found the exit"),
+            EndReason::ConnectionLost(reason) => write!(f, "lost the connection ({})", reason),
+            EndReason::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+/**
+ * The SolveResult struct reports how the game of a player ended, printed by main for each player.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SolveResult {
+    pub(crate) found_exit: bool,
+    /// Number of MoveTo actions sent
+    pub(crate) moves: u64,
+    pub(crate) reason: EndReason,
+}
+
+impl SolveResult {
+    /**
+     * The end function builds the result of a game ending for the given reason.
+     *
+     * @param moves: u64 - The number of moves sent
+     * @param reason: EndReason - Why the game ended
+     * @return SolveResult - The result, found_exit matching the reason
+     */
+    fn end(moves: u64, reason: EndReason) -> SolveResult {
+        SolveResult {
+            found_exit: reason == EndReason::FoundExit,
+            moves,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for SolveResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} after {} moves", self.reason, self.moves)
+    }
+}
+
+/**
+ * The receive_action_response function reads the server's response to an action.
+ * A connection the server closed or dropped ends the game instead of failing it.
+ *
+ * @param player_stream: &mut impl Read - The connection of the player
+ * @return Result<Result<String, EndReason>, Error> - The response, or ConnectionLost
+ */
+fn receive_action_response(
+    player_stream: &mut impl Read,
+) -> Result<Result<String, EndReason>, Error> {
+    match receive_game_message(player_stream) {
+        Ok(response) => Ok(Ok(response)),
+        Err(Error::Network(
+            e @ (NetworkError::ConnectionClosed | NetworkError::ReadLengthFailed(_)),
+        )) => Ok(Err(EndReason::ConnectionLost(e.to_string()))),
+        Err(e) => Err(PlayerError::RadarResponseFailed(e.to_string()).into()),
+    }
+}
+
+/**
+ * The number of consecutive wall hits in the same direction after which the player is forced to turn.
+ */
+const WALL_HIT_THRESHOLD: u32 = 3;
+
+/**
+ * The WallHitTracker struct counts the consecutive wall hits in the same direction,
+ * so a player whose radar keeps showing an open passage does not spin against the wall.
+ */
+#[derive(Debug)]
+pub(crate) struct WallHitTracker {
+    direction: Option<Direction>,
+    hits: u32,
+    threshold: u32,
+}
+
+impl WallHitTracker {
+    /**
+     * The new function creates a tracker forcing a turn after the given number of hits.
+     *
+     * @param threshold: u32 - The number of consecutive hits before turning
+     * @return WallHitTracker - The new tracker
+     */
+    pub(crate) fn new(threshold: u32) -> WallHitTracker {
+        WallHitTracker {
+            direction: None,
+            hits: 0,
+            threshold,
+        }
+    }
+
+    /**
+     * The record_hit function counts a wall hit in the given direction.
+     * A hit in another direction starts a new count.
+     *
+     * @param direction: &Direction - The direction the player tried to move to
+     * @return Option<Direction> - The direction to force once the threshold is reached
+     */
+    pub(crate) fn record_hit(&mut self, direction: &Direction) -> Option<Direction> {
+        if self.direction.as_ref() == Some(direction) {
+            self.hits += 1;
+        } else {
+            self.direction = Some(direction.clone());
+            self.hits = 1;
+        }
+
+        if self.hits < self.threshold {
+            return None;
+        }
+        self.reset();
+        Some(turn_left(direction))
+    }
+
+    /**
+     * The reset function forgets the hits, after a successful move.
+     */
+    pub(crate) fn reset(&mut self) {
+        self.direction = None;
+        self.hits = 0;
+    }
+}
+
 /**
  * The player_thread function represents the main logic for each player thread.
  * It subscribes the player to the server, then enters a loop to solve the labyrinth.
  *
  * @param player_name: String - The name of the player
  * @param registration_token: String - The registration token for the player
- * @param server_address: String - The address of the server
+ * @param config: ClientConfig - The client options (server address, solver, display)
+ * @return Result<SolveResult, Error> - How the game of the player ended
  */
 pub(crate) fn start_player_thread(
     player_name: String,
     registration_token: String,
-    server_address: String,
-    use_smart_mode: bool,
-) -> Result<(), Error> {
-    let mut player_stream = TcpStream::connect(server_address)
+    config: ClientConfig,
+) -> Result<SolveResult, Error> {
+    let mut player_stream = TcpStream::connect(&config.server_address)
         .map_err(|e| NetworkError::ConnectionFailed(e.to_string()))?;
     println!("Connected for player: {}", player_name);
 
@@ -120,31 +345,80 @@ pub(crate) fn start_player_thread(
         .map_err(|e| PlayerError::SubscriptionFailed(e.to_string()))?;
     println!("Subscribed player: {}", player_name);
 
-    let response = receive_message(&mut player_stream)
-        .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
-    if !response.contains("Ok") {
-        return Err(PlayerError::SubscriptionFailed(response).into());
+    let response = receive_subscription(&player_name, &mut player_stream)?;
+
+    match config.strategy {
+        Strategy::Simple => search_for_exit(player_name, player_stream, response, &config),
+        Strategy::Smart | Strategy::Explore => {
+            search_for_exit_smart(player_name, player_stream, response, &config)
+        }
+        Strategy::Hand => play_by_hand(
+            player_name,
+            player_stream,
+            response,
+            std::io::stdin().lock(),
+            &config,
+        ),
     }
-    println!("Server response for player {}: {}", player_name, response);
+}
 
-    // get the next response from the server that contains the radar view
-    let response = receive_message(&mut player_stream)
-        .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
-    println!(
-        "Player {} received radar response: {}",
-        player_name, response
-    );
+/**
+ * The receive_subscription function reads the subscription result and the first radar view,
+ * in whichever order the server sends them.
+ * A refused subscription ends the player as soon as it is read, even after a radar view.
+ *
+ * @param player_name: &str - The name of the player
+ * @param player_stream: &mut impl Read - The connection of the player
+ * @return Result<String, Error> - The first radar view response
+ */
+fn receive_subscription(player_name: &str, player_stream: &mut impl Read) -> Result<String, Error> {
+    let mut subscribed = false;
+    let mut radar_response = None;
 
-    if use_smart_mode {
-        search_for_exit_smart(player_name, player_stream, response)?;
-    } else {
-        search_for_exit(player_name, player_stream, response)?;
+    while !subscribed || radar_response.is_none() {
+        let response = receive_game_message(player_stream)
+            .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+        println!("Server response for player {}: {}", player_name, response);
+
+        let json_val: serde_json::Value = serde_json::from_str(&response)
+            .map_err(|e| PlayerError::SubscriptionFailed(format!("Invalid JSON: {}", e)))?;
+        if json_val.get("SubscribePlayerResult").is_some() {
+            parse_subscription_result(&response)?;
+            subscribed = true;
+        } else if json_val.get("RadarView").is_some() {
+            radar_response = Some(response);
+        } else {
+            warn!(
+                "Player {} ignored a message before the game started: {}",
+                player_name, response
+            );
+        }
     }
 
-    // fixme remove, only for testing
-    // choose_direction_by_hand(player_name, player_stream);
+    Ok(radar_response.unwrap_or_default())
+}
 
-    Ok(())
+/**
+ * The parse_subscription_result function reads the server's answer to SubscribePlayer.
+ *
+ * @param response: &str - The JSON response, `{"SubscribePlayerResult":"Ok"}` or
+ * `{"SubscribePlayerResult":{"Error":"<reason>"}}`
+ * @return Result<(), Error> - SubscriptionFailed with the server's reason if the subscription was refused
+ */
+fn parse_subscription_result(response: &str) -> Result<(), Error> {
+    let json_val: serde_json::Value = serde_json::from_str(response)
+        .map_err(|e| PlayerError::SubscriptionFailed(format!("Invalid JSON: {}", e)))?;
+
+    let result = &json_val["SubscribePlayerResult"];
+    if result == "Ok" {
+        return Ok(());
+    }
+    let reason = match &result["Error"] {
+        serde_json::Value::String(reason) => reason.clone(),
+        serde_json::Value::Null => response.to_string(),
+        error => error.to_string(),
+    };
+    Err(PlayerError::SubscriptionFailed(reason).into())
 }
 
 /**
@@ -152,26 +426,61 @@ pub(crate) fn start_player_thread(
  * It receives the initial radar response and enters a loop to explore the labyrinth and find the exit.
  *
  * @param player_name: String - The name of the player
- * @param player_stream: TcpStream - The TCP stream for the player
+ * @param player_stream: impl Read + Write - The connection of the player, a TcpStream or a scripted stream in tests
  * @param initial_radar_response: String - The initial radar response from the server
+ * @param config: &ClientConfig - The client options
+ * @return Result<SolveResult, Error> - How the game ended and the number of moves sent
  */
 fn search_for_exit(
     player_name: String,
-    mut player_stream: TcpStream,
+    mut player_stream: impl Read + Write,
     initial_radar_response: String,
-) -> Result<(), Error> {
+    config: &ClientConfig,
+) -> Result<SolveResult, Error> {
     // Parse the radar to get the initial state of the labyrinth
-    let (mut _cells, mut horizontal_passages, mut vertical_passages) =
-        parse_radar_response(&initial_radar_response);
-    // Initial player direction
-    let mut current_direction = Direction::Right; // always try to go right first
+    let mut radar_cache = RadarCache::default();
+    let (mut cells, mut horizontal_passages, mut vertical_passages) =
+        radar_cache.parse(&initial_radar_response, config.color)?;
+    print_radar_legend(config);
+    let mut facing = MapDirection::North;
+    let mut moves: u64 = 0;
+    let mut wall_hits = WallHitTracker::new(WALL_HIT_THRESHOLD);
+    let mut forced_direction: Option<Direction> = None;
+    // The server keeps the facing of a player moving back, who goes on walking backward
+    let mut walking_back = false;
+    // Detours to visible hints stop once one is collected, until the hints are out of sight,
+    // so the player does not walk back and forth over the same hint
+    let mut seek_hints = true;
+
+    // The discovered map is only built when the mini-map has to be shown
+    let mut minimap = config
+        .minimap_interval
+        .map(|_| make_map_with_passages(&horizontal_passages, &vertical_passages));
 
     // main loop for player movement
     loop {
-        // check if the player can go right else try front then left then back
-        while !is_direction_open(&current_direction, &horizontal_passages, &vertical_passages) {
-            current_direction = turn_left(&current_direction);
+        // follow the right-hand rule (or step onto a visible exit with --seek-goal),
+        // unless it kept hitting the same wall and has to turn
+        let mut decision = decide_simple_direction(
+            &cells,
+            &horizontal_passages,
+            &vertical_passages,
+            config.seek_goal,
+            walking_back,
+        );
+        if let Some(direction) = forced_direction.take() {
+            decision.chosen = Some(direction);
+            decision.reason = DecisionReason::ForcedTurn;
+        } else if seek_hints && !matches!(decision.reason, DecisionReason::ExitInSight) {
+            if let Some(direction) =
+                hint_direction(&cells, &horizontal_passages, &vertical_passages)
+            {
+                decision.chosen = Some(direction);
+                decision.reason = DecisionReason::HintDetour;
+            }
         }
+        explain_decision(&player_name, &decision, config.explain)?;
+        let current_direction = decision.chosen.ok_or(PlayerError::InvalidRadarData)?;
         // Send the current movement action
         let action_message = Message::Action(Action::MoveTo(current_direction.clone()));
 
@@ -181,37 +490,60 @@ fn search_for_exit(
             "Player {} sent action: {:?}",
             player_name, current_direction
         );
+        moves += 1;
 
         // Receive the server's response to the action
-        let mut action_response = receive_message(&mut player_stream)
-            .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+        let mut action_response = match receive_action_response(&mut player_stream)? {
+            Ok(response) => response,
+            Err(reason) => return Ok(SolveResult::end(moves, reason)),
+        };
         println!(
             "Player {} received response: {}",
             player_name, action_response
         );
 
-        if action_response.contains("Hint") {
-            println!("Player {} found a hint!", player_name);
-            handle_hint(&player_name, &action_response)?;
+        let mut server_message = ServerMessage::parse(&action_response);
 
-            // get next message from server to get the radar view
-            action_response = receive_message(&mut player_stream)
-                .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+        // The server follows a wall hit with the radar view of the cell the player stayed in
+        let hit_wall = server_message == ServerMessage::CannotPassThroughWall;
+        if hit_wall {
+            action_response = match receive_action_response(&mut player_stream)? {
+                Ok(response) => response,
+                Err(reason) => return Ok(SolveResult::end(moves, reason)),
+            };
             println!(
                 "Player {} received response: {}",
                 player_name, action_response
             );
+            server_message = ServerMessage::parse(&action_response);
+        }
+
+        if server_message.hint().is_some() {
+            println!("Player {} found a hint!", player_name);
+            handle_hint(&player_name, &mut player_stream, &action_response)?;
+
+            // get next message from server to get the radar view, unless it came with the hint
+            if !matches!(server_message, ServerMessage::RadarView { .. }) {
+                action_response = receive_game_message(&mut player_stream)
+                    .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+                println!(
+                    "Player {} received response: {}",
+                    player_name, action_response
+                );
+                server_message = ServerMessage::parse(&action_response);
+            }
         }
 
-        if action_response.contains("Challenge") {
+        if let ServerMessage::Challenge(_) = server_message {
             println!("Player {} found a challenge!", player_name);
             // cannot move until challenge is solved
             resolve_challenge(&player_name, &mut player_stream, &action_response)?;
 
             // get next message from server to get the radar view
-            action_response = receive_message(&mut player_stream)
+            action_response = receive_game_message(&mut player_stream)
                 .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
-            if action_response.contains("RadarView") {
+            server_message = ServerMessage::parse(&action_response);
+            if let ServerMessage::RadarView { .. } = server_message {
                 // Log the challenge solution in projectRoot/log/challenge.log
                 log_message(
                     "challenge",
@@ -225,28 +557,356 @@ fn search_for_exit(
             .map_err(|e| PlayerError::ActionFailed(e.to_string()))?;
 
         // Check for exit condition
-        if action_response.contains("FoundExit") {
+        if server_message == ServerMessage::FoundExit {
             println!("Player {} found the exit!", player_name);
             // terminate the player thread
-            return Ok(());
+            return Ok(SolveResult::end(moves, EndReason::FoundExit));
         }
 
-        // parse and update cells, horizontal and vertical passages
-        (_cells, horizontal_passages, vertical_passages) = parse_radar_response(&action_response);
-        current_direction = Direction::Right; // Reset the direction to right
-
-        // timeout 1/100 of a second
-        thread::sleep(Duration::from_millis(10));
+        // parse and update cells, horizontal and vertical passages,
+        // a malformed radar view is skipped and the last one kept
+        let radar_skipped = match radar_cache.parse(&action_response, config.color) {
+            Ok(parsed) => {
+                (cells, horizontal_passages, vertical_passages) = parsed;
+                false
+            }
+            Err(e) => {
+                error!("Player {} skipped a radar view: {}", player_name, e);
+                true
+            }
+        };
 
         // Check if movement was blocked
-        if action_response.contains("CannotPassThroughWall") {
-            // throw error
+        if hit_wall {
+            // the passage we bumped into is a wall, whatever the radar view shows
+            forced_direction = record_wall_hit(
+                &current_direction,
+                &mut horizontal_passages,
+                &mut vertical_passages,
+                &mut wall_hits,
+            );
+            if let Some(direction) = &forced_direction {
+                log_message(
+                    "player",
+                    &format!(
+                        "Player {} kept hitting a wall to the {:?}, forcing a turn to {:?}\n",
+                        player_name, current_direction, direction
+                    ),
+                )?;
+            }
             eprintln!(
-                "Player {} hit a wall, turning to {:?}",
+                "Player {} hit a wall to the {:?}",
                 player_name, current_direction
             );
+            thread::sleep(Duration::from_millis(10));
+            continue;
+        }
+        wall_hits.reset();
+        // the move went through, the player faces its new direction
+        let travel = travel_direction(facing, &current_direction);
+        facing = apply_turn(facing, &current_direction);
+        walking_back = matches!(current_direction, Direction::Back);
+        if radar_skipped {
+            continue;
+        }
+        print_radar_legend(config);
+        if cells.get(4).is_some_and(|cell| cell.item == Item::Hint) {
+            seek_hints = false;
+        } else if !cells.iter().any(|cell| cell.item == Item::Hint) {
+            seek_hints = true;
+        }
+
+        if let Some(map) = minimap.as_mut() {
+            if !horizontal_passages.is_empty() {
+                let discovered = rotate_map(
+                    make_map_with_passages(&horizontal_passages, &vertical_passages),
+                    facing,
+                );
+                update_map(map, discovered, travel);
+            }
+            if should_render_minimap(config.minimap_interval, moves) {
+                print!("{}", get_minimap_as_string(map, &facing));
+            }
+        }
+
+        // timeout 1/100 of a second
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/**
+ * The Decision struct records why the simple strategy picked a direction.
+ * It is written to the decision log every move, and printed with --explain.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct Decision {
+    /// The directions with an open passage, clockwise from the front
+    pub(crate) open: Vec<Direction>,
+    /// The score of each open direction, the lowest one is picked
+    pub(crate) scores: Vec<(Direction, u32)>,
+    pub(crate) chosen: Option<Direction>,
+    pub(crate) reason: DecisionReason,
+}
+
+/**
+ * The DecisionReason enum tells why a direction was chosen, written in words by --explain.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DecisionReason {
+    /// The move leads toward the exit seen in the radar view (--seek-goal)
+    ExitInSight,
+    RightHandRule,
+    /// No passage is open, nothing is chosen
+    AllClosed,
+    /// The player kept hitting the same wall and has to turn
+    ForcedTurn,
+    /// The move leads toward a hint seen in the radar view
+    HintDetour,
+}
+
+impl fmt::Display for DecisionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecisionReason::ExitInSight => write!(f, "exit in sight"),
+            DecisionReason::RightHandRule => write!(f, "right-hand rule"),
+            DecisionReason::AllClosed => write!(f, "every passage is closed"),
+            DecisionReason::ForcedTurn => write!(f, "forced turn after repeated wall hits"),
+            DecisionReason::HintDetour => write!(f, "detour to a visible hint"),
+        }
+    }
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scores: Vec<String> = self
+            .scores
+            .iter()
+            .map(|(direction, score)| format!("{:?}={}", direction, score))
+            .collect();
+        write!(
+            f,
+            "open {:?}, scores [{}], chose {:?} ({})",
+            self.open,
+            scores.join(", "),
+            self.chosen,
+            self.reason
+        )
+    }
+}
+
+/**
+ * The goal_direction function finds the move toward the exit when it is in the radar view,
+ * through open passages only (see item_direction).
+ *
+ * @param cells: &[RadarCell] - The cells of the radar view, the front being the top row
+ * @param h_passages: &[Boundary] - The horizontal passages of the radar view
+ * @param v_passages: &[Boundary] - The vertical passages of the radar view
+ * @return Option<Direction> - The move toward the exit, None if it cannot be reached in one or two moves
+ */
+fn goal_direction(
+    cells: &[RadarCell],
+    h_passages: &[Boundary],
+    v_passages: &[Boundary],
+) -> Option<Direction> {
+    item_direction(cells, h_passages, v_passages, &Item::Goal)
+}
+
+/**
+ * The decide_simple_direction function chooses the next move of the simple strategy.
+ * The right-hand rule scores right 0, front 1, left 2 and back 3, seen from the way the player walks:
+ * the server keeps the facing of a player moving back, so after a Back move the rule is turned around.
+ * With seek_goal, an open passage leading toward the exit in the radar view (see goal_direction)
+ * scores 0 and the others one more.
+ *
+ * @param cells: &[RadarCell] - The cells of the last radar view, the front being the top row
+ * @param h_passages: &[Boundary] - The horizontal passages of the last radar view
+ * @param v_passages: &[Boundary] - The vertical passages of the last radar view
+ * @param seek_goal: bool - Whether to step onto a visible exit
+ * @param walking_back: bool - Whether the last move was Back, the player walking away from its facing
+ * @return Decision - The open directions, their scores and the chosen one (None if every passage is closed)
+ */
+fn decide_simple_direction(
+    cells: &[RadarCell],
+    h_passages: &[Boundary],
+    v_passages: &[Boundary],
+    seek_goal: bool,
+    walking_back: bool,
+) -> Decision {
+    let goal = goal_direction(cells, h_passages, v_passages).filter(|_| seek_goal);
+    let goal_visible = |direction: &Direction| goal.as_ref() == Some(direction);
+    let right_hand_rank = |direction: &Direction| match if walking_back {
+        turn_left(&turn_left(direction))
+    } else {
+        direction.clone()
+    } {
+        Direction::Right => 0,
+        Direction::Front => 1,
+        Direction::Left => 2,
+        Direction::Back => 3,
+    };
+
+    let open: Vec<Direction> = Direction::all()
+        .into_iter()
+        .filter(|direction| is_direction_open(direction, h_passages, v_passages))
+        .collect();
+    let scores: Vec<(Direction, u32)> = open
+        .iter()
+        .map(|direction| {
+            let score = match (goal_visible(direction), seek_goal) {
+                (true, _) => 0,
+                (false, true) => right_hand_rank(direction) + 1,
+                (false, false) => right_hand_rank(direction),
+            };
+            (direction.clone(), score)
+        })
+        .collect();
+    let chosen = scores
+        .iter()
+        .min_by_key(|(_, score)| *score)
+        .map(|(direction, _)| direction.clone());
+    let reason = match &chosen {
+        Some(direction) if goal_visible(direction) => DecisionReason::ExitInSight,
+        Some(_) => DecisionReason::RightHandRule,
+        None => DecisionReason::AllClosed,
+    };
+
+    Decision {
+        open,
+        scores,
+        chosen,
+        reason,
+    }
+}
+
+/**
+ * The hint_direction function finds the first move of the shortest path to a hint
+ * visible in the radar view (see item_direction).
+ *
+ * @param cells: &[RadarCell] - The cells of the last radar view, the front being the top row
+ * @param h_passages: &[Boundary] - The horizontal passages of the last radar view
+ * @param v_passages: &[Boundary] - The vertical passages of the last radar view
+ * @return Option<Direction> - The move toward the closest hint, None if no hint can be reached
+ */
+fn hint_direction(
+    cells: &[RadarCell],
+    h_passages: &[Boundary],
+    v_passages: &[Boundary],
+) -> Option<Direction> {
+    item_direction(cells, h_passages, v_passages, &Item::Hint)
+}
+
+/**
+ * The item_direction function finds the first move of the shortest path to an item
+ * visible in the radar view. An item next to the player is one move away; an item in a
+ * corner of the radar is two moves away, through the cell in front of or behind the player
+ * or through the cell on its side, when both passages on the way are open.
+ *
+ * @param cells: &[RadarCell] - The cells of the last radar view, the front being the top row
+ * @param h_passages: &[Boundary] - The horizontal passages of the last radar view
+ * @param v_passages: &[Boundary] - The vertical passages of the last radar view
+ * @param item: &Item - The item to reach
+ * @return Option<Direction> - The move toward the closest such item, None if none can be reached
+ */
+fn item_direction(
+    cells: &[RadarCell],
+    h_passages: &[Boundary],
+    v_passages: &[Boundary],
+    item: &Item,
+) -> Option<Direction> {
+    let is_item = |index: usize| cells.get(index).is_some_and(|cell| &cell.item == item);
+    let is_open =
+        |passages: &[Boundary], index: usize| matches!(passages.get(index), Some(Boundary::Open));
+    // Cell of the radar next to the center (4) in each direction
+    let next_cell = |direction: &Direction| match direction {
+        Direction::Front => 1,
+        Direction::Right => 5,
+        Direction::Back => 7,
+        Direction::Left => 3,
+    };
+
+    let open: Vec<Direction> = Direction::all()
+        .into_iter()
+        .filter(|direction| {
+            cells.len() == 9
+                && match direction {
+                    Direction::Front | Direction::Back => {
+                        is_open(h_passages, passage_index(direction))
+                    }
+                    Direction::Left | Direction::Right => {
+                        is_open(v_passages, passage_index(direction))
+                    }
+                }
+        })
+        .collect();
+    if let Some(direction) = open.iter().find(|direction| is_item(next_cell(direction))) {
+        return Some(direction.clone());
+    }
+
+    // From the cell in front (1) or behind (7), a corner is through a vertical passage;
+    // from the cell on a side (3 or 5), through a horizontal one
+    open.into_iter().find(|direction| {
+        let cell = next_cell(direction);
+        let (row, column) = (cell / 3, cell % 3);
+        match direction {
+            Direction::Front | Direction::Back => {
+                (is_item(cell - 1) && is_open(v_passages, row * 4 + column))
+                    || (is_item(cell + 1) && is_open(v_passages, row * 4 + column + 1))
+            }
+            Direction::Left | Direction::Right => {
+                (is_item(cell - 3) && is_open(h_passages, row * 3 + column))
+                    || (is_item(cell + 3) && is_open(h_passages, (row + 1) * 3 + column))
+            }
         }
+    })
+}
+
+/**
+ * The explain_decision function writes a decision to the decision log,
+ * and to the standard output with --explain.
+ *
+ * @param player_name: &str - The name of the player
+ * @param decision: &Decision - The decision taken for this move
+ * @param explain: bool - Whether to print the decision too
+ * @return Result<String, Error> - The line written, or an error if the log could not be written
+ */
+fn explain_decision(
+    player_name: &str,
+    decision: &Decision,
+    explain: bool,
+) -> Result<String, Error> {
+    let line = format!("Player {} {}", player_name, decision);
+    log_message("decision", &line)?;
+    if explain {
+        println!("{}", line);
+    }
+    Ok(line)
+}
+
+/**
+ * The record_wall_hit function handles a CannotPassThroughWall response.
+ * The passage the player bumped into is marked as a wall and the hit is counted.
+ *
+ * @param direction: &Direction - The direction the player tried to move to
+ * @param h_passages: &mut [Boundary] - The horizontal passages of the last radar view
+ * @param v_passages: &mut [Boundary] - The vertical passages of the last radar view
+ * @param wall_hits: &mut WallHitTracker - The consecutive wall hits so far
+ * @return Option<Direction> - The direction to force once the player is considered stuck
+ */
+fn record_wall_hit(
+    direction: &Direction,
+    h_passages: &mut [Boundary],
+    v_passages: &mut [Boundary],
+    wall_hits: &mut WallHitTracker,
+) -> Option<Direction> {
+    let passages = match direction {
+        Direction::Front | Direction::Back => h_passages,
+        Direction::Left | Direction::Right => v_passages,
+    };
+    if let Some(passage) = passages.get_mut(passage_index(direction)) {
+        *passage = Boundary::Wall;
     }
+
+    wall_hits.record_hit(direction)
 }
 
 /**
@@ -254,22 +914,56 @@ fn search_for_exit(
  * It receives the initial radar response and enters a loop to explore the labyrinth and find the exit.
  *
  * @param player_name: String - The name of the player
- * @param player_stream: TcpStream - The TCP stream for the player
+ * @param player_stream: impl Read + Write - The connection of the player, a TcpStream or a scripted stream in tests
  * @param initial_radar_response: String - The initial radar response from the server
+ * @param config: &ClientConfig - The client options
+ * @return Result<SolveResult, Error> - How the game ended and the number of moves sent
  */
 fn search_for_exit_smart(
     player_name: String,
-    mut player_stream: TcpStream,
+    mut player_stream: impl Read + Write,
     initial_radar_response: String,
-) -> Result<(), Error> {
+    config: &ClientConfig,
+) -> Result<SolveResult, Error> {
     // Parse the radar to get the initial state of the labyrinth
-    let mut map = parse_radar_response_smart(&initial_radar_response);
+    let mut radar_cache = RadarCache::default();
+    let parsed = radar_cache.parse(&initial_radar_response, config.color)?;
+    let mut map = radar_map(&parsed);
+    print_radar_legend(config);
+    let mut goal = goal_direction(&parsed.0, &parsed.1, &parsed.2);
     // Initial player direction
     let mut north_at: MapDirection = MapDirection::North;
+    let mut moves: u64 = 0;
 
     // main loop for player movement
     loop {
-        let next_direction = find_closest_open(&mut map, Vec::new(), 0);
+        // head for the exit as soon as the radar shows it, when the passage that way is open
+        let goal_heading = goal
+            .take()
+            .map(|direction| travel_direction(north_at, &direction))
+            .filter(|direction| {
+                map.iter().flatten().any(|cell| {
+                    cell.is_player_here
+                        && matches!(cell.boundary(direction), Boundary::Open | Boundary::Checked)
+                })
+            });
+        let explore_direction = goal_heading.or_else(|| match config.strategy {
+            Strategy::Explore => choose_explore_direction(&map),
+            _ => None,
+        });
+        let next_direction = match explore_direction {
+            Some(direction) => NextDirection {
+                direction,
+                steps: 1,
+            },
+            None => match shortest_path_to_frontier(&map) {
+                Some(path) => NextDirection {
+                    direction: path[0],
+                    steps: path.len() as u64,
+                },
+                None => find_closest_open(&mut map, Vec::new(), 0),
+            },
+        };
         println!(
             "Next direction: {:?} with {} steps",
             next_direction.direction, next_direction.steps
@@ -281,52 +975,12 @@ fn search_for_exit_smart(
         //     }
         //     // Send the current movement action
 
-        let mut current_direction = Direction::Front;
-        if north_at == MapDirection::North {
-            if next_direction.direction == MapDirection::North {
-                current_direction = Direction::Front;
-            } else if next_direction.direction == MapDirection::East {
-                current_direction = Direction::Right;
-            } else if next_direction.direction == MapDirection::South {
-                current_direction = Direction::Back;
-            } else if next_direction.direction == MapDirection::West {
-                current_direction = Direction::Left;
-            }
-        } else if north_at == MapDirection::East {
-            if next_direction.direction == MapDirection::North {
-                current_direction = Direction::Left;
-            } else if next_direction.direction == MapDirection::East {
-                current_direction = Direction::Front;
-            } else if next_direction.direction == MapDirection::South {
-                current_direction = Direction::Right;
-            } else if next_direction.direction == MapDirection::West {
-                current_direction = Direction::Back;
-            }
-        } else if north_at == MapDirection::South {
-            if next_direction.direction == MapDirection::North {
-                current_direction = Direction::Back;
-            } else if next_direction.direction == MapDirection::East {
-                current_direction = Direction::Left;
-            } else if next_direction.direction == MapDirection::South {
-                current_direction = Direction::Front;
-            } else if next_direction.direction == MapDirection::West {
-                current_direction = Direction::Right;
-            }
-        } else if north_at == MapDirection::West {
-            if next_direction.direction == MapDirection::North {
-                current_direction = Direction::Right;
-            } else if next_direction.direction == MapDirection::East {
-                current_direction = Direction::Back;
-            } else if next_direction.direction == MapDirection::South {
-                current_direction = Direction::Left;
-            } else if next_direction.direction == MapDirection::West {
-                current_direction = Direction::Front;
-            }
-        }
-
-        calculate_next_north(&mut north_at, &current_direction);
+        let current_direction = to_relative(north_at, next_direction.direction);
 
-        print_map(&map);
+        // Without a mini-map interval, the map is printed before every move
+        if config.minimap_interval.is_none() {
+            print_map(&map);
+        }
         let action_message = Message::Action(Action::MoveTo(current_direction.clone()));
 
         send_message(&mut player_stream, &action_message)
@@ -335,37 +989,60 @@ fn search_for_exit_smart(
             "Player {} sent action: {:?}",
             player_name, current_direction
         );
+        moves += 1;
 
         // Receive the server's response to the action
-        let mut action_response = receive_message(&mut player_stream)
-            .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+        let mut action_response = match receive_action_response(&mut player_stream)? {
+            Ok(response) => response,
+            Err(reason) => return Ok(SolveResult::end(moves, reason)),
+        };
         println!(
             "Player {} received response: {}",
             player_name, action_response
         );
 
-        if action_response.contains("Hint") {
-            println!("Player {} found a hint!", player_name);
-            handle_hint(&player_name, &action_response)?;
+        let mut server_message = ServerMessage::parse(&action_response);
 
-            // get next message from server to get the radar view
-            action_response = receive_message(&mut player_stream)
-                .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+        // The server follows a wall hit with the radar view of the cell the player stayed in
+        let hit_wall = server_message == ServerMessage::CannotPassThroughWall;
+        if hit_wall {
+            action_response = match receive_action_response(&mut player_stream)? {
+                Ok(response) => response,
+                Err(reason) => return Ok(SolveResult::end(moves, reason)),
+            };
             println!(
                 "Player {} received response: {}",
                 player_name, action_response
             );
+            server_message = ServerMessage::parse(&action_response);
+        }
+
+        if server_message.hint().is_some() {
+            println!("Player {} found a hint!", player_name);
+            handle_hint(&player_name, &mut player_stream, &action_response)?;
+
+            // get next message from server to get the radar view, unless it came with the hint
+            if !matches!(server_message, ServerMessage::RadarView { .. }) {
+                action_response = receive_game_message(&mut player_stream)
+                    .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+                println!(
+                    "Player {} received response: {}",
+                    player_name, action_response
+                );
+                server_message = ServerMessage::parse(&action_response);
+            }
         }
 
-        if action_response.contains("Challenge") {
+        if let ServerMessage::Challenge(_) = server_message {
             println!("Player {} found a challenge!", player_name);
             // cannot move until challenge is solved
             resolve_challenge(&player_name, &mut player_stream, &action_response)?;
 
             // get next message from server to get the radar view
-            action_response = receive_message(&mut player_stream)
+            action_response = receive_game_message(&mut player_stream)
                 .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
-            if action_response.contains("RadarView") {
+            server_message = ServerMessage::parse(&action_response);
+            if let ServerMessage::RadarView { .. } = server_message {
                 // Log the challenge solution in projectRoot/log/challenge.log
                 log_message(
                     "challenge",
@@ -379,124 +1056,151 @@ fn search_for_exit_smart(
             .map_err(|e| PlayerError::ActionFailed(e.to_string()))?;
 
         // Check for exit condition
-        if action_response.contains("FoundExit") {
+        if server_message == ServerMessage::FoundExit {
             println!("Player {} found the exit!", player_name);
             // terminate the player thread
-            return Ok(());
+            return Ok(SolveResult::end(moves, EndReason::FoundExit));
+        }
+
+        // The player did not move nor turn, it only learnt where the wall is
+        if hit_wall {
+            eprintln!(
+                "Player {} hit a wall to the {:?}",
+                player_name, next_direction.direction
+            );
+            mark_wall(&mut map, next_direction.direction);
+            thread::sleep(Duration::from_millis(10));
+            continue;
         }
 
-        let mut map_new = parse_radar_response_smart(&action_response);
-        map_new = rotate_map(map_new, next_direction.direction);
+        // a malformed radar view is skipped, the map stays as it was
+        let parsed = match radar_cache.parse(&action_response, config.color) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                error!("Player {} skipped a radar view: {}", player_name, e);
+                continue;
+            }
+        };
+        // the move went through, the player faces its new direction
+        north_at = apply_turn(north_at, &current_direction);
+        goal = goal_direction(&parsed.0, &parsed.1, &parsed.2);
+        let mut map_new = radar_map(&parsed);
+        print_radar_legend(config);
+        map_new = rotate_map(map_new, north_at);
         map = update_map(&mut map, map_new, next_direction.direction).to_vec();
 
+        if should_render_minimap(config.minimap_interval, moves) {
+            print!("{}", get_minimap_as_string(&map, &north_at));
+        }
+
         // timeout 1/100 of a second
         thread::sleep(Duration::from_millis(10));
     }
 }
 
-fn calculate_next_north(north_at: &mut MapDirection, current_direction: &Direction) {
-    if current_direction == &Direction::Right {
-        if *north_at == MapDirection::North {
-            *north_at = MapDirection::East;
-        } else if *north_at == MapDirection::East {
-            *north_at = MapDirection::South;
-        } else if *north_at == MapDirection::South {
-            *north_at = MapDirection::West;
-        } else if *north_at == MapDirection::West {
-            *north_at = MapDirection::North;
-        }
-    } else if current_direction == &Direction::Left {
-        if *north_at == MapDirection::North {
-            *north_at = MapDirection::West;
-        } else if *north_at == MapDirection::East {
-            *north_at = MapDirection::North;
-        } else if *north_at == MapDirection::South {
-            *north_at = MapDirection::East;
-        } else if *north_at == MapDirection::West {
-            *north_at = MapDirection::South;
-        }
-    } else if current_direction == &Direction::Back {
-        if *north_at == MapDirection::North {
-            *north_at = MapDirection::South;
-        } else if *north_at == MapDirection::East {
-            *north_at = MapDirection::West;
-        } else if *north_at == MapDirection::South {
-            *north_at = MapDirection::North;
-        } else if *north_at == MapDirection::West {
-            *north_at = MapDirection::East;
-        }
+fn print_map(map: &Vec<Vec<MapCell>>) {
+    print!("{}", get_map_as_string(map));
+}
+
+/**
+ * The boundary_symbol function returns the character used to draw a boundary of the discovered map.
+ *
+ * @param boundary: &Boundary - The boundary to draw
+ * @param wall: char - The character used for a wall ('-' or '|')
+ * @return char - The symbol of the boundary
+ */
+fn boundary_symbol(boundary: &Boundary, wall: char) -> char {
+    match boundary {
+        Boundary::Wall => wall,
+        Boundary::Open => ' ',
+        Boundary::Checked => 'C',
+        _ => '*',
     }
 }
 
-fn print_map(map: &Vec<Vec<MapCell>>) {
+/**
+ * The get_map_as_string function renders the discovered map, with 'P' marking the player.
+ * Walls are drawn with '-' and '|', checked passages with 'C' and unknown ones with '*'.
+ *
+ * @param map: &Vec<Vec<MapCell>> - The map discovered so far
+ * @return String - The rendered map
+ */
+fn get_map_as_string(map: &Vec<Vec<MapCell>>) -> String {
+    let mut rendered = String::new();
     for i in 0..map.len() {
         for j in 0..map[i].len() {
-            if map[i][j].north == Boundary::Wall {
-                print!("•-");
-            } else if map[i][j].north == Boundary::Open {
-                print!("• ");
-            } else if map[i][j].north == Boundary::Checked {
-                print!("•C");
-            } else {
-                print!("•*");
-            }
+            rendered.push('•');
+            rendered.push(boundary_symbol(&map[i][j].north, '-'));
             if j == map[i].len() - 1 {
-                println!("•");
+                rendered.push_str("•\n");
             }
         }
         for j in 0..map[i].len() {
-            if map[i][j].west == Boundary::Wall {
-                print!("|");
-            } else if map[i][j].west == Boundary::Open {
-                print!(" ");
-            } else if map[i][j].west == Boundary::Checked {
-                print!("C");
-            } else {
-                print!("*");
-            }
-            if map[i][j].is_player_here {
-                print!("P");
-            } else {
-                print!(" ");
-            }
+            rendered.push(boundary_symbol(&map[i][j].west, '|'));
+            rendered.push(if map[i][j].is_player_here { 'P' } else { ' ' });
             if j == map[i].len() - 1 {
-                if map[i][j].east == Boundary::Wall {
-                    print!("|");
-                } else if map[i][j].east == Boundary::Open {
-                    print!(" ");
-                } else if map[i][j].east == Boundary::Checked {
-                    print!("C");
-                } else {
-                    print!("*");
-                }
+                rendered.push(boundary_symbol(&map[i][j].east, '|'));
             }
         }
         if i == map.len() - 1 {
-            println!();
+            rendered.push('\n');
             for j in 0..map[i].len() {
-                if map[i][j].south == Boundary::Wall {
-                    print!("•-");
-                } else if map[i][j].south == Boundary::Open {
-                    print!("• ");
-                } else if map[i][j].south == Boundary::Checked {
-                    print!("•C");
-                } else {
-                    print!("•*");
-                }
+                rendered.push('•');
+                rendered.push(boundary_symbol(&map[i][j].south, '-'));
                 if j == map[i].len() - 1 {
-                    println!("•");
+                    rendered.push_str("•\n");
                 }
             }
         }
-        println!();
+        rendered.push('\n');
     }
+    rendered
 }
 
-fn handle_hint(player_name: &String, hint: &String) -> Result<(), Error> {
-    // Log the hint in projectRoot/log/hint.log
-    debug!("Received a hint: {}", hint);
-
-    // Parse secret from hint if present
+/**
+ * The get_minimap_as_string function renders the discovered map under a compass line
+ * telling which direction the player is facing.
+ *
+ * @param map: &Vec<Vec<MapCell>> - The map discovered so far
+ * @param facing: &MapDirection - The direction the player is facing
+ * @return String - The rendered mini-map
+ */
+fn get_minimap_as_string(map: &Vec<Vec<MapCell>>, facing: &MapDirection) -> String {
+    format!("Compass: facing {:?}\n{}", facing, get_map_as_string(map))
+}
+
+/**
+ * The should_render_minimap function tells if the mini-map is due after the given number of moves.
+ *
+ * @param minimap_interval: Option<u64> - The number of moves between two renders, if enabled
+ * @param moves: u64 - The number of moves made so far
+ * @return bool - true if the mini-map must be printed now
+ */
+fn should_render_minimap(minimap_interval: Option<u64>, moves: u64) -> bool {
+    match minimap_interval {
+        Some(interval) => moves > 0 && moves.is_multiple_of(interval),
+        None => false,
+    }
+}
+
+/**
+ * The handle_hint function stores and logs a hint received by the player.
+ * A secret is stored in SECRET_MAP and shared with the teammates through the server,
+ * for those playing from another process.
+ *
+ * @param player_name: &String - The name of the player
+ * @param player_stream: &mut impl Write - The connection of the player
+ * @param hint: &String - The server message holding the hint
+ */
+fn handle_hint(
+    player_name: &String,
+    player_stream: &mut impl Write,
+    hint: &String,
+) -> Result<(), Error> {
+    // Log the hint in projectRoot/log/hint.log
+    debug!("Received a hint: {}", hint);
+
+    // Parse secret from hint if present
     if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(hint) {
         if let Some(secret_val) = json_val["Hint"]["Secret"].as_u64() {
             if let Some(map) = SECRET_MAP.get() {
@@ -506,6 +1210,13 @@ fn handle_hint(player_name: &String, hint: &String) -> Result<(), Error> {
                 map.insert(player_name.clone(), secret_val);
                 info!("Stored secret for player {}: {}", player_name, secret_val);
             }
+
+            let share_message = Message::ShareSecret(ShareSecret {
+                player: player_name.clone(),
+                secret: secret_val,
+            });
+            send_message(player_stream, &share_message)
+                .map_err(|e| PlayerError::HintHandlingFailed(e.to_string()))?;
         }
     }
 
@@ -532,66 +1243,147 @@ fn handle_hint(player_name: &String, hint: &String) -> Result<(), Error> {
     Ok(())
 }
 
+/**
+ * The receive_game_message function reads the next message sent to the player.
+ * Secrets relayed from teammates (ShareSecret) can arrive at any time:
+ * they are stored in SECRET_MAP and the following message is read instead.
+ * Radar views pushed after a ToggleWall answer no action, so they are skipped the same way.
+ *
+ * @param player_stream: &mut impl Read - The connection of the player
+ * @return Result<String, Error> - The next message that is not a relayed secret nor a pushed view
+ */
+fn receive_game_message(player_stream: &mut impl Read) -> Result<String, Error> {
+    loop {
+        let message = receive_message(player_stream)?;
+        if is_pushed_radar_view(&message) {
+            debug!("Skipped a pushed radar view: {}", message);
+            continue;
+        }
+        if !store_shared_secret(&message)? {
+            return Ok(message);
+        }
+    }
+}
+
+/**
+ * The is_pushed_radar_view function tells a radar view the server sent on its own,
+ * e.g. {"RadarView":"...","pushed":true} after a wall next to the player was toggled.
+ *
+ * @param message: &str - The server message
+ * @return bool - true if the message is a pushed radar view
+ */
+fn is_pushed_radar_view(message: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(message).is_ok_and(|json_val| {
+        json_val.get("RadarView").is_some() && json_val["pushed"].as_bool() == Some(true)
+    })
+}
+
+/**
+ * The store_shared_secret function stores a secret relayed from a teammate in SECRET_MAP,
+ * e.g. {"ShareSecret":{"player":"Paul","secret":42}}.
+ *
+ * @param message: &str - The server message
+ * @return Result<bool, Error> - true if the message was a relayed secret
+ */
+fn store_shared_secret(message: &str) -> Result<bool, Error> {
+    let Ok(json_val) = serde_json::from_str::<serde_json::Value>(message) else {
+        return Ok(false);
+    };
+    let shared = &json_val["ShareSecret"];
+    let (Some(player), Some(secret)) = (shared["player"].as_str(), shared["secret"].as_u64())
+    else {
+        return Ok(false);
+    };
+
+    if let Some(map) = SECRET_MAP.get() {
+        let mut map = map
+            .write()
+            .map_err(|e| PlayerError::HintHandlingFailed(e.to_string()))?;
+        map.insert(player.to_string(), secret);
+        info!("Stored secret shared by teammate {}: {}", player, secret);
+    }
+    Ok(true)
+}
+
+/**
+ * The resolve_challenge function answers a challenge sent by the server.
+ * The answer is computed by solve_challenge from the secrets known to the team.
+ *
+ * @param player_name: &String - The name of the player
+ * @param player_stream: &mut impl Write - The connection of the player
+ * @param challenge: &String - The server message holding the challenge
+ * @return Result<(), Error> - An error if the challenge is unknown or the answer could not be sent
+ */
 fn resolve_challenge(
     player_name: &String,
-    player_stream: &mut TcpStream,
+    player_stream: &mut impl Write,
     challenge: &String,
 ) -> Result<(), Error> {
-    // Try to read "Modulo" first, if not present, try "SecretSumModulo"
     let json_val = serde_json::from_str::<serde_json::Value>(challenge)
         .map_err(|e| PlayerError::ChallengeResolutionFailed(e.to_string()))?;
 
-    let mod_val = json_val["Challenge"]["Modulo"]
-        .as_u64()
-        .or(json_val["Challenge"]["SecretSumModulo"].as_u64())
-        .ok_or_else(|| {
-            PlayerError::ChallengeResolutionFailed("Missing modulo value in challenge".to_string())
-        })?;
-
-    if let Some(map) = SECRET_MAP.get() {
-        // Now we have the modulo value from the challenge
-        let map = map
+    let secrets: Vec<u64> = match SECRET_MAP.get() {
+        Some(map) => map
             .read()
-            .map_err(|e| PlayerError::ChallengeResolutionFailed(e.to_string()))?;
-        let secret_hints: Vec<&u64> = map
-            .iter()
-            // .filter(|(name, _)| *name != player_name)
-            .map(|(_, secret)| secret)
-            // log for debugging
-            .inspect(|secret| println!("Secret: {}", secret))
-            .collect();
-
-        // Calculate the sum of the secret hints
-        let sum_of_secret_hint: u128 = secret_hints.iter().map(|&hint| *hint as u128).sum();
-
-        let modulo_result = (sum_of_secret_hint % mod_val as u128) as u64;
+            .map_err(|e| PlayerError::ChallengeResolutionFailed(e.to_string()))?
+            .values()
+            .copied()
+            .collect(),
+        None => Vec::new(),
+    };
+    let answer = solve_challenge(&json_val["Challenge"], &secrets)?;
+    println!(
+        "Player {} resolving challenge {} with {}",
+        player_name, json_val["Challenge"], answer
+    );
 
-        println!(
-            "Player {} resolving challenge with sum {} modulo {} = {}",
-            player_name, sum_of_secret_hint, mod_val, modulo_result
-        );
+    // Construct solution message
+    let solution_message = Message::Action(Action::SolveChallenge(Answer {
+        answer: answer.clone(),
+    }));
+
+    // Send the solution message
+    send_message(player_stream, &solution_message)
+        .map_err(|e| PlayerError::ActionFailed(e.to_string()))?;
+    info!(
+        "Sent challenge solution for player {}: {}",
+        player_name, answer
+    );
 
-        // Construct solution message
-        let solution_message = Message::Action(Action::SolveChallenge(Answer {
-            answer: modulo_result.to_string(),
-        }));
+    // Log the challenge solution
+    log_message(
+        "challenge",
+        &format!("Player {} found: {}", player_name, challenge),
+    )?;
 
-        // Send the solution message
-        send_message(player_stream, &solution_message)
-            .map_err(|e| PlayerError::ActionFailed(e.to_string()))?;
-        info!(
-            "Sent challenge solution for player {}: {}",
-            player_name, modulo_result
-        );
+    Ok(())
+}
 
-        // Log the challenge solution
-        log_message(
-            "challenge",
-            &format!("Player {} found: {}", player_name, challenge),
-        )?;
+/**
+ * The solve_challenge function computes the answer of a challenge, chosen by its shape:
+ * - {"SecretSumModulo": n} (or {"Modulo": n}): the sum of the secrets modulo n
+ * - {"ReverseWord": "word"}: the word written backwards
+ *
+ * @param challenge: &serde_json::Value - The content of the Challenge message
+ * @param secrets: &[u64] - The secrets known to the team
+ * @return Result<String, Error> - The answer, or ChallengeResolutionFailed for an unknown challenge
+ */
+fn solve_challenge(challenge: &serde_json::Value, secrets: &[u64]) -> Result<String, Error> {
+    if let Some(word) = challenge["ReverseWord"].as_str() {
+        return Ok(word.chars().rev().collect());
     }
 
-    Ok(())
+    let mod_val = challenge["Modulo"]
+        .as_u64()
+        .or(challenge["SecretSumModulo"].as_u64())
+        .filter(|&mod_val| mod_val > 0)
+        .ok_or_else(|| {
+            PlayerError::ChallengeResolutionFailed(format!("Unknown challenge: {}", challenge))
+        })?;
+
+    // Calculate the sum of the secret hints
+    let sum_of_secret_hint: u128 = secrets.iter().map(|&hint| hint as u128).sum();
+    Ok((sum_of_secret_hint % mod_val as u128).to_string())
 }
 
 fn find_closest_open(
@@ -618,137 +1410,64 @@ fn find_closest_open(
         }
     }
 
-    if map[player_x][player_y].north == Boundary::Open {
-        return NextDirection {
-            direction: MapDirection::North,
-            steps: 1,
-        };
-    } else if map[player_x][player_y].east == Boundary::Open {
-        return NextDirection {
-            direction: MapDirection::East,
-            steps: 1,
-        };
-    } else if map[player_x][player_y].south == Boundary::Open {
-        return NextDirection {
-            direction: MapDirection::South,
-            steps: 1,
-        };
-    } else if map[player_x][player_y].west == Boundary::Open {
+    // Take an open passage right away, unless it only leads into a dead end already fully explored
+    let player = Coordinates {
+        position_x: player_x,
+        position_y: player_y,
+    };
+    if let Some(direction) = MapDirection::all()
+        .into_iter()
+        .filter(|direction| map[player_x][player_y].boundary(direction) == &Boundary::Open)
+        .min_by_key(|direction| {
+            neighbor_coordinates(map, &player, direction).is_some_and(|next| {
+                is_dead_end(map, &next) && map[next.position_x][next.position_y].is_fully_explored()
+            })
+        })
+    {
         return NextDirection {
-            direction: MapDirection::West,
+            direction,
             steps: 1,
         };
     }
 
-    let mut less_moves_for_north = NextDirection {
-        direction: MapDirection::North,
-        steps: 999,
-    };
-    let mut less_moves_for_south = NextDirection {
-        direction: MapDirection::South,
-        steps: 999,
-    };
-    let mut less_moves_for_east = NextDirection {
-        direction: MapDirection::East,
-        steps: 999,
-    };
-    let mut less_moves_for_west = NextDirection {
-        direction: MapDirection::West,
-        steps: 999,
-    };
-
-    let mut coordinates = Coordinates {
+    let coordinates = Coordinates {
         position_x: player_x,
         position_y: player_y,
     };
 
     let mut copy_of_previous_move = previous_move.clone();
-    copy_of_previous_move.push(coordinates);
+    copy_of_previous_move.push(coordinates.clone());
 
-    if map[player_x][player_y].north == Boundary::Open
-        || map[player_x][player_y].north == Boundary::Checked
-    {
-        if player_x != 0 {
-            let mut found = false;
-            for i in 0..copy_of_previous_move.len() {
-                if copy_of_previous_move[i].position_x == player_x - 1
-                    && copy_of_previous_move[i].position_y == player_y
-                {
-                    found = true;
-                }
-            }
-            if !found {
-                let mut temp_map = map.clone();
-                temp_map[player_x][player_y].is_player_here = false;
-                temp_map[player_x - 1][player_y].is_player_here = true;
-                less_moves_for_north =
-                    find_closest_open(&mut temp_map, copy_of_previous_move.clone(), how_deep + 1);
-            }
-        }
-    }
-    if map[player_x][player_y].west == Boundary::Open
-        || map[player_x][player_y].west == Boundary::Checked
-    {
-        if player_y != 0 {
-            let mut found = false;
-            for i in 0..copy_of_previous_move.len() {
-                if copy_of_previous_move[i].position_x == player_x
-                    && copy_of_previous_move[i].position_y == player_y - 1
-                {
-                    found = true;
-                }
-            }
-            if !found {
-                let mut temp_map = map.clone();
-                temp_map[player_x][player_y].is_player_here = false;
-                temp_map[player_x][player_y - 1].is_player_here = true;
-                less_moves_for_west =
-                    find_closest_open(&mut temp_map, copy_of_previous_move.clone(), how_deep + 1);
-            }
-        }
-    }
-    if map[player_x][player_y].south == Boundary::Open
-        || map[player_x][player_y].south == Boundary::Checked
-    {
-        if player_x != map.len() - 1 {
-            let mut found = false;
-            for i in 0..copy_of_previous_move.len() {
-                if copy_of_previous_move[i].position_x == player_x + 1
-                    && copy_of_previous_move[i].position_y == player_y
-                {
-                    found = true;
-                }
-            }
-            if !found {
-                let mut temp_map = map.clone();
-                temp_map[player_x][player_y].is_player_here = false;
-                temp_map[player_x + 1][player_y].is_player_here = true;
-                less_moves_for_south =
-                    find_closest_open(&mut temp_map, copy_of_previous_move.clone(), how_deep + 1);
-            }
+    // Explore every open side that does not lead back to a cell of the current path
+    let explore = |direction: MapDirection| {
+        let not_found = NextDirection {
+            direction,
+            steps: 999,
+        };
+        if !matches!(
+            map[player_x][player_y].boundary(&direction),
+            Boundary::Open | Boundary::Checked
+        ) {
+            return not_found;
         }
-    }
-    if map[player_x][player_y].east == Boundary::Open
-        || map[player_x][player_y].east == Boundary::Checked
-    {
-        if player_y != map[0].len() - 1 {
-            let mut found = false;
-            for i in 0..copy_of_previous_move.len() {
-                if copy_of_previous_move[i].position_x == player_x
-                    && copy_of_previous_move[i].position_y == player_y + 1
-                {
-                    found = true;
-                }
-            }
-            if !found {
-                let mut temp_map = map.clone();
-                temp_map[player_x][player_y].is_player_here = false;
-                temp_map[player_x][player_y + 1].is_player_here = true;
-                less_moves_for_east =
-                    find_closest_open(&mut temp_map, copy_of_previous_move.clone(), how_deep + 1);
-            }
+        let Some(next) = neighbor_coordinates(map, &coordinates, &direction) else {
+            return not_found;
+        };
+        let found = copy_of_previous_move
+            .iter()
+            .any(|previous| relative_dir(&coordinates, previous) == Some(direction));
+        if found {
+            return not_found;
         }
-    }
+        let mut temp_map = map.clone();
+        temp_map[player_x][player_y].is_player_here = false;
+        temp_map[next.position_x][next.position_y].is_player_here = true;
+        find_closest_open(&mut temp_map, copy_of_previous_move.clone(), how_deep + 1)
+    };
+    let less_moves_for_north = explore(MapDirection::North);
+    let less_moves_for_west = explore(MapDirection::West);
+    let less_moves_for_south = explore(MapDirection::South);
+    let less_moves_for_east = explore(MapDirection::East);
 
     if (less_moves_for_north.steps <= less_moves_for_south.steps)
         && (less_moves_for_north.steps <= less_moves_for_east.steps)
@@ -801,6 +1520,166 @@ fn find_closest_open(
     };
 }
 
+/**
+ * The shortest_path_to_frontier function searches the map breadth-first for the nearest open passage
+ * not taken yet. Open and checked passages can be walked through, walls and undefined boundaries cannot.
+ * Of the open passages of the nearest cell, one not leading into a fully explored dead end is preferred.
+ *
+ * @param map: &[Vec<MapCell>] - The accumulated map, with the player on it
+ * @return Option<Vec<MapDirection>> - The moves from the player's cell through the open passage,
+ * or None if no open passage can be reached
+ */
+fn shortest_path_to_frontier(map: &[Vec<MapCell>]) -> Option<Vec<MapDirection>> {
+    let player = map.iter().enumerate().find_map(|(row, cells)| {
+        cells
+            .iter()
+            .position(|cell| cell.is_player_here)
+            .map(|column| Coordinates {
+                position_x: row,
+                position_y: column,
+            })
+    })?;
+
+    let mut visited = HashSet::from([player.clone()]);
+    let mut queue = VecDeque::from([(player, Vec::new())]);
+    while let Some((coordinates, path)) = queue.pop_front() {
+        let cell = &map[coordinates.position_x][coordinates.position_y];
+
+        let frontier = MapDirection::all()
+            .into_iter()
+            .filter(|direction| cell.boundary(direction) == &Boundary::Open)
+            .min_by_key(|direction| {
+                neighbor_coordinates(map, &coordinates, direction).is_some_and(|next| {
+                    is_dead_end(map, &next)
+                        && map[next.position_x][next.position_y].is_fully_explored()
+                })
+            });
+        if let Some(direction) = frontier {
+            let mut path = path;
+            path.push(direction);
+            return Some(path);
+        }
+
+        for direction in MapDirection::all() {
+            if cell.boundary(&direction) != &Boundary::Checked {
+                continue;
+            }
+            let Some(next) = neighbor_coordinates(map, &coordinates, &direction) else {
+                continue;
+            };
+            if visited.insert(next.clone()) {
+                let mut next_path = path.clone();
+                next_path.push(direction);
+                queue.push_back((next, next_path));
+            }
+        }
+    }
+
+    None
+}
+
+/**
+ * The choose_explore_direction function picks the next direction of the Explore strategy.
+ * Passages leading to unexplored cells (all boundaries undefined, or outside the map) come first,
+ * then open passages not taken yet, then checked passages. Ties keep the MapDirection::all order.
+ *
+ * @param map: &[Vec<MapCell>] - The accumulated map, with the player on it
+ * @return Option<MapDirection> - The direction to take, or None if no passage can be taken
+ */
+fn choose_explore_direction(map: &[Vec<MapCell>]) -> Option<MapDirection> {
+    let player = map.iter().enumerate().find_map(|(row, cells)| {
+        cells
+            .iter()
+            .position(|cell| cell.is_player_here)
+            .map(|column| Coordinates {
+                position_x: row,
+                position_y: column,
+            })
+    })?;
+    let player_cell = &map[player.position_x][player.position_y];
+
+    let is_unexplored = |cell: &MapCell| {
+        MapDirection::all()
+            .iter()
+            .all(|direction| cell.boundary(direction) == &Boundary::Undefined)
+    };
+
+    MapDirection::all()
+        .into_iter()
+        .filter_map(|direction| {
+            let rank = match player_cell.boundary(&direction) {
+                Boundary::Open => 1,
+                Boundary::Checked => 2,
+                _ => return None,
+            };
+            let neighbor = neighbor_coordinates(map, &player, &direction)
+                .map(|next| &map[next.position_x][next.position_y]);
+
+            match neighbor {
+                Some(cell) if !is_unexplored(cell) => Some((rank, direction)),
+                _ => Some((0, direction)),
+            }
+        })
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, direction)| direction)
+}
+
+/**
+ * The neighbor_coordinates function gives the cell next to another one on the map.
+ *
+ * @param map: &[Vec<MapCell>] - The map
+ * @param from: &Coordinates - The cell to start from
+ * @param direction: &MapDirection - The side to look at
+ * @return Option<Coordinates> - The adjacent cell, or None if it is outside the map
+ */
+fn neighbor_coordinates(
+    map: &[Vec<MapCell>],
+    from: &Coordinates,
+    direction: &MapDirection,
+) -> Option<Coordinates> {
+    let next = from.neighbor(*direction)?;
+    map.get(next.position_x)?.get(next.position_y)?;
+    Some(next)
+}
+
+/**
+ * The is_dead_end function tells if a cell of the map can only be entered and left one way.
+ *
+ * @param map: &[Vec<MapCell>] - The map
+ * @param coordinates: &Coordinates - The cell to check
+ * @return bool - True if the cell has exactly one open or checked boundary
+ */
+fn is_dead_end(map: &[Vec<MapCell>], coordinates: &Coordinates) -> bool {
+    map.get(coordinates.position_x)
+        .and_then(|cells| cells.get(coordinates.position_y))
+        .is_some_and(|cell| cell.open_count() == 1)
+}
+
+/**
+ * The relative_dir function gives the direction to take to go from a cell to an adjacent one.
+ * On the map, position_x is the row (growing to the south) and position_y the column (growing to the east).
+ *
+ * @param from: &Coordinates - The cell to start from
+ * @param to: &Coordinates - The cell to reach
+ * @return Option<MapDirection> - The direction, or None if the cells are not adjacent
+ */
+fn relative_dir(from: &Coordinates, to: &Coordinates) -> Option<MapDirection> {
+    let same_row = from.position_x == to.position_x;
+    let same_column = from.position_y == to.position_y;
+
+    if same_column && to.position_x + 1 == from.position_x {
+        Some(MapDirection::North)
+    } else if same_column && to.position_x == from.position_x + 1 {
+        Some(MapDirection::South)
+    } else if same_row && to.position_y == from.position_y + 1 {
+        Some(MapDirection::East)
+    } else if same_row && to.position_y + 1 == from.position_y {
+        Some(MapDirection::West)
+    } else {
+        None
+    }
+}
+
 fn rotate_map(map: Vec<Vec<MapCell>>, direction: MapDirection) -> Vec<Vec<MapCell>> {
     let mut new_map = vec![
         vec![
@@ -862,6 +1741,35 @@ fn rotate_map(map: Vec<Vec<MapCell>>, direction: MapDirection) -> Vec<Vec<MapCel
     return new_map.clone();
 }
 
+/**
+ * The mark_wall function records a wall the player bumped into on the discovered map,
+ * on both sides of the passage.
+ *
+ * @param map: &mut [Vec<MapCell>] - The discovered map
+ * @param direction: MapDirection - The side of the player's cell where the wall is
+ */
+fn mark_wall(map: &mut [Vec<MapCell>], direction: MapDirection) {
+    let Some(from) = (0..map.len())
+        .flat_map(|i| (0..map[i].len()).map(move |j| (i, j)))
+        .find(|&(i, j)| map[i][j].is_player_here)
+        .map(|(i, j)| Coordinates {
+            position_x: i,
+            position_y: j,
+        })
+    else {
+        return;
+    };
+    *map[from.position_x][from.position_y].boundary_mut(&direction) = Boundary::Wall;
+    if let Some(to) = from.neighbor(direction) {
+        if let Some(cell) = map
+            .get_mut(to.position_x)
+            .and_then(|row| row.get_mut(to.position_y))
+        {
+            *cell.boundary_mut(&travel_direction(direction, &Direction::Back)) = Boundary::Wall;
+        }
+    }
+}
+
 fn update_map(
     mut map: &mut Vec<Vec<MapCell>>,
     new_map: Vec<Vec<MapCell>>,
@@ -872,39 +1780,38 @@ fn update_map(
     let mut player_moved = false;
     for i in 0..map.len() - 1 {
         for j in 0..map[i].len() - 1 {
-            if map[i][j].is_player_here {
-                if !player_moved {
-                    map[i][j].is_player_here = false;
-                    if direction == MapDirection::North {
-                        map[i - 1][j].is_player_here = true;
-                        map[i - 1][j].south = Boundary::Checked;
+            if map[i][j].is_player_here && !player_moved {
+                let from = Coordinates {
+                    position_x: i,
+                    position_y: j,
+                };
+                let Some(to) = from.neighbor(direction) else {
+                    continue;
+                };
+                let (x, y) = (to.position_x, to.position_y);
+                map[i][j].is_player_here = false;
+                map[x][y].is_player_here = true;
+                match direction {
+                    MapDirection::North => {
+                        map[x][y].south = Boundary::Checked;
                         map[i][j].north = Boundary::Checked;
-                        player_x = i - 1;
-                        player_y = j;
-                        player_moved = true;
-                    } else if direction == MapDirection::East {
-                        map[i][j + 1].is_player_here = true;
-                        map[i][j + 1].west = Boundary::Checked;
+                    }
+                    MapDirection::East => {
+                        map[x][y].west = Boundary::Checked;
                         map[i][j].east = Boundary::Checked;
-                        player_x = i;
-                        player_y = j + 1;
-                        player_moved = true;
-                    } else if direction == MapDirection::South {
-                        map[i + 1][j].is_player_here = true;
-                        map[i + 1][j].north = Boundary::Checked;
+                    }
+                    MapDirection::South => {
+                        map[x][y].north = Boundary::Checked;
                         map[i][j].south = Boundary::Checked;
-                        player_x = i + 1;
-                        player_y = j;
-                        player_moved = true;
-                    } else if direction == MapDirection::West {
-                        map[i][j - 1].is_player_here = true;
-                        map[i][j - 1].east = Boundary::Checked;
+                    }
+                    MapDirection::West => {
+                        map[x][y].east = Boundary::Checked;
                         map[i][j].west = Boundary::Checked;
-                        player_x = i;
-                        player_y = j - 1;
-                        player_moved = true;
                     }
                 }
+                player_x = x;
+                player_y = y;
+                player_moved = true;
             }
         }
     }
@@ -1023,44 +1930,148 @@ fn update_map(
     map
 }
 
-// fixme remove, only for testing
-// waiting for user input 1,2,3 or 4
-fn choose_direction_by_hand(player_name: String, mut player_stream: TcpStream) {
-    let mut current_direction = Direction::Right;
+/**
+ * The play_by_hand function lets the user move the player from the terminal (--strategy hand).
+ * Each turn the radar map and its open directions are printed, then a command is read:
+ * f, r, b or l (or front, right, back, left) to move, q to quit. The game also ends at the end of the input.
+ *
+ * @param player_name: String - The name of the player
+ * @param player_stream: impl Read + Write - The connection of the player
+ * @param initial_radar_response: String - The initial radar response from the server
+ * @param input: impl BufRead - Where the commands are read, the standard input outside of tests
+ * @param config: &ClientConfig - The client options
+ * @return Result<SolveResult, Error> - How the game ended and the number of moves sent
+ */
+fn play_by_hand(
+    player_name: String,
+    mut player_stream: impl Read + Write,
+    initial_radar_response: String,
+    mut input: impl BufRead,
+    config: &ClientConfig,
+) -> Result<SolveResult, Error> {
+    let mut radar_response = initial_radar_response;
+    let mut moves: u64 = 0;
+
     loop {
-        // 1 = front, 2 = right, 3 = back, 4 = left
-        let mut input = String::new();
-        std::io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read line");
-        let input = input.trim();
-        match input {
-            "1" => current_direction = Direction::Front,
-            "2" => current_direction = Direction::Right,
-            "3" => current_direction = Direction::Back,
-            "4" => current_direction = Direction::Left,
-            _ => println!("Invalid input"),
+        match parse_radar_response(&radar_response, config.color) {
+            Ok((_, horizontal_passages, vertical_passages)) => {
+                print_radar_legend(config);
+                let open: Vec<Direction> = Direction::all()
+                    .into_iter()
+                    .filter(|direction| {
+                        is_direction_open(direction, &horizontal_passages, &vertical_passages)
+                    })
+                    .collect();
+                println!("Open directions: {:?}", open);
+            }
+            Err(e) => error!(
+                "Player {} received an invalid radar view: {}",
+                player_name, e
+            ),
         }
 
-        let action_message = Message::Action(Action::MoveTo(current_direction.clone()));
+        let Some(direction) = read_hand_command(&player_name, &mut input)? else {
+            return Ok(SolveResult::end(moves, EndReason::Quit));
+        };
 
-        send_message(&mut player_stream, &action_message).expect("Failed to send action");
-        println!(
-            "Player {} sent action: {:?}",
-            player_name, current_direction
-        );
+        let action_message = Message::Action(Action::MoveTo(direction.clone()));
+        send_message(&mut player_stream, &action_message)
+            .map_err(|e| PlayerError::ActionFailed(e.to_string()))?;
+        moves += 1;
 
-        // Receive the server's response to the action
-        let action_response =
-            receive_message(&mut player_stream).expect("Failed to receive action response");
-        println!(
-            "Player {} received response: {}",
-            player_name, action_response
-        );
+        let mut action_response = match receive_action_response(&mut player_stream)? {
+            Ok(response) => response,
+            Err(reason) => return Ok(SolveResult::end(moves, reason)),
+        };
+        let mut server_message = ServerMessage::parse(&action_response);
+
+        // The server follows a wall hit with the radar view of the cell the player stayed in
+        if server_message == ServerMessage::CannotPassThroughWall {
+            println!("Player {} hit a wall to the {:?}", player_name, direction);
+            action_response = match receive_action_response(&mut player_stream)? {
+                Ok(response) => response,
+                Err(reason) => return Ok(SolveResult::end(moves, reason)),
+            };
+            server_message = ServerMessage::parse(&action_response);
+        }
+
+        if server_message.hint().is_some() {
+            println!("Player {} found a hint: {}", player_name, action_response);
+            handle_hint(&player_name, &mut player_stream, &action_response)?;
+            if !matches!(server_message, ServerMessage::RadarView { .. }) {
+                action_response = receive_game_message(&mut player_stream)
+                    .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+                server_message = ServerMessage::parse(&action_response);
+            }
+        }
+
+        if let ServerMessage::Challenge(_) = server_message {
+            resolve_challenge(&player_name, &mut player_stream, &action_response)?;
+            action_response = receive_game_message(&mut player_stream)
+                .map_err(|e| PlayerError::RadarResponseFailed(e.to_string()))?;
+            server_message = ServerMessage::parse(&action_response);
+        }
+
+        if server_message == ServerMessage::FoundExit {
+            println!("Player {} found the exit!", player_name);
+            return Ok(SolveResult::end(moves, EndReason::FoundExit));
+        }
+        radar_response = action_response;
+    }
+}
+
+/**
+ * The read_hand_command function asks for the next move of the player played by hand,
+ * until a valid command is given.
+ *
+ * @param player_name: &str - The name of the player
+ * @param input: &mut impl BufRead - Where the commands are read
+ * @return Result<Option<Direction>, Error> - The move, or None to quit (q or end of the input)
+ */
+fn read_hand_command(
+    player_name: &str,
+    input: &mut impl BufRead,
+) -> Result<Option<Direction>, Error> {
+    loop {
+        print!("Player {} move (f/r/b/l, q to quit): ", player_name);
+        std::io::stdout()
+            .flush()
+            .map_err(|e| PlayerError::ActionFailed(e.to_string()))?;
+
+        let mut line = String::new();
+        let read = input
+            .read_line(&mut line)
+            .map_err(|e| PlayerError::ActionFailed(e.to_string()))?;
+        if read == 0 {
+            println!();
+            return Ok(None);
+        }
 
-        player_stream.flush().expect("Failed to flush stream");
+        let command = line.trim();
+        if command.eq_ignore_ascii_case("q") || command.eq_ignore_ascii_case("quit") {
+            return Ok(None);
+        }
+        match command.parse::<Direction>() {
+            Ok(direction) => return Ok(Some(direction)),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
 
-        parse_radar_response(&action_response);
+/**
+ * The passage_index function gives the index of the passage around the center cell
+ * of the radar view in the given direction.
+ * Front and Back index the horizontal passages, Left and Right the vertical ones.
+ *
+ * @param direction: &Direction - The direction from the center cell
+ * @return usize - The passage index
+ */
+fn passage_index(direction: &Direction) -> usize {
+    match direction {
+        Direction::Front => 4,
+        Direction::Right => 6,
+        Direction::Back => 7,
+        Direction::Left => 5,
     }
 }
 
@@ -1078,12 +2089,7 @@ fn is_direction_open(
     // We are following the right-hand rule, so we want to check the passage to the right of the player.
 
     // Map the next direction to the passage index
-    let passage_index = match next_direction {
-        Direction::Front => 4,
-        Direction::Right => 6,
-        Direction::Back => 7,
-        Direction::Left => 5,
-    };
+    let passage_index = passage_index(next_direction);
 
     // log for debugging
     println!(
@@ -1119,48 +2125,129 @@ fn is_direction_open(
 }
 
 /**
- * The parse_radar_response function parses the radar response from the server.
- * It extracts the radar data from the response, decodes the data, and parses the cells, horizontal passages, and vertical passages.
- * It returns a tuple containing the cells, horizontal passages, and vertical passages.
+ * The split_radar_and_hint function extracts the radar view and the hint from a server message.
+ * The server sends them either as two messages or, to save a round-trip,
+ * combined in a single `{"RadarView":"...","Hint":{...}}` message.
+ *
+ * @param response: &str - The raw message received from the server
+ * @return (Option<String>, Option<serde_json::Value>) - The encoded radar view and the hint, if present
  */
-pub(crate) fn parse_radar_response(
-    response: &str,
-) -> (Vec<RadarCell>, Vec<Boundary>, Vec<Boundary>) {
-    if response.contains("CannotPassThroughWall")
-        || response.contains("FoundExit")
-        || response.contains("Hint")
-    {
-        return (vec![], vec![], vec![]);
-    }
+pub(crate) fn split_radar_and_hint(response: &str) -> (Option<String>, Option<serde_json::Value>) {
+    let json_val = match serde_json::from_str::<serde_json::Value>(response) {
+        Ok(json_val) => json_val,
+        Err(_) => return (None, None),
+    };
 
-    // Extract radar data from the response
-    // Response format: {"RadarView":"aeQrajHOapap//a"}
-    let radar_data = response
-        .split("\":\"")
-        .nth(1)
-        .unwrap()
-        .split("\"")
-        .next()
-        .unwrap();
+    let radar_view = json_val["RadarView"].as_str().map(String::from);
+    let hint = match &json_val["Hint"] {
+        serde_json::Value::Null => None,
+        hint => Some(hint.clone()),
+    };
 
-    if radar_data.is_empty() {
-        println!("No radar data found in the response.");
-        panic!("No radar data found in the response.");
+    (radar_view, hint)
+}
+
+/**
+ * The cells, horizontal passages and vertical passages of a parsed radar view.
+ */
+type ParsedRadar = (Vec<RadarCell>, Vec<Boundary>, Vec<Boundary>);
+
+/**
+ * The RadarCache struct keeps the last radar view parsed, keyed by its raw encoded string,
+ * so that a payload seen again within a step is not decoded and parsed a second time.
+ */
+#[derive(Debug, Default)]
+pub(crate) struct RadarCache {
+    last: Option<(String, ParsedRadar)>,
+    /// Number of radar views actually decoded, checked by the tests
+    #[cfg(test)]
+    decodes: usize,
+}
+
+impl RadarCache {
+    /**
+     * The parse function returns the same result as parse_radar_response,
+     * reusing the last result if the radar view is the same as last time.
+     * The radar map is printed either way.
+     *
+     * @param response: &str - The server response holding the radar view
+     * @param color: bool - Whether the radar map is printed in color
+     * @return Result<ParsedRadar, Error> - The cells, horizontal passages and vertical passages,
+     * or InvalidRadarData for a malformed radar view
+     */
+    pub(crate) fn parse(&mut self, response: &str, color: bool) -> Result<ParsedRadar, Error> {
+        let (radar_view, _) = split_radar_and_hint(response);
+        if let (Some((last_view, parsed)), Some(radar_view)) = (&self.last, &radar_view) {
+            if last_view == radar_view {
+                let (cells, h_passages, v_passages) = parsed;
+                print_radar_map(cells, h_passages, v_passages, color);
+                return Ok(parsed.clone());
+            }
+        }
+
+        let parsed = parse_radar_response(response, color)?;
+        #[cfg(test)]
+        {
+            self.decodes += 1;
+        }
+        // Wall and exit responses have no radar view to remember
+        if let Some(radar_view) = radar_view.filter(|_| !parsed.1.is_empty()) {
+            self.last = Some((radar_view, parsed.clone()));
+        }
+        Ok(parsed)
     }
+}
+
+/**
+ * The decode_radar_view function extracts and decodes the radar view of a server message.
+ * Wall and exit responses have no radar view: None is returned for them.
+ *
+ * @param response: &str - The raw message received from the server
+ * @return Result<Option<Vec<u8>>, Error> - The 11 or 12 decoded bytes, or InvalidRadarData
+ * if the message has no radar view, or one that is empty, not valid base64 or of the wrong length
+ */
+fn decode_radar_view(response: &str) -> Result<Option<Vec<u8>>, Error> {
+    // Response format: {"RadarView":"aeQrajHOapap//a"}
+    let radar_data = match ServerMessage::parse(response) {
+        ServerMessage::RadarView { view, .. } => view,
+        ServerMessage::CannotPassThroughWall | ServerMessage::FoundExit => return Ok(None),
+        _ => {
+            warn!("No radar view in the response: {}", response);
+            return Err(PlayerError::InvalidRadarData.into());
+        }
+    };
 
-    // Decode the radar data
-    let decoded_radar_data = decode(radar_data).expect("Failed to decode radar data");
+    let decoded_radar_data = decode(&radar_data).map_err(|e| {
+        warn!("Failed to decode radar data '{}': {}", radar_data, e);
+        PlayerError::InvalidRadarData
+    })?;
 
     // Print the decoded radar data
     println!("Decoded radar data: {:?}", decoded_radar_data);
 
     // Check that the length of the decoded data is 11 bytes
-    // (3 bytes for horizontal passages, 3 bytes for vertical passages, 5 bytes for cells)
-    if decoded_radar_data.len() != 11 {
-        println!("Invalid radar data length: {}", decoded_radar_data.len());
-        panic!("Invalid radar data length: {}", decoded_radar_data.len());
+    // (3 bytes for horizontal passages, 3 bytes for vertical passages, 5 bytes for cells),
+    // or 12 with the bearing of a nearby exit (server option --sense-radius)
+    if !matches!(decoded_radar_data.len(), 11 | 12) {
+        warn!("Invalid radar data length: {}", decoded_radar_data.len());
+        return Err(PlayerError::InvalidRadarData.into());
     }
 
+    Ok(Some(decoded_radar_data))
+}
+
+/**
+ * The parse_radar_response function parses the radar response from the server.
+ * It extracts the radar data from the response, decodes the data, and parses the cells, horizontal passages, and vertical passages.
+ * It returns a tuple containing the cells, horizontal passages, and vertical passages,
+ * all empty for a wall or exit response, or InvalidRadarData for a malformed radar view.
+ * The radar map is printed in color if `color` is set (--color).
+ */
+pub(crate) fn parse_radar_response(response: &str, color: bool) -> Result<ParsedRadar, Error> {
+    let Some(decoded_radar_data) = decode_radar_view(response)? else {
+        return Ok((vec![], vec![], vec![]));
+    };
+
     // Parse the horizontal passages (12 passages, 2 bits each)
     let horizontal_passages = parse_passages(&decoded_radar_data[0..3], 12, "Horizontal");
 
@@ -1168,7 +2255,7 @@ pub(crate) fn parse_radar_response(
     let vertical_passages = parse_passages(&decoded_radar_data[3..6], 12, "Vertical");
 
     // Parse les cellules (9 cellules, 4 bits chacune)
-    let cells = parse_cells(&decoded_radar_data[6..11]);
+    let cells = parse_cells(&decoded_radar_data[6..11])?;
 
     println!("Horizontal Passages:");
     for (i, passage) in horizontal_passages.iter().enumerate() {
@@ -1185,102 +2272,72 @@ pub(crate) fn parse_radar_response(
         println!("  Cell {}: {:?}", i, cell);
     }
 
-    let two_d_cells: Vec<Vec<RadarCell>> = cells.chunks(3).map(|chunk| chunk.to_vec()).collect();
+    if let Some(angle) = exit_sense_angle(&decoded_radar_data) {
+        println!("Exit sensed {} degrees clockwise from the front", angle);
+    }
 
-    // print radar map
+    print_radar_map(&cells, &horizontal_passages, &vertical_passages, color);
+
+    Ok((cells, horizontal_passages, vertical_passages))
+}
+
+/**
+ * The print_radar_map function prints the map of a parsed radar view.
+ *
+ * @param cells: &[RadarCell] - The 9 cells of the radar view
+ * @param h_passages: &[Boundary] - The horizontal passages of the radar view
+ * @param v_passages: &[Boundary] - The vertical passages of the radar view
+ * @param color: bool - Whether the map is printed in color (--color)
+ */
+fn print_radar_map(
+    cells: &[RadarCell],
+    h_passages: &[Boundary],
+    v_passages: &[Boundary],
+    color: bool,
+) {
+    let two_d_cells: Vec<Vec<RadarCell>> = cells.chunks(3).map(|chunk| chunk.to_vec()).collect();
     println!(
         "{}",
-        get_radar_map_as_string(&two_d_cells, &horizontal_passages, &vertical_passages)
+        if color {
+            get_colored_radar_map_as_string(&two_d_cells, h_passages, v_passages)
+        } else {
+            get_radar_map_as_string(&two_d_cells, h_passages, v_passages)
+        }
     );
-
-    (cells, horizontal_passages, vertical_passages)
 }
 
 /**
- * The parse_radar_response function parses the radar response from the server.
- * It extracts the radar data from the response, decodes the data, and parses the cells, horizontal passages, and vertical passages.
- * It returns a tuple containing the cells, horizontal passages, and vertical passages.
+ * The undefined_radar_map function returns a 3x3 map where nothing is known,
+ * used when a response has no radar view to read.
  */
-pub(crate) fn parse_radar_response_smart(response: &str) -> (Vec<Vec<MapCell>>) {
-    if response.contains("CannotPassThroughWall")
-        || response.contains("FoundExit")
-        || response.contains("Hint")
-    {
-        return vec![
-            vec![MapCell {
+fn undefined_radar_map() -> Vec<Vec<MapCell>> {
+    vec![
+        vec![
+            MapCell {
                 north: Boundary::Undefined,
                 east: Boundary::Undefined,
                 south: Boundary::Undefined,
                 west: Boundary::Undefined,
                 is_player_here: false
-            }];
+            };
             3
         ];
-    }
-
-    // Extract radar data from the response
-    // Response format: {"RadarView":"aeQrajHOapap//a"}
-    let radar_data = response
-        .split("\":\"")
-        .nth(1)
-        .unwrap()
-        .split("\"")
-        .next()
-        .unwrap();
-
-    if radar_data.is_empty() {
-        println!("No radar data found in the response.");
-        panic!("No radar data found in the response.");
-    }
-
-    // Decode the radar data
-    let decoded_radar_data = decode(radar_data).expect("Failed to decode radar data");
-
-    // Print the decoded radar data
-    println!("Decoded radar data: {:?}", decoded_radar_data);
+        3
+    ]
+}
 
-    // Check that the length of the decoded data is 11 bytes
-    // (3 bytes for horizontal passages, 3 bytes for vertical passages, 5 bytes for cells)
-    if decoded_radar_data.len() != 11 {
-        println!("Invalid radar data length: {}", decoded_radar_data.len());
-        panic!("Invalid radar data length: {}", decoded_radar_data.len());
+/**
+ * The radar_map function turns the passages of a parsed radar view into a 3x3 map.
+ * The map is undefined for a wall or exit response, which has no passages.
+ *
+ * @param parsed: &ParsedRadar - The cells, horizontal passages and vertical passages of the radar view
+ * @return Vec<Vec<MapCell>> - The 3x3 map around the player
+ */
+fn radar_map((_, h_passages, v_passages): &ParsedRadar) -> Vec<Vec<MapCell>> {
+    if h_passages.is_empty() {
+        return undefined_radar_map();
     }
-
-    // Parse the horizontal passages (12 passages, 2 bits each)
-    let horizontal_passages = parse_passages(&decoded_radar_data[0..3], 12, "Horizontal");
-
-    // Parse les passages verticaux (12 passages, 2 bits chacun)
-    let vertical_passages = parse_passages(&decoded_radar_data[3..6], 12, "Vertical");
-
-    // Parse les cellules (9 cellules, 4 bits chacune)
-    let cells = parse_cells(&decoded_radar_data[6..11]);
-
-    let map = make_map_with_passages(&horizontal_passages, &vertical_passages);
-
-    // println!("Horizontal Passages:");
-    // for (i, passage) in horizontal_passages.iter().enumerate() {
-    //     println!("  Passage {}: {:?}", i, passage);
-    // }
-
-    // println!("Vertical Passages:");
-    // for (i, passage) in vertical_passages.iter().enumerate() {
-    //     println!("  Passage {}: {:?}", i, passage);
-    // }
-
-    // println!("Cells:");
-    // for (i, cell) in cells.iter().enumerate() {
-    //     println!("  Cell {}: {:?}", i, cell);
-    // }
-
-    let two_d_cells: Vec<Vec<RadarCell>> = cells.chunks(3).map(|chunk| chunk.to_vec()).collect();
-
-    // print radar map
-    println!(
-        "{}",
-        get_radar_map_as_string(&two_d_cells, &horizontal_passages, &vertical_passages)
-    );
-
-    map
+    make_map_with_passages(h_passages, v_passages)
 }
 
 fn make_map_with_passages(
@@ -1382,7 +2439,30 @@ fn parse_passages(bytes: &[u8], num_passages: usize, passage_type: &str) -> Vec<
     passages
 }
 
-fn parse_cells(data: &[u8]) -> Vec<RadarCell> {
+/**
+ * The exit_sense_angle function reads the optional 12th byte of a radar view, sent by servers
+ * running with --sense-radius when the exit is out of view but close: the bearing of the exit
+ * in eighths of a turn clockwise from the front.
+ *
+ * @param data: &[u8] - The decoded radar view
+ * @return Option<f64> - The bearing of the exit in degrees clockwise from the front, None without the byte
+ */
+pub(crate) fn exit_sense_angle(data: &[u8]) -> Option<f64> {
+    data.get(11).map(|&eighths| f64::from(eighths % 8) * 45.0)
+}
+
+/**
+ * The parse_cells function extracts the 9 cells from the last 5 bytes of the radar data.
+ * Each cell takes 4 bits, the last 4 bits being padding.
+ *
+ * @param data: &[u8] - The 5 bytes of cells
+ * @return Result<Vec<RadarCell>, Error> - The cells row by row, or InvalidSize if data is not 5 bytes long
+ */
+fn parse_cells(data: &[u8]) -> Result<Vec<RadarCell>, Error> {
+    if data.len() != 5 {
+        return Err(DecodeError::InvalidSize.into());
+    }
+
     let mut cells = Vec::new();
     let mut bits = 0u64;
     for &byte in data {
@@ -1404,6 +2484,17 @@ fn parse_cells(data: &[u8]) -> Vec<RadarCell> {
             continue;
         }
 
+        if value == 0b1101 {
+            // Visited cell: a reserved whole-cell code, read before the item and entity bits
+            // since its low bit is not an ally
+            cells.push(RadarCell {
+                is_undefined: false,
+                item: Item::Visited,
+                entity: Entity::None,
+            });
+            continue;
+        }
+
         let item_bits = (value >> 2) & 0b11;
         let entity_bits = value & 0b11;
 
@@ -1429,17 +2520,72 @@ fn parse_cells(data: &[u8]) -> Vec<RadarCell> {
         });
     }
 
-    cells
+    Ok(cells)
+}
+
+/// The legend printed under the radar map when the client runs with `--legend`.
+pub(crate) const RADAR_LEGEND: &str =
+    "Legend: # = unknown, - | = wall, ? = unreadable, • = joint, \
+G = goal, H = hint, A = ally, E = enemy, M = monster, . = visited";
+
+/**
+ * The print_radar_legend function prints the radar legend if it is enabled in the config.
+ *
+ * @param config: &ClientConfig - The client options
+ */
+fn print_radar_legend(config: &ClientConfig) {
+    if config.show_legend {
+        println!("{}", RADAR_LEGEND);
+    }
+}
+
+/**
+ * The radar_cell_code function gives back the 4-bit code the server sent for a radar cell.
+ *
+ * @param cell: &RadarCell - The decoded radar cell
+ * @return u8 - The item in the high 2 bits and the entity in the low 2 bits, 0b1111 if undefined
+ * and 0b1101 if visited
+ */
+fn radar_cell_code(cell: &RadarCell) -> u8 {
+    if cell.is_undefined {
+        return 0b1111;
+    }
+    let item = match cell.item {
+        Item::None => 0b00,
+        Item::Hint => 0b01,
+        Item::Goal => 0b10,
+        // A reserved whole-cell code, not an item with an entity on it
+        Item::Visited => return 0b1101,
+    };
+    let entity = match cell.entity {
+        Entity::None => 0b00,
+        Entity::Ally => 0b01,
+        Entity::Enemy => 0b10,
+        Entity::Monster => 0b11,
+    };
+    (item << 2) | entity
+}
+
+/**
+ * The boundary_code function gives back the 2-bit code the server sent for a passage.
+ * Checked passages are open passages the client already went through.
+ *
+ * @param boundary: &Boundary - The decoded passage
+ * @return u8 - 0 undefined, 1 open, 2 wall, 3 error
+ */
+fn boundary_code(boundary: &Boundary) -> u8 {
+    match boundary {
+        Boundary::Undefined => 0,
+        Boundary::Open | Boundary::Checked => 1,
+        Boundary::Wall => 2,
+        Boundary::Error => 3,
+    }
 }
 
 /// The get_radar_map_as_string function generates a string representation of the radar map.<br>
 /// It takes the radar cells, horizontal passages, and vertical passages as input.<br>
-/// It constructs the map line by line, using symbols to represent the different elements:
-/// - '#' for undefined cells and passages
-/// - ' ' for defined cells and open passages
-/// - '-' for walls in horizontal passages
-/// - '|' for walls in vertical passages
-/// - '•' for joints between passages
+/// The drawing itself is shared with the server (SQP_common::radar), which can log
+/// the radar views it sends the same way. See there for the symbols used.
 /// It returns the radar map as a string.
 ///
 /// @param cells: &Vec<RadarCell> - The radar cells (9 cells)<br>
@@ -1450,114 +2596,40 @@ fn get_radar_map_as_string(
     h_passages: &[Boundary],
     v_passages: &[Boundary],
 ) -> String {
-    // Symbol mappings
-    let symbols_cells = std::collections::HashMap::from([(true, '#'), (false, ' ')]);
-
-    let joint = '•';
-
-    let symbols_passages_horizontal = std::collections::HashMap::from([
-        (Boundary::Undefined, '#'),
-        (Boundary::Open, ' '),
-        (Boundary::Wall, '-'),
-    ]);
-
-    let symboles_passages_vertical = std::collections::HashMap::from([
-        (Boundary::Undefined, '#'),
-        (Boundary::Open, ' '),
-        (Boundary::Wall, '|'),
-    ]);
-
-    let mut carte: Vec<String> = Vec::new();
-
-    // Convert v_passages to a 2D array (3x4)
-    let mut passages_verticaux = vec![vec![Boundary::Undefined; 4]; 3];
-    for i in 0..3 {
-        for j in 0..4 {
-            passages_verticaux[i][j] = v_passages[i * 4 + j].clone();
-        }
-    }
-
-    // Convert h_passages to a 2D array (4x3)
-    let mut passages_horizontaux = vec![vec![Boundary::Undefined; 3]; 4];
-    for i in 0..4 {
-        for j in 0..3 {
-            passages_horizontaux[i][j] = h_passages[i * 3 + j].clone();
-        }
-    }
-
-    // Construct the radar map line by line
-    // seven iterations for each line
-    for i in 0..7 {
-        // Line of cells
-        let mut ligne = String::new();
-
-        if i % 2 == 0 {
-            // seven iterations for each line
-            for j in 0..7 {
-                // if j is not pair check if joint char is needed '•'
-                if j % 2 != 0 {
-                    ligne.push(
-                        *symbols_passages_horizontal
-                            .get(&passages_horizontaux[i / 2][j / 2])
-                            .unwrap(),
-                    );
-                } else {
-                    // to check if joint is needed ->
-                    // if first half of the line, check the passage after, if open '•' else '#'
-                    // if second half of the line, check the passage before, if open '•' else '#'
-                    if j < 3 {
-                        ligne.push(
-                            if passages_horizontaux[i / 2][j / 2] != Boundary::Undefined
-                                || (j != 0
-                                    && passages_horizontaux[i / 2][(j - 1) / 2]
-                                        != Boundary::Undefined)
-                            {
-                                joint
-                            } else {
-                                '#'
-                            },
-                        );
-                    } else {
-                        ligne.push(
-                            if passages_horizontaux[i / 2][(j - 1) / 2] != Boundary::Undefined
-                                || (j != 6
-                                    && passages_horizontaux[i / 2][j / 2] != Boundary::Undefined)
-                            {
-                                joint
-                            } else {
-                                '#'
-                            },
-                        );
-                    }
-                }
-            }
-        } else {
-            // Line of vertical passages
-            // seven iterations for each line
-            for j in 0..7 {
-                // if j is not pair place the value of the vertical passage / 2
-                // else place the value of the cell / 2
-                if j % 2 == 0 {
-                    ligne.push(
-                        *symboles_passages_vertical
-                            .get(&passages_verticaux[(i - 1) / 2][j / 2])
-                            .unwrap(),
-                    );
-                } else {
-                    ligne.push(
-                        *symbols_cells
-                            .get(&cells[i / 2][j / 2].is_undefined)
-                            .unwrap(),
-                    );
-                }
-            }
-        }
+    let (h_codes, v_codes, cell_codes) = radar_codes(cells, h_passages, v_passages);
+    render_radar(&h_codes, &v_codes, &cell_codes)
+}
 
-        carte.push(ligne);
-    }
+/// The get_colored_radar_map_as_string function draws the radar map like get_radar_map_as_string,
+/// with ANSI colors for walls, open passages, the player, entities and items (--color).
+///
+/// @param cells: &[Vec<RadarCell>] - The radar cells (3 rows of 3)<br>
+/// @param h_passages: &[Boundary] - The horizontal passages (12 passages)<br>
+/// @param v_passages: &[Boundary] - The vertical passages (12 passages)<br>
+fn get_colored_radar_map_as_string(
+    cells: &[Vec<RadarCell>],
+    h_passages: &[Boundary],
+    v_passages: &[Boundary],
+) -> String {
+    let (h_codes, v_codes, cell_codes) = radar_codes(cells, h_passages, v_passages);
+    render_radar_colored(&h_codes, &v_codes, &cell_codes)
+}
 
-    // return map joined by return char '\n' and a return char at the end
-    carte.join("\n") + "\n"
+/**
+ * The radar_codes function turns decoded radar data back into the codes the shared renderer draws.
+ *
+ * @return (Vec<u8>, Vec<u8>, Vec<u8>) - The horizontal passage, vertical passage and cell codes
+ */
+fn radar_codes(
+    cells: &[Vec<RadarCell>],
+    h_passages: &[Boundary],
+    v_passages: &[Boundary],
+) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    (
+        h_passages.iter().map(boundary_code).collect(),
+        v_passages.iter().map(boundary_code).collect(),
+        cells.iter().flatten().map(radar_cell_code).collect(),
+    )
 }
 
 #[cfg(test)]
@@ -1626,6 +2698,61 @@ mod tests {
         assert_eq!(passages, expected);
     }
 
+    #[test]
+    fn test_radar_view_with_an_exit_sense_byte() {
+        // Sent by a server running with --sense-radius 2, the exit two cells east of a player
+        // facing north (server test test_sense_byte_gives_the_bearing_of_a_nearby_exit)
+        let response = r#"{"RadarView":"aeiaabqaaaaa8p8c"}"#;
+        let (cells, h_passages, v_passages) = parse_radar_response(response, false).unwrap();
+        assert_eq!(
+            (cells.len(), h_passages.len(), v_passages.len()),
+            (9, 12, 12)
+        );
+
+        let data = decode("aeiaabqaaaaa8p8c").unwrap();
+        assert_eq!(exit_sense_angle(&data), Some(90.0));
+        assert_eq!(exit_sense_angle(&data[..11]), None);
+    }
+
+    #[test]
+    fn test_parse_passages_of_a_server_radar_view() {
+        // Sent by the server for a corridor going east and west, the player facing north
+        // (server test test_radar_passages_round_trip_through_the_decoder)
+        let data = decode("aiiaabqaaaaaaia").unwrap();
+        use Boundary::{Open as O, Undefined as U, Wall as W};
+        assert_eq!(
+            parse_passages(&data[0..3], 12, "Horizontal"),
+            vec![U, U, U, U, W, U, U, W, U, U, U, U]
+        );
+        assert_eq!(
+            parse_passages(&data[3..6], 12, "Vertical"),
+            vec![U, U, U, U, U, O, O, U, U, U, U, U]
+        );
+
+        let h_passages = parse_passages(&data[0..3], 12, "Horizontal");
+        let v_passages = parse_passages(&data[3..6], 12, "Vertical");
+        assert!(!is_direction_open(
+            &Direction::Front,
+            &h_passages,
+            &v_passages
+        ));
+        assert!(!is_direction_open(
+            &Direction::Back,
+            &h_passages,
+            &v_passages
+        ));
+        assert!(is_direction_open(
+            &Direction::Left,
+            &h_passages,
+            &v_passages
+        ));
+        assert!(is_direction_open(
+            &Direction::Right,
+            &h_passages,
+            &v_passages
+        ));
+    }
+
     #[test]
     fn test_parse_passage_real_case() {
         //00100000 01000110 00010010
@@ -2196,4 +3323,1260 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_print_map_goal_at_center() {
+        let mut cells = vec![
+            RadarCell {
+                is_undefined: false,
+                item: Item::None,
+                entity: Entity::None,
+            };
+            9
+        ];
+        cells[4].item = Item::Goal;
+        cells[0].item = Item::Hint;
+        cells[2].entity = Entity::Monster;
+
+        let passages = vec![Boundary::Open; 12];
+        let two_d_cells: Vec<Vec<RadarCell>> =
+            cells.chunks(3).map(|chunk| chunk.to_vec()).collect();
+
+        let result = get_radar_map_as_string(&two_d_cells, &passages, &passages);
+        let lines: Vec<&str> = result.lines().collect();
+
+        // The center cell is on the fourth line, fourth column.
+        assert_eq!(lines[3].chars().nth(3), Some('G'));
+        assert_eq!(lines[1].chars().nth(1), Some('H'));
+        assert_eq!(lines[1].chars().nth(5), Some('M'));
+    }
+
+    #[test]
+    fn test_split_radar_and_hint_combined() {
+        let response =
+            r#"{"RadarView":"beeqkcGO8p8p8pa","Hint":{"RelativeCompass":{"angle":90.0}}}"#;
+        let (radar_view, hint) = split_radar_and_hint(response);
+        assert_eq!(radar_view, Some("beeqkcGO8p8p8pa".to_string()));
+        assert_eq!(hint.unwrap()["RelativeCompass"]["angle"], 90.0);
+
+        // The combined message is parsed as a regular radar view
+        let (cells, horizontal_passages, vertical_passages) =
+            parse_radar_response(response, false).unwrap();
+        assert_eq!(cells.len(), 9);
+        assert_eq!(horizontal_passages.len(), 12);
+        assert_eq!(vertical_passages.len(), 12);
+    }
+
+    #[test]
+    fn test_split_radar_and_hint_separate_messages() {
+        let (radar_view, hint) = split_radar_and_hint(r#"{"RadarView":"beeqkcGO8p8p8pa"}"#);
+        assert_eq!(radar_view, Some("beeqkcGO8p8p8pa".to_string()));
+        assert!(hint.is_none());
+
+        let (radar_view, hint) = split_radar_and_hint(r#"{"Hint":{"Secret":42}}"#);
+        assert!(radar_view.is_none());
+        assert_eq!(hint.unwrap()["Secret"], 42);
+    }
+
+    #[test]
+    fn test_radar_view_containing_hint_is_not_a_hint() {
+        let response = r#"{"RadarView":"Hintkcap8p8p8pa","seq":3}"#;
+        assert_eq!(
+            ServerMessage::parse(response),
+            ServerMessage::RadarView {
+                view: "Hintkcap8p8p8pa".to_string(),
+                hint: None,
+            }
+        );
+        assert!(ServerMessage::parse(response).hint().is_none());
+
+        let (cells, horizontal_passages, vertical_passages) =
+            parse_radar_response(response, false).unwrap();
+        assert_eq!(cells.len(), 9);
+        assert_eq!(horizontal_passages.len(), 12);
+        assert_eq!(vertical_passages.len(), 12);
+    }
+
+    #[test]
+    fn test_malformed_radar_views_are_errors() {
+        for response in [
+            // Truncated base64
+            r#"{"RadarView":"beeqkcGO8p"}"#,
+            r#"{"RadarView":"beeqkcGO8p8p8p"}"#,
+            r#"{"RadarView":""}"#,
+            r#"{"RadarView":"beeqkc!O8p8p8pa"}"#,
+            // No RadarView key
+            r#"{"seq":3,"facing":"North"}"#,
+            "not json",
+        ] {
+            assert!(
+                matches!(
+                    parse_radar_response(response, false),
+                    Err(Error::Player(PlayerError::InvalidRadarData))
+                ),
+                "{}",
+                response
+            );
+            assert!(RadarCache::default().parse(response, false).is_err());
+        }
+
+        // Wall and exit responses are not errors, they just have no radar view
+        let (cells, _, _) = parse_radar_response(r#"{"FoundExit":true}"#, false).unwrap();
+        assert!(cells.is_empty());
+        let parsed = parse_radar_response(r#"{"CannotPassThroughWall":true}"#, false).unwrap();
+        assert_eq!(radar_map(&parsed).len(), 3);
+    }
+
+    #[test]
+    fn test_server_messages_are_told_apart_by_their_fields() {
+        assert_eq!(
+            ServerMessage::parse(r#"{"Hint":{"Secret":42}}"#),
+            ServerMessage::Hint(json!({"Secret": 42}))
+        );
+        assert_eq!(
+            ServerMessage::parse(r#"{"Challenge":{"SecretSumModulo":7}}"#),
+            ServerMessage::Challenge(json!({"SecretSumModulo": 7}))
+        );
+        assert_eq!(
+            ServerMessage::parse(r#"{"FoundExit":true}"#),
+            ServerMessage::FoundExit
+        );
+        assert_eq!(
+            ServerMessage::parse(r#"{"CannotPassThroughWall":true}"#),
+            ServerMessage::CannotPassThroughWall
+        );
+        assert_eq!(ServerMessage::parse("FoundExit"), ServerMessage::Other);
+
+        let combined = ServerMessage::parse(
+            r#"{"RadarView":"beeqkcGO8p8p8pa","Hint":{"RelativeCompass":{"angle":90.0}}}"#,
+        );
+        assert!(matches!(combined, ServerMessage::RadarView { .. }));
+        assert_eq!(combined.hint().unwrap()["RelativeCompass"]["angle"], 90.0);
+    }
+
+    #[test]
+    fn test_minimap_rendered_at_interval() {
+        let rendered_at: Vec<u64> = (1..=10)
+            .filter(|&moves| should_render_minimap(Some(3), moves))
+            .collect();
+        assert_eq!(rendered_at, vec![3, 6, 9]);
+
+        // Disabled mini-map is never rendered
+        assert!(!(1..=10).any(|moves| should_render_minimap(None, moves)));
+    }
+
+    #[test]
+    fn test_minimap_shows_player_and_compass() {
+        let passages = vec![Boundary::Open; 12];
+        let map = make_map_with_passages(&passages, &passages);
+        let minimap = get_minimap_as_string(&map, &MapDirection::East);
+
+        assert!(minimap.starts_with("Compass: facing East\n"));
+        assert_eq!(minimap.matches('P').count(), 1);
+    }
+
+    #[test]
+    fn test_repeated_wall_hits_force_a_turn() {
+        let mut h_passages = vec![Boundary::Open; 12];
+        let mut v_passages = vec![Boundary::Open; 12];
+        let mut wall_hits = WallHitTracker::new(WALL_HIT_THRESHOLD);
+
+        // The passage we bumped into is marked as a wall
+        let forced = record_wall_hit(
+            &Direction::Right,
+            &mut h_passages,
+            &mut v_passages,
+            &mut wall_hits,
+        );
+        assert!(forced.is_none());
+        assert!(!is_direction_open(
+            &Direction::Right,
+            &h_passages,
+            &v_passages
+        ));
+        assert!(is_direction_open(
+            &Direction::Front,
+            &h_passages,
+            &v_passages
+        ));
+
+        // A stale radar keeps showing the passage open: hit it until the threshold
+        let mut forced = None;
+        for _ in 1..WALL_HIT_THRESHOLD {
+            v_passages[6] = Boundary::Open;
+            forced = record_wall_hit(
+                &Direction::Right,
+                &mut h_passages,
+                &mut v_passages,
+                &mut wall_hits,
+            );
+        }
+        assert_eq!(forced.as_ref(), Some(&Direction::Front));
+
+        // The count starts over after the forced turn or a hit in another direction
+        assert!(wall_hits.record_hit(&Direction::Right).is_none());
+        assert!(wall_hits.record_hit(&Direction::Front).is_none());
+        assert!(wall_hits.record_hit(&Direction::Right).is_none());
+    }
+
+    #[test]
+    fn test_noisy_radar_with_error_passages() {
+        // Every passage is the 0b11 error code, except the one in front of the player.
+        // Parsing also draws the radar, which must not panic on them.
+        let (_, h_passages, v_passages) =
+            parse_radar_response(r#"{"RadarView":"/3//////aaaaaaa"}"#, false).unwrap();
+
+        assert_eq!(h_passages[4], Boundary::Open);
+        assert_eq!(
+            h_passages.iter().filter(|p| **p == Boundary::Error).count(),
+            11
+        );
+        assert!(v_passages.iter().all(|p| *p == Boundary::Error));
+
+        // Error passages are never taken, the open one still is
+        assert!(is_direction_open(
+            &Direction::Front,
+            &h_passages,
+            &v_passages
+        ));
+        assert!(!is_direction_open(
+            &Direction::Right,
+            &h_passages,
+            &v_passages
+        ));
+        assert!(!is_direction_open(
+            &Direction::Left,
+            &h_passages,
+            &v_passages
+        ));
+        assert!(!is_direction_open(
+            &Direction::Back,
+            &h_passages,
+            &v_passages
+        ));
+
+        // and the discovered map shows them as unknown
+        let map = make_map_with_passages(&h_passages, &v_passages);
+        assert!(get_map_as_string(&map).contains('*'));
+    }
+
+    #[test]
+    fn test_relative_dir() {
+        let at = |position_x, position_y| Coordinates {
+            position_x,
+            position_y,
+        };
+        let from = at(1, 1);
+
+        assert_eq!(relative_dir(&from, &at(0, 1)), Some(MapDirection::North));
+        assert_eq!(relative_dir(&from, &at(2, 1)), Some(MapDirection::South));
+        assert_eq!(relative_dir(&from, &at(1, 2)), Some(MapDirection::East));
+        assert_eq!(relative_dir(&from, &at(1, 0)), Some(MapDirection::West));
+
+        // Same cell, diagonal and distant cells are not adjacent
+        assert_eq!(relative_dir(&from, &at(1, 1)), None);
+        assert_eq!(relative_dir(&from, &at(0, 0)), None);
+        assert_eq!(relative_dir(&from, &at(1, 3)), None);
+    }
+
+    #[test]
+    fn test_radar_ascii_matches_server_rendering() {
+        // Radar views sent by the server: a known maze seen from (1, 1), and one with entities
+        for radar in ["fvvvvvvvaeaaGaa", "beeqkcGO8p8p8pa"] {
+            let data = decode(radar).unwrap();
+            let horizontal_passages = parse_passages(&data[0..3], 12, "Horizontal");
+            let vertical_passages = parse_passages(&data[3..6], 12, "Vertical");
+            let cells = parse_cells(&data[6..11]).unwrap();
+            let two_d_cells: Vec<Vec<RadarCell>> =
+                cells.chunks(3).map(|chunk| chunk.to_vec()).collect();
+
+            assert_eq!(
+                get_radar_map_as_string(&two_d_cells, &horizontal_passages, &vertical_passages),
+                SQP_common::radar::render_radar_bytes(&data).unwrap()
+            );
+        }
+    }
+
+    fn map_cell(north: Boundary, east: Boundary, south: Boundary, west: Boundary) -> MapCell {
+        MapCell {
+            north,
+            east,
+            south,
+            west,
+            is_player_here: false,
+        }
+    }
+
+    #[test]
+    fn test_explore_prefers_unexplored_cells() {
+        use Boundary::{Checked, Open, Undefined, Wall};
+        let unexplored = map_cell(Undefined, Undefined, Undefined, Undefined);
+        let mut map = vec![vec![unexplored; 3]; 3];
+        // The player came from the north and sees open passages to the east and south
+        map[0][1] = map_cell(Wall, Wall, Checked, Wall);
+        map[1][1] = map_cell(Checked, Open, Open, Wall);
+        map[1][1].is_player_here = true;
+        map[2][1] = map_cell(Open, Wall, Wall, Wall);
+
+        // Only the cell to the east is still unexplored
+        assert_eq!(choose_explore_direction(&map), Some(MapDirection::East));
+
+        // Everything explored: open passages before checked ones
+        map[1][2] = map_cell(Wall, Wall, Wall, Open);
+        assert_eq!(choose_explore_direction(&map), Some(MapDirection::East));
+        map[1][1].east = Checked;
+        assert_eq!(choose_explore_direction(&map), Some(MapDirection::South));
+
+        // Walled in
+        map[1][1] = map_cell(Wall, Wall, Wall, Wall);
+        map[1][1].is_player_here = true;
+        assert_eq!(choose_explore_direction(&map), None);
+    }
+
+    #[test]
+    fn test_dead_end_and_corridor_cells() {
+        use Boundary::{Checked, Open, Wall};
+        let dead_end = map_cell(Wall, Wall, Checked, Wall);
+        let corridor = map_cell(Open, Wall, Checked, Wall);
+        assert_eq!(dead_end.open_count(), 1);
+        assert_eq!(corridor.open_count(), 2);
+
+        let map = vec![vec![dead_end], vec![corridor]];
+        let at = |row: usize| Coordinates {
+            position_x: row,
+            position_y: 0,
+        };
+        assert!(is_dead_end(&map, &at(0)));
+        assert!(!is_dead_end(&map, &at(1)));
+        assert!(!is_dead_end(&map, &at(2)));
+    }
+
+    #[test]
+    fn test_smart_solver_avoids_explored_dead_ends() {
+        use Boundary::{Open, Undefined, Wall};
+        let unexplored = map_cell(Undefined, Undefined, Undefined, Undefined);
+        let mut map = vec![vec![unexplored; 3]; 3];
+        map[1][1] = map_cell(Open, Wall, Open, Wall);
+        map[1][1].is_player_here = true;
+        // North leads into a dead end whose walls are all known, south is still unknown
+        map[0][1] = map_cell(Wall, Wall, Open, Wall);
+
+        let next_direction = find_closest_open(&mut map, Vec::new(), 0);
+        assert_eq!(next_direction.direction, MapDirection::South);
+    }
+
+    #[test]
+    fn test_shortest_path_to_the_nearest_frontier() {
+        use Boundary::{Checked, Open, Undefined, Wall};
+        use MapDirection::{East, North, South, West};
+        let mut map = vec![vec![map_cell(Wall, Wall, Wall, Wall); 5]; 5];
+        map[2][2] = map_cell(Checked, Wall, Undefined, Checked);
+        map[2][2].is_player_here = true;
+        // Three cells away: north, north, east, then the open passage to the east
+        map[1][2] = map_cell(Checked, Wall, Checked, Wall);
+        map[0][2] = map_cell(Wall, Checked, Checked, Wall);
+        map[0][3] = map_cell(Wall, Open, Wall, Checked);
+        // Four cells away: west, west, south, south, then the open passage to the east
+        map[2][1] = map_cell(Wall, Checked, Wall, Checked);
+        map[2][0] = map_cell(Wall, Checked, Checked, Wall);
+        map[3][0] = map_cell(Checked, Wall, Checked, Wall);
+        map[4][0] = map_cell(Checked, Open, Wall, Wall);
+        // Next to the player, but behind an undefined boundary
+        map[3][2] = map_cell(Undefined, Open, Wall, Wall);
+
+        assert_eq!(
+            shortest_path_to_frontier(&map),
+            Some(vec![North, North, East, East])
+        );
+
+        // The northern frontier explored: the western one is the nearest left
+        map[0][3].east = Checked;
+        assert_eq!(
+            shortest_path_to_frontier(&map),
+            Some(vec![West, West, South, South, East])
+        );
+
+        // An open passage of the player's own cell is taken right away
+        map[2][2].east = Open;
+        assert_eq!(shortest_path_to_frontier(&map), Some(vec![East]));
+
+        // Walled in
+        map[2][2] = map_cell(Wall, Wall, Undefined, Wall);
+        map[2][2].is_player_here = true;
+        assert_eq!(shortest_path_to_frontier(&map), None);
+    }
+
+    #[test]
+    fn test_shortest_path_avoids_explored_dead_ends() {
+        use Boundary::{Open, Undefined, Wall};
+        let unexplored = map_cell(Undefined, Undefined, Undefined, Undefined);
+        let mut map = vec![vec![unexplored; 3]; 3];
+        map[1][1] = map_cell(Open, Wall, Open, Wall);
+        map[1][1].is_player_here = true;
+        map[0][1] = map_cell(Wall, Wall, Open, Wall);
+
+        assert_eq!(
+            shortest_path_to_frontier(&map),
+            Some(vec![MapDirection::South])
+        );
+    }
+
+    #[test]
+    fn test_colored_radar_has_reset_codes() {
+        let data = decode("beeqkcGO8p8p8pa").unwrap();
+        let horizontal_passages = parse_passages(&data[0..3], 12, "Horizontal");
+        let vertical_passages = parse_passages(&data[3..6], 12, "Vertical");
+        let cells = parse_cells(&data[6..11]).unwrap();
+        let two_d_cells: Vec<Vec<RadarCell>> =
+            cells.chunks(3).map(|chunk| chunk.to_vec()).collect();
+
+        let plain = get_radar_map_as_string(&two_d_cells, &horizontal_passages, &vertical_passages);
+        let colored =
+            get_colored_radar_map_as_string(&two_d_cells, &horizontal_passages, &vertical_passages);
+        assert!(colored.contains("\x1b[0m"));
+        assert!(!plain.contains("\x1b["));
+    }
+
+    #[test]
+    fn test_radar_cache_decodes_each_view_once() {
+        let mut cache = RadarCache::default();
+        let response = r#"{"RadarView":"ieysGjGO8papd/a"}"#;
+
+        let first = cache.parse(response, false).unwrap();
+        let second = cache.parse(response, false).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.decodes, 1);
+
+        // Same radar view with a seq number: still a hit
+        cache
+            .parse(r#"{"RadarView":"ieysGjGO8papd/a","seq":3}"#, false)
+            .unwrap();
+        assert_eq!(cache.decodes, 1);
+
+        cache
+            .parse(r#"{"RadarView":"beeqkcGO8p8p8pa"}"#, false)
+            .unwrap();
+        assert_eq!(cache.decodes, 2);
+    }
+
+    #[test]
+    fn test_parse_cells_visited() {
+        // Cells 0b1101 (visited), 0b0000 and 0b1100 (collapsed), then undefined, with the padding
+        let data = [0b1101_0000, 0b1100_1111, 0xFF, 0xFF, 0xF0];
+        let cells = parse_cells(&data).unwrap();
+        assert_eq!(cells[0].item, Item::Visited);
+        assert_eq!(cells[0].entity, Entity::None);
+        assert_eq!(cells[1].item, Item::None);
+        assert_eq!(cells[2].item, Item::None);
+        assert!(cells[3].is_undefined);
+        assert_eq!(radar_cell_code(&cells[0]), 0b1101);
+    }
+
+    #[test]
+    fn test_coordinates_neighbor() {
+        let center = Coordinates {
+            position_x: 2,
+            position_y: 5,
+        };
+        let at = |position_x, position_y| {
+            Some(Coordinates {
+                position_x,
+                position_y,
+            })
+        };
+        assert_eq!(center.neighbor(MapDirection::North), at(1, 5));
+        assert_eq!(center.neighbor(MapDirection::East), at(2, 6));
+        assert_eq!(center.neighbor(MapDirection::South), at(3, 5));
+        assert_eq!(center.neighbor(MapDirection::West), at(2, 4));
+    }
+
+    #[test]
+    fn test_coordinates_neighbor_at_origin() {
+        let origin = Coordinates {
+            position_x: 0,
+            position_y: 0,
+        };
+        assert_eq!(origin.neighbor(MapDirection::North), None);
+        assert_eq!(origin.neighbor(MapDirection::West), None);
+        assert!(origin.neighbor(MapDirection::South).is_some());
+    }
+
+    #[test]
+    fn test_simple_direction_turns_toward_visible_goal() {
+        let open = vec![Boundary::Open; 12];
+        let mut cells = vec![
+            RadarCell {
+                is_undefined: false,
+                item: Item::None,
+                entity: Entity::None,
+            };
+            9
+        ];
+        assert_eq!(
+            decide_simple_direction(&cells, &open, &open, true, false)
+                .chosen
+                .as_ref(),
+            Some(&Direction::Right)
+        );
+
+        // Exit on the left: the right-hand rule would go right
+        cells[3].item = Item::Goal;
+        assert_eq!(
+            decide_simple_direction(&cells, &open, &open, false, false)
+                .chosen
+                .as_ref(),
+            Some(&Direction::Right)
+        );
+        assert_eq!(
+            decide_simple_direction(&cells, &open, &open, true, false)
+                .chosen
+                .as_ref(),
+            Some(&Direction::Left)
+        );
+
+        // Exit on the right
+        cells[3].item = Item::None;
+        cells[5].item = Item::Goal;
+        assert_eq!(
+            decide_simple_direction(&cells, &open, &open, true, false)
+                .chosen
+                .as_ref(),
+            Some(&Direction::Right)
+        );
+
+        // A wall between the player and the exit: back to the right-hand rule
+        let mut v_passages = open.clone();
+        v_passages[6] = Boundary::Wall;
+        assert_eq!(
+            decide_simple_direction(&cells, &open, &v_passages, true, false)
+                .chosen
+                .as_ref(),
+            Some(&Direction::Front)
+        );
+    }
+
+    #[test]
+    fn test_parse_cells_checks_length() {
+        // Open cell in the center, undefined around it
+        let data = [0xFF, 0xFF, 0x0F, 0xFF, 0xF0];
+        let cells = parse_cells(&data).unwrap();
+        assert_eq!(cells.len(), 9);
+        assert!(cells[3].is_undefined);
+        assert!(!cells[4].is_undefined);
+        assert_eq!(cells[4].item, Item::None);
+
+        assert_eq!(
+            parse_cells(&data[..4]),
+            Err(DecodeError::InvalidSize.into())
+        );
+    }
+
+    #[test]
+    fn test_parse_cells_hint_with_ally() {
+        // Open cell in the center, a hint with an ally (0b0101) east of it
+        let data = [0xFF, 0xFF, 0x05, 0xFF, 0xF0];
+        let cells = parse_cells(&data).unwrap();
+        assert_eq!(cells[5].item, Item::Hint);
+        assert_eq!(cells[5].entity, Entity::Ally);
+        assert_eq!(cells[4].item, Item::None);
+        assert_eq!(cells[4].entity, Entity::None);
+    }
+
+    #[test]
+    fn test_relayed_secret_is_stored() {
+        use std::collections::HashMap;
+        use std::sync::{Arc, RwLock};
+
+        let log_dir = std::env::temp_dir().join("sqp-client-test-log");
+        SQP_common::logger::init_logging(log_dir.to_str().unwrap(), &["server_message"]).unwrap();
+        let map = SECRET_MAP.get_or_init(|| Arc::new(RwLock::new(HashMap::new())));
+
+        // A secret relayed before the radar view is stored and skipped
+        let mut stream = Vec::new();
+        send_message(
+            &mut stream,
+            &json!({"ShareSecret": {"player": "Relayed Paul", "secret": 42}}),
+        )
+        .unwrap();
+        send_message(&mut stream, &json!({"RadarView": "ieysGjGO8papd/a"})).unwrap();
+
+        let message = receive_game_message(&mut std::io::Cursor::new(stream)).unwrap();
+        assert_eq!(message, r#"{"RadarView":"ieysGjGO8papd/a"}"#);
+        assert_eq!(map.read().unwrap().get("Relayed Paul"), Some(&42));
+
+        assert!(!store_shared_secret(r#"{"Hint":{"Secret":7}}"#).unwrap());
+    }
+
+    #[test]
+    fn test_pushed_radar_view_is_skipped() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"])
+            .unwrap();
+
+        // A view pushed after a ToggleWall comes before the answer to the action
+        let mut stream = Vec::new();
+        send_message(
+            &mut stream,
+            &json!({"RadarView": "bKBhGjGO8p8pa8a", "facing": "North", "pushed": true}),
+        )
+        .unwrap();
+        send_message(&mut stream, &json!({"RadarView": "ieysGjGO8papd/a"})).unwrap();
+
+        let message = receive_game_message(&mut std::io::Cursor::new(stream)).unwrap();
+        assert_eq!(message, r#"{"RadarView":"ieysGjGO8papd/a"}"#);
+
+        assert!(!is_pushed_radar_view(
+            r#"{"RadarView":"ieysGjGO8papd/a","seq":3}"#
+        ));
+        assert!(!is_pushed_radar_view(
+            r#"{"Hint":{"Secret":7},"pushed":true}"#
+        ));
+    }
+
+    #[test]
+    fn test_explained_decision_names_open_and_chosen_directions() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(log_dir.path().to_str().unwrap(), &["decision"]).unwrap();
+
+        // Open to the front and the left only, the exit on the left
+        let mut h_passages = vec![Boundary::Wall; 12];
+        let mut v_passages = vec![Boundary::Wall; 12];
+        h_passages[passage_index(&Direction::Front)] = Boundary::Open;
+        v_passages[passage_index(&Direction::Left)] = Boundary::Open;
+        let mut cells = vec![
+            RadarCell {
+                is_undefined: false,
+                item: Item::None,
+                entity: Entity::None,
+            };
+            9
+        ];
+        cells[3].item = Item::Goal;
+
+        // The decision log may belong to another test, so the lines written are checked instead
+        let lines: Vec<String> = [false, true]
+            .into_iter()
+            .map(|seek_goal| {
+                let decision =
+                    decide_simple_direction(&cells, &h_passages, &v_passages, seek_goal, false);
+                explain_decision("Ada", &decision, false).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "Player Ada open [Front, Left], scores [Front=1, Left=2], \
+                 chose Some(Front) (right-hand rule)",
+                "Player Ada open [Front, Left], scores [Front=2, Left=0], \
+                 chose Some(Left) (exit in sight)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_solve_challenge() {
+        assert_eq!(
+            solve_challenge(&json!({"SecretSumModulo": 10}), &[7, 8]),
+            Ok("5".to_string())
+        );
+        assert_eq!(
+            solve_challenge(&json!({"ReverseWord": "labyrinth"}), &[7, 8]),
+            Ok("htnirybal".to_string())
+        );
+        assert!(solve_challenge(&json!({"SOS": null}), &[]).is_err());
+        assert!(solve_challenge(&json!({"Modulo": 0}), &[1]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_string_challenge_sends_the_answer() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(
+            log_dir.path().to_str().unwrap(),
+            &["challenge", "server_message"],
+        )
+        .unwrap();
+
+        let mut stream = Vec::new();
+        let challenge = r#"{"Challenge":{"ReverseWord":"Sauve"}}"#.to_string();
+        resolve_challenge(&"Ada".to_string(), &mut stream, &challenge).unwrap();
+
+        let sent = receive_message(&mut std::io::Cursor::new(stream)).unwrap();
+        assert_eq!(sent, r#"{"Action":{"SolveChallenge":{"answer":"evuaS"}}}"#);
+    }
+
+    #[test]
+    fn test_goal_direction_for_each_surrounding_cell() {
+        let empty = RadarCell {
+            is_undefined: false,
+            item: Item::None,
+            entity: Entity::None,
+        };
+        let open = vec![Boundary::Open; 12];
+        assert!(goal_direction(&vec![empty.clone(); 9], &open, &open).is_none());
+
+        // Cells row by row, the front being the top row
+        let expected = [
+            (0, Direction::Front),
+            (1, Direction::Front),
+            (2, Direction::Front),
+            (3, Direction::Left),
+            (5, Direction::Right),
+            (6, Direction::Back),
+            (7, Direction::Back),
+            (8, Direction::Right),
+        ];
+        for (index, direction) in expected {
+            let mut cells = vec![empty.clone(); 9];
+            cells[index].item = Item::Goal;
+            assert_eq!(
+                goal_direction(&cells, &open, &open).as_ref(),
+                Some(&direction),
+                "goal in cell {}",
+                index
+            );
+        }
+
+        // An exit in a corner behind a wall is reached the other way round, or not at all
+        let mut cells = vec![empty.clone(); 9];
+        cells[0].item = Item::Goal;
+        let mut v_passages = open.clone();
+        v_passages[1] = Boundary::Wall;
+        assert_eq!(
+            goal_direction(&cells, &open, &v_passages).as_ref(),
+            Some(&Direction::Left)
+        );
+        let mut h_passages = open.clone();
+        h_passages[3] = Boundary::Wall;
+        assert!(goal_direction(&cells, &h_passages, &v_passages).is_none());
+
+        // The player already stands on the exit
+        let mut cells = vec![empty; 9];
+        cells[4].item = Item::Goal;
+        assert!(goal_direction(&cells, &open, &open).is_none());
+    }
+
+    #[test]
+    fn test_seek_goal_heads_for_an_exit_in_a_corner() {
+        let open = vec![Boundary::Open; 12];
+        let mut cells = vec![
+            RadarCell {
+                is_undefined: false,
+                item: Item::None,
+                entity: Entity::None,
+            };
+            9
+        ];
+        cells[6].item = Item::Goal;
+
+        let decision = decide_simple_direction(&cells, &open, &open, true, false);
+        assert_eq!(decision.chosen.as_ref(), Some(&Direction::Back));
+        assert_eq!(decision.reason, DecisionReason::ExitInSight);
+
+        // Without --seek-goal the right-hand rule is kept
+        let decision = decide_simple_direction(&cells, &open, &open, false, false);
+        assert_eq!(decision.chosen.as_ref(), Some(&Direction::Right));
+    }
+
+    #[test]
+    fn test_right_hand_rule_is_turned_around_when_walking_back() {
+        let open = vec![Boundary::Open; 12];
+        let cells = vec![
+            RadarCell {
+                is_undefined: false,
+                item: Item::None,
+                entity: Entity::None,
+            };
+            9
+        ];
+
+        // After a Back move, the right hand of the player is on the left of its facing
+        let decision = decide_simple_direction(&cells, &open, &open, false, true);
+        assert_eq!(decision.chosen.as_ref(), Some(&Direction::Left));
+
+        // In a corridor, it goes on walking back instead of returning the way it came
+        let mut v_passages = open.clone();
+        v_passages[passage_index(&Direction::Left)] = Boundary::Wall;
+        v_passages[passage_index(&Direction::Right)] = Boundary::Wall;
+        let decision = decide_simple_direction(&cells, &open, &v_passages, false, true);
+        assert_eq!(decision.chosen.as_ref(), Some(&Direction::Back));
+        let decision = decide_simple_direction(&cells, &open, &v_passages, false, false);
+        assert_eq!(decision.chosen.as_ref(), Some(&Direction::Front));
+    }
+
+    #[test]
+    fn test_hint_direction_detours_to_visible_hint() {
+        let open = vec![Boundary::Open; 12];
+        let mut cells = vec![
+            RadarCell {
+                is_undefined: false,
+                item: Item::None,
+                entity: Entity::None,
+            };
+            9
+        ];
+        assert!(hint_direction(&cells, &open, &open).is_none());
+
+        // Hint one cell ahead: the right-hand rule would go right, the detour goes onto it
+        cells[1].item = Item::Hint;
+        assert_eq!(
+            decide_simple_direction(&cells, &open, &open, false, false)
+                .chosen
+                .as_ref(),
+            Some(&Direction::Right)
+        );
+        assert_eq!(
+            hint_direction(&cells, &open, &open).as_ref(),
+            Some(&Direction::Front)
+        );
+
+        // A wall in front: no detour
+        let mut h_passages = open.clone();
+        h_passages[passage_index(&Direction::Front)] = Boundary::Wall;
+        assert!(hint_direction(&cells, &h_passages, &open).is_none());
+
+        // Hint in the back-left corner, the passage behind closed: through the left cell
+        cells[1].item = Item::None;
+        cells[6].item = Item::Hint;
+        let mut h_passages = open.clone();
+        h_passages[passage_index(&Direction::Back)] = Boundary::Wall;
+        assert_eq!(
+            hint_direction(&cells, &h_passages, &open).as_ref(),
+            Some(&Direction::Left)
+        );
+        // The passage between the left cell and the corner closed too: out of reach
+        h_passages[2 * 3] = Boundary::Wall;
+        assert!(hint_direction(&cells, &h_passages, &open).is_none());
+    }
+
+    #[test]
+    fn test_refused_subscription_returns_the_server_reason() {
+        use std::net::TcpListener;
+
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"])
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            receive_message(&mut stream).unwrap();
+            send_message(
+                &mut stream,
+                &json!({"SubscribePlayerResult": {"Error": "Invalid registration token"}}),
+            )
+            .unwrap();
+            // The player closes the connection instead of waiting for a radar view
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let mut buffer = [0; 1];
+            assert_eq!(stream.read(&mut buffer).unwrap(), 0);
+        });
+
+        let config = crate::config::parse_args(&["worker".to_string(), address]).unwrap();
+        let result =
+            start_player_thread("Nino".to_string(), "0123456789abcdef".to_string(), config);
+        assert_eq!(
+            result,
+            Err(PlayerError::SubscriptionFailed("Invalid registration token".to_string()).into())
+        );
+        server.join().unwrap();
+
+        assert_eq!(
+            parse_subscription_result(r#"{"SubscribePlayerResult":"Ok"}"#),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_radar_view_before_the_subscription_result_is_accepted() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"])
+            .unwrap();
+        let script = |messages: &[serde_json::Value]| {
+            let mut responses = Vec::new();
+            for message in messages {
+                send_message(&mut responses, message).unwrap();
+            }
+            std::io::Cursor::new(responses)
+        };
+        let radar = json!({"RadarView": "aeeaabqaaaaaaia"});
+        let subscribed = json!({"SubscribePlayerResult": "Ok"});
+
+        for order in [
+            [radar.clone(), subscribed.clone()],
+            [subscribed.clone(), radar.clone()],
+        ] {
+            let response = receive_subscription("Nino", &mut script(&order)).unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&response).unwrap(),
+                radar
+            );
+        }
+
+        // A refusal read after the radar view still ends the player
+        let refused = json!({"SubscribePlayerResult": {"Error": "team full"}});
+        assert_eq!(
+            receive_subscription("Nino", &mut script(&[radar, refused])),
+            Err(PlayerError::SubscriptionFailed("team full".to_string()).into())
+        );
+    }
+
+    /// A player connection replaying the server responses of a recorded game
+    /// and keeping everything the player sends.
+    struct ScriptedStream {
+        responses: std::io::Cursor<Vec<u8>>,
+        sent: Vec<u8>,
+    }
+
+    impl Read for ScriptedStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.responses.read(buf)
+        }
+    }
+
+    impl Write for ScriptedStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.sent.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Replays `tests/fixtures/<name>.jsonl`, the server responses of a recorded game (the
+    /// first one being the radar view after subscribing), to the simple strategy.
+    /// Returns how the game ended and the directions the player sent, in order.
+    fn replay_fixture(name: &str, config: &ClientConfig) -> (SolveResult, Vec<String>) {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let script = std::fs::read_to_string(fixtures.join(format!("{}.jsonl", name))).unwrap();
+        let lines: Vec<&str> = script.lines().collect();
+        replay_responses(lines[0], &lines[1..], config)
+    }
+
+    /// Replays the given server responses to the strategy of the config, starting from the given radar view.
+    /// Returns how the game ended and the directions the player sent, in order.
+    fn replay_responses(
+        initial_radar_response: &str,
+        lines: &[&str],
+        config: &ClientConfig,
+    ) -> (SolveResult, Vec<String>) {
+        let mut responses = Vec::new();
+        for line in lines {
+            let response: serde_json::Value = serde_json::from_str(line).unwrap();
+            send_message(&mut responses, &response).unwrap();
+        }
+        let mut stream = ScriptedStream {
+            responses: std::io::Cursor::new(responses),
+            sent: Vec::new(),
+        };
+
+        let initial_radar_response = initial_radar_response.to_string();
+        let result = match config.strategy {
+            Strategy::Simple => search_for_exit(
+                "Nino".to_string(),
+                &mut stream,
+                initial_radar_response,
+                config,
+            ),
+            _ => search_for_exit_smart(
+                "Nino".to_string(),
+                &mut stream,
+                initial_radar_response,
+                config,
+            ),
+        }
+        .unwrap();
+
+        let mut sent = std::io::Cursor::new(stream.sent);
+        let mut directions = Vec::new();
+        while let Ok(message) = receive_message(&mut sent) {
+            let message: serde_json::Value = serde_json::from_str(&message).unwrap();
+            if let Some(direction) = message["Action"]["MoveTo"].as_str() {
+                directions.push(direction.to_string());
+            }
+        }
+        (result, directions)
+    }
+
+    /// Plays by hand with the given commands against the given server responses.
+    /// Returns how the game ended and the directions the player sent, in order.
+    fn play_by_hand_with(commands: &str, responses: &[&str]) -> (SolveResult, Vec<String>) {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(log_dir.path().to_str().unwrap(), &["server_message"])
+            .unwrap();
+        let mut scripted = Vec::new();
+        for response in responses {
+            let response: serde_json::Value = serde_json::from_str(response).unwrap();
+            send_message(&mut scripted, &response).unwrap();
+        }
+        let mut stream = ScriptedStream {
+            responses: std::io::Cursor::new(scripted),
+            sent: Vec::new(),
+        };
+        let config =
+            crate::config::parse_args(&["worker".to_string(), "127.0.0.1:8778".to_string()])
+                .unwrap();
+
+        let result = play_by_hand(
+            "Nino".to_string(),
+            &mut stream,
+            r#"{"RadarView":"beeqkcGO8p8p8pa"}"#.to_string(),
+            std::io::Cursor::new(commands),
+            &config,
+        )
+        .unwrap();
+
+        let mut sent = std::io::Cursor::new(stream.sent);
+        let mut directions = Vec::new();
+        while let Ok(message) = receive_message(&mut sent) {
+            let message: serde_json::Value = serde_json::from_str(&message).unwrap();
+            directions.push(message["Action"]["MoveTo"].as_str().unwrap().to_string());
+        }
+        (result, directions)
+    }
+
+    #[test]
+    fn test_play_by_hand_sends_the_moves_typed_and_quits() {
+        let (result, directions) = play_by_hand_with(
+            "f\nup\n\nR\nback\nq\nl\n",
+            &[
+                r#"{"RadarView":"beeqkcGO8p8p8pa"}"#,
+                // A wall hit comes with the radar view of the cell the player stayed in
+                r#"{"CannotPassThroughWall":true}"#,
+                r#"{"RadarView":"beeqkcGO8p8p8pa"}"#,
+                r#"{"RadarView":"ieysGjGO8papd/a"}"#,
+            ],
+        );
+        // "up" and the empty line are not moves, nothing is read after "q"
+        assert_eq!(directions, ["Front", "Right", "Back"]);
+        assert_eq!(result, SolveResult::end(3, EndReason::Quit));
+    }
+
+    #[test]
+    fn test_play_by_hand_ends_with_the_input_or_the_exit() {
+        let (result, directions) = play_by_hand_with("", &[]);
+        assert!(directions.is_empty());
+        assert_eq!(result, SolveResult::end(0, EndReason::Quit));
+
+        let (result, directions) = play_by_hand_with("l\nf\n", &[r#"{"FoundExit":true}"#]);
+        assert_eq!(directions, ["Left"]);
+        assert!(result.found_exit);
+
+        // The radar view sent with a wall hit is not taken for the answer to the next move
+        let (result, directions) = play_by_hand_with(
+            "l\nf\n",
+            &[
+                r#"{"CannotPassThroughWall":true}"#,
+                r#"{"RadarView":"beeqkcGO8p8p8pa"}"#,
+                r#"{"FoundExit":true}"#,
+            ],
+        );
+        assert_eq!(directions, ["Left", "Front"]);
+        assert!(result.found_exit);
+    }
+
+    #[test]
+    fn test_simple_strategy_replays_golden_moves() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(
+            log_dir.path().to_str().unwrap(),
+            &["server_message", "decision", "player", "challenge"],
+        )
+        .unwrap();
+        let config = |flags: &[&str]| {
+            let mut args = vec!["worker".to_string(), "127.0.0.1:8778".to_string()];
+            args.extend(flags.iter().map(|flag| flag.to_string()));
+            crate::config::parse_args(&args).unwrap()
+        };
+
+        for (name, config) in [
+            ("simple_4x4", config(&[])),
+            ("seek_goal_3x3", config(&["--seek-goal"])),
+        ] {
+            let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+            let golden = std::fs::read_to_string(fixtures.join(format!("{}.moves", name))).unwrap();
+            let golden: Vec<&str> = golden.lines().collect();
+            let (_, moves) = replay_fixture(name, &config);
+
+            if let Some(index) = (0..golden.len().max(moves.len()))
+                .find(|&index| golden.get(index).copied() != moves.get(index).map(String::as_str))
+            {
+                panic!(
+                    "{}: move {} diverged from the golden file, expected {:?} but the player sent {:?}\n\
+                     golden: {:?}\nsent:   {:?}",
+                    name,
+                    index + 1,
+                    golden.get(index),
+                    moves.get(index),
+                    golden,
+                    moves
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_radar_view_after_a_wall_hit_is_read_with_it() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(
+            log_dir.path().to_str().unwrap(),
+            &["server_message", "decision", "player", "challenge"],
+        )
+        .unwrap();
+        let config =
+            crate::config::parse_args(&["worker".to_string(), "127.0.0.1:8778".to_string()])
+                .unwrap();
+        // Every passage open, then the same view with a wall on the right, then also in front
+        let open = r#"{"RadarView":"vvvvvvvvaaaaaaa"}"#;
+        let wall_on_the_right = r#"{"RadarView":"vvvvvvLvaaaaaaa"}"#;
+
+        // The radar view sent with the wall hit shows the wall, the player goes on to the front
+        let (result, directions) = replay_responses(
+            open,
+            &[
+                r#"{"CannotPassThroughWall":true}"#,
+                wall_on_the_right,
+                r#"{"FoundExit":true}"#,
+            ],
+            &config,
+        );
+        assert_eq!(directions, ["Right", "Front"]);
+        assert_eq!(result, SolveResult::end(2, EndReason::FoundExit));
+
+        // Each move is matched with its own response, wall hit after wall hit
+        let (result, directions) = replay_responses(
+            open,
+            &[
+                r#"{"CannotPassThroughWall":true}"#,
+                wall_on_the_right,
+                r#"{"CannotPassThroughWall":true}"#,
+                r#"{"RadarView":"vzvvvvLvaaaaaaa"}"#,
+                r#"{"FoundExit":true}"#,
+            ],
+            &config,
+        );
+        assert_eq!(directions, ["Right", "Front", "Left"]);
+        assert_eq!(result, SolveResult::end(3, EndReason::FoundExit));
+    }
+
+    #[test]
+    fn test_smart_player_stays_in_place_after_a_wall_hit() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(
+            log_dir.path().to_str().unwrap(),
+            &["server_message", "decision", "player", "challenge"],
+        )
+        .unwrap();
+        let config = crate::config::parse_args(&[
+            "worker".to_string(),
+            "127.0.0.1:8778".to_string(),
+            "--smart".to_string(),
+        ])
+        .unwrap();
+        // Open to the front and to the right only
+        let radar = r#"{"RadarView":"QMQQQQAQaaaaaaa"}"#;
+
+        // The radar view after the wall still shows the passage open, the wall is remembered anyway
+        let (result, directions) = replay_responses(
+            radar,
+            &[
+                r#"{"CannotPassThroughWall":true}"#,
+                radar,
+                r#"{"FoundExit":true}"#,
+            ],
+            &config,
+        );
+        assert_eq!(directions.len(), 2);
+        assert_ne!(directions[0], directions[1]);
+        assert!(["Front", "Right"].contains(&directions[1].as_str()));
+        assert_eq!(result, SolveResult::end(2, EndReason::FoundExit));
+    }
+
+    #[test]
+    fn test_smart_player_moves_back_to_a_visible_exit() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(
+            log_dir.path().to_str().unwrap(),
+            &["server_message", "decision", "player", "challenge"],
+        )
+        .unwrap();
+        let config = crate::config::parse_args(&[
+            "worker".to_string(),
+            "127.0.0.1:8778".to_string(),
+            "--smart".to_string(),
+        ])
+        .unwrap();
+
+        // The exit is behind, the front and the right are open too
+        let (result, directions) = replay_responses(
+            r#"{"RadarView":"QMMQQQAQaaaacaa"}"#,
+            &[r#"{"FoundExit":true}"#],
+            &config,
+        );
+        assert_eq!(directions, ["Back"]);
+        assert!(result.found_exit);
+    }
+
+    #[test]
+    fn test_mark_wall_closes_both_sides_of_the_passage() {
+        let open = map_cell(
+            Boundary::Open,
+            Boundary::Open,
+            Boundary::Open,
+            Boundary::Open,
+        );
+        let mut map = vec![vec![open.clone(); 3]; 3];
+        map[1][1].is_player_here = true;
+
+        mark_wall(&mut map, MapDirection::East);
+        assert_eq!(map[1][1].east, Boundary::Wall);
+        assert_eq!(map[1][2].west, Boundary::Wall);
+        assert_eq!(map[1][1].north, Boundary::Open);
+
+        // Nothing to close past the edge of the map
+        mark_wall(&mut map, MapDirection::North);
+        map[1][1].is_player_here = false;
+        map[0][0].is_player_here = true;
+        mark_wall(&mut map, MapDirection::West);
+        assert_eq!(map[0][0].west, Boundary::Wall);
+        assert_eq!(map[1][1].north, Boundary::Wall);
+    }
+
+    #[test]
+    fn test_solve_result_reports_the_end_of_the_game() {
+        let log_dir = tempfile::tempdir().unwrap();
+        SQP_common::logger::init_logging(
+            log_dir.path().to_str().unwrap(),
+            &["server_message", "decision", "player", "challenge"],
+        )
+        .unwrap();
+        let config =
+            crate::config::parse_args(&["worker".to_string(), "127.0.0.1:8778".to_string()])
+                .unwrap();
+
+        let (result, directions) = replay_fixture("simple_4x4", &config);
+        assert!(result.found_exit);
+        assert_eq!(result.reason, EndReason::FoundExit);
+        assert!(result.moves > 0);
+        assert_eq!(result.moves, directions.len() as u64);
+
+        // A server closing the connection after the first radar view ends the game after one move
+        let mut stream = ScriptedStream {
+            responses: std::io::Cursor::new(Vec::new()),
+            sent: Vec::new(),
+        };
+        let result = search_for_exit(
+            "Nino".to_string(),
+            &mut stream,
+            r#"{"RadarView":"vvrvrvvvapae//8"}"#.to_string(),
+            &config,
+        )
+        .unwrap();
+        assert!(!result.found_exit);
+        assert!(matches!(result.reason, EndReason::ConnectionLost(_)));
+        assert_eq!(result.moves, 1);
+    }
 }
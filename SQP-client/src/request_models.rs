@@ -9,6 +9,12 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct RegisterTeam {
     pub(crate) name: String,
+    /// Team size asked with --players; the server may grant fewer. Left out when None.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expected_players: Option<usize>,
+    /// PROTOCOL_VERSION of the client, echoed by servers that check it. Left out when None.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) protocol_version: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -26,6 +32,20 @@ pub(crate) struct Answer {
 pub(crate) enum Action {
     MoveTo(Direction),
     SolveChallenge(Answer),
+    /// Keep-alive: the server answers with the current radar view, without moving nor counting a move.
+    Noop,
+    /// Asks which relative directions are open from the player's cell, without moving.
+    GetOpenDirections,
+}
+
+/**
+ * The ShareSecret struct represents a secret a player found, sent to the server
+ * and relayed by it to the other players of the team.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ShareSecret {
+    pub(crate) player: String,
+    pub(crate) secret: u64,
 }
 
 /**
@@ -38,4 +58,99 @@ pub(crate) enum Message {
     RegisterTeam(RegisterTeam),
     SubscribePlayer(SubscribePlayer),
     Action(Action),
+    ShareSecret(ShareSecret),
+}
+
+/**
+ * The ServerMessage enum represents the messages the server sends to a player during the game.
+ * A message is read once with ServerMessage::parse and dispatched on its variant,
+ * whatever the content of its payload (a radar view may well contain "Hint" in its base64).
+ *
+ * The server can send a radar view and a hint in a single `{"RadarView":"...","Hint":{...}}`
+ * message: it is a RadarView carrying the hint.
+ */
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "ServerMessageFields")]
+pub(crate) enum ServerMessage {
+    RadarView {
+        view: String,
+        hint: Option<serde_json::Value>,
+    },
+    Hint(serde_json::Value),
+    Challenge(serde_json::Value),
+    FoundExit,
+    CannotPassThroughWall,
+    /// Any other message, or one that is not valid JSON.
+    Other,
+}
+
+/**
+ * The top-level fields a server message can have, before they are told apart by ServerMessage.
+ * The extra fields of a radar view (seq, facing, orientation) are ignored.
+ */
+#[derive(Deserialize, Debug)]
+struct ServerMessageFields {
+    #[serde(rename = "RadarView")]
+    radar_view: Option<String>,
+    #[serde(rename = "Hint")]
+    hint: Option<serde_json::Value>,
+    #[serde(rename = "Challenge")]
+    challenge: Option<serde_json::Value>,
+    #[serde(rename = "FoundExit")]
+    found_exit: Option<serde_json::Value>,
+    #[serde(rename = "CannotPassThroughWall")]
+    cannot_pass_through_wall: Option<serde_json::Value>,
+}
+
+impl From<ServerMessageFields> for ServerMessage {
+    fn from(fields: ServerMessageFields) -> Self {
+        match fields {
+            ServerMessageFields {
+                radar_view: Some(view),
+                hint,
+                ..
+            } => ServerMessage::RadarView { view, hint },
+            ServerMessageFields {
+                hint: Some(hint), ..
+            } => ServerMessage::Hint(hint),
+            ServerMessageFields {
+                challenge: Some(challenge),
+                ..
+            } => ServerMessage::Challenge(challenge),
+            ServerMessageFields {
+                found_exit: Some(_),
+                ..
+            } => ServerMessage::FoundExit,
+            ServerMessageFields {
+                cannot_pass_through_wall: Some(_),
+                ..
+            } => ServerMessage::CannotPassThroughWall,
+            _ => ServerMessage::Other,
+        }
+    }
+}
+
+impl ServerMessage {
+    /**
+     * The parse function reads a raw server message.
+     *
+     * @param message: &str - The raw message received from the server
+     * @return ServerMessage - The message, or Other if it is not one the player handles
+     */
+    pub(crate) fn parse(message: &str) -> ServerMessage {
+        serde_json::from_str(message).unwrap_or(ServerMessage::Other)
+    }
+
+    /**
+     * The hint function returns the hint carried by the message, alone or with a radar view.
+     *
+     * @return Option<&serde_json::Value> - The content of the Hint field, if any
+     */
+    pub(crate) fn hint(&self) -> Option<&serde_json::Value> {
+        match self {
+            ServerMessage::RadarView { hint, .. } => hint.as_ref(),
+            ServerMessage::Hint(hint) => Some(hint),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,107 @@
+use crate::decoder::decode;
+use crate::player::split_radar_and_hint;
+use std::fs;
+use SQP_common::error::{Error, PlayerError, ProtocolError};
+
+/**
+ * The validate_radar function checks one recorded radar view.
+ * The line is either the encoded radar string or a server response holding it ({"RadarView":"..."}).
+ * The radar must decode, be 11 bytes long and have no passage with the error code (0b11).
+ *
+ * @param line: &str - The recorded radar view
+ * @return Result<(), Error> - Ok, or why the radar view is invalid
+ */
+pub(crate) fn validate_radar(line: &str) -> Result<(), Error> {
+    let line = line.trim();
+    let radar_view = if line.starts_with('{') {
+        split_radar_and_hint(line).0.ok_or_else(|| {
+            ProtocolError::ResponseParsingFailed("no RadarView in the response".to_string())
+        })?
+    } else {
+        line.to_string()
+    };
+
+    let data = decode(&radar_view)?;
+    if data.len() != 11 {
+        return Err(PlayerError::RadarResponseFailed(format!(
+            "expected 11 bytes, got {}",
+            data.len()
+        ))
+        .into());
+    }
+
+    for (name, bytes) in [("Horizontal", &data[0..3]), ("Vertical", &data[3..6])] {
+        let bits = ((bytes[2] as u32) << 16) | ((bytes[1] as u32) << 8) | (bytes[0] as u32);
+        for i in 0..12 {
+            if (bits >> ((11 - i) * 2)) & 0b11 == 0b11 {
+                return Err(PlayerError::RadarResponseFailed(format!(
+                    "{} passage {} has the error code",
+                    name, i
+                ))
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * The validate_radar_file function checks every radar view of a file, one per line.
+ * Blank lines are skipped.
+ *
+ * @param path: &str - The file of recorded radar views
+ * @return Result<Vec<(usize, Error)>, std::io::Error> - The failing lines (numbered from 1) and their errors
+ */
+pub(crate) fn validate_radar_file(path: &str) -> Result<Vec<(usize, Error)>, std::io::Error> {
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| validate_radar(line).err().map(|e| (index + 1, e)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SQP_common::error::DecodeError;
+
+    #[test]
+    fn test_validate_radar() {
+        assert_eq!(validate_radar("ieysGjGO8papd/a"), Ok(()));
+        assert_eq!(validate_radar(r#"{"RadarView":"ieysGjGO8papd/a"}"#), Ok(()));
+        assert_eq!(
+            validate_radar("ieysGjGO8pa*d/a"),
+            Err(DecodeError::UnauthorizedCharacter('*').into())
+        );
+        assert!(matches!(
+            validate_radar("ieysGjGO"),
+            Err(Error::Player(PlayerError::RadarResponseFailed(_)))
+        ));
+        // First horizontal passage set to the error code
+        assert!(matches!(
+            validate_radar("aadaaaaa//////a"),
+            Err(Error::Player(PlayerError::RadarResponseFailed(_)))
+        ));
+    }
+
+    #[test]
+    fn test_validate_radar_file_reports_failing_line() {
+        let path = std::env::temp_dir().join("sqp-client-test-radars.txt");
+        fs::write(
+            &path,
+            "ieysGjGO8papd/a\n{\"RadarView\":\"ieysGjGO8papd/a\"}\n\nieysGjG*8papd/a\nieysGjGO8papd/a\n",
+        )
+        .unwrap();
+
+        let failures = validate_radar_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 4);
+        assert_eq!(
+            failures[0].1,
+            DecodeError::UnauthorizedCharacter('*').into()
+        );
+    }
+}
@@ -8,6 +8,8 @@ pub enum NetworkError {
     ReadLengthFailed(String),
     ReadPayloadFailed(String),
     Utf8ConversionFailed(String),
+    Timeout(String),
+    ConnectionClosed,
 }
 
 #[derive(Debug, PartialEq)]
@@ -17,7 +19,12 @@ pub enum ProtocolError {
     TokenNotFound,
     InvalidArguments,
     InvalidAddressFormat,
-    RegistrationFailed,
+    RegistrationFailed(String),
+    /// The peer speaks another version of the protocol than `PROTOCOL_VERSION`.
+    VersionMismatch {
+        ours: u32,
+        theirs: u32,
+    },
 }
 
 #[derive(Debug, PartialEq)]
@@ -44,6 +51,8 @@ pub enum PlayerError {
     HintHandlingFailed(String),
     ChallengeResolutionFailed(String),
     InvalidRadarData,
+    /// A player thread could not be started, or panicked
+    ThreadFailed(String),
 }
 
 impl fmt::Display for NetworkError {
@@ -67,6 +76,8 @@ impl fmt::Display for NetworkError {
             NetworkError::Utf8ConversionFailed(msg) => {
                 write!(f, "Invalid UTF-8 message received: {}", msg)
             }
+            NetworkError::Timeout(msg) => write!(f, "Timed out waiting for a message: {}", msg),
+            NetworkError::ConnectionClosed => write!(f, "Connection closed by peer"),
         }
     }
 }
@@ -85,7 +96,14 @@ impl fmt::Display for ProtocolError {
             ProtocolError::InvalidAddressFormat => {
                 write!(f, "Invalid server address. Use <host:port> format")
             }
-            ProtocolError::RegistrationFailed => write!(f, "Failed to register team"),
+            ProtocolError::RegistrationFailed(msg) => {
+                write!(f, "Failed to register team: {}", msg)
+            }
+            ProtocolError::VersionMismatch { ours, theirs } => write!(
+                f,
+                "Protocol version mismatch: this side speaks version {}, the peer version {}",
+                ours, theirs
+            ),
         }
     }
 }
@@ -131,6 +149,7 @@ impl fmt::Display for PlayerError {
                 write!(f, "Failed to resolve challenge: {}", msg)
             }
             PlayerError::InvalidRadarData => write!(f, "Invalid radar data"),
+            PlayerError::ThreadFailed(msg) => write!(f, "Player thread failed: {}", msg),
         }
     }
 }
@@ -151,6 +170,49 @@ pub enum Error {
     Player(PlayerError),
 }
 
+/// The family of an `Error`, to branch on without matching the wrapped error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Network,
+    Protocol,
+    Log,
+    Decode,
+    Player,
+}
+
+impl Error {
+    /// Returns the family of the wrapped error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Network(_) => ErrorKind::Network,
+            Error::Protocol(_) => ErrorKind::Protocol,
+            Error::Log(_) => ErrorKind::Log,
+            Error::Decode(_) => ErrorKind::Decode,
+            Error::Player(_) => ErrorKind::Player,
+        }
+    }
+
+    pub fn is_network(&self) -> bool {
+        self.kind() == ErrorKind::Network
+    }
+
+    pub fn is_protocol(&self) -> bool {
+        self.kind() == ErrorKind::Protocol
+    }
+
+    pub fn is_log(&self) -> bool {
+        self.kind() == ErrorKind::Log
+    }
+
+    pub fn is_decode(&self) -> bool {
+        self.kind() == ErrorKind::Decode
+    }
+
+    pub fn is_player(&self) -> bool {
+        self.kind() == ErrorKind::Player
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -205,3 +267,32 @@ impl From<PlayerError> for Error {
         Error::Player(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_of_each_wrapped_error() {
+        let errors: [(Error, ErrorKind); 5] = [
+            (NetworkError::ConnectionClosed.into(), ErrorKind::Network),
+            (ProtocolError::TokenNotFound.into(), ErrorKind::Protocol),
+            (
+                LogError::WriteFailed("disk full".to_string()).into(),
+                ErrorKind::Log,
+            ),
+            (DecodeError::InvalidSize.into(), ErrorKind::Decode),
+            (PlayerError::InvalidRadarData.into(), ErrorKind::Player),
+        ];
+        for (error, kind) in &errors {
+            assert_eq!(error.kind(), *kind, "{}", error);
+        }
+
+        let [network, protocol, log, decode, player] = errors.map(|(error, _)| error);
+        assert!(network.is_network() && !network.is_protocol());
+        assert!(protocol.is_protocol() && !protocol.is_log());
+        assert!(log.is_log() && !log.is_decode());
+        assert!(decode.is_decode() && !decode.is_player());
+        assert!(player.is_player() && !player.is_network());
+    }
+}
@@ -1,3 +1,4 @@
 pub mod server_utils;
 pub mod error;
-pub mod logger;
\ No newline at end of file
+pub mod logger;
+pub mod radar;
\ No newline at end of file
@@ -1,23 +1,94 @@
 use crate::error::{Error, LogError};
 use log::{error, info, warn};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fs::{File, Metadata, OpenOptions};
 use std::io::{Seek, SeekFrom, Write};
 use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// A global (static) map that holds our file handles for different log categories.
 /// We use `OnceLock` to ensure it's initialized only once.
 /// `Mutex` ensures thread-safe access if multiple threads log concurrently.
-static LOG_MAP: OnceLock<Mutex<HashMap<String, File>>> = OnceLock::new();
+static LOG_MAP: OnceLock<Mutex<HashMap<String, LogFile>>> = OnceLock::new();
+
+/// The file of a log category, with the format its entries are written in.
+struct LogFile {
+    file: File,
+    format: LogFormat,
+}
+
+/// How the entries of a log file are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LogFormat {
+    /// The message as is, one line per entry.
+    #[default]
+    Text,
+    /// One JSON object per line: `{"ts":<milliseconds since the epoch>,"category":...,"thread":...,"message":...}`.
+    Json,
+}
+
+/// The line written between two sessions of the same log file.
+const SESSION_SEPARATOR: &str = "\n\n\n########## NEW SESSION ##########\n";
+
+/// How much of the previous sessions is kept when the log files are opened.
+/// The default keeps everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LogRetention {
+    /// Number of previous sessions kept in each file, besides the one being started.
+    pub max_sessions: Option<usize>,
+    /// Total size, in bytes, the files of all the categories may take.
+    /// The oldest sessions of the largest files are dropped first; the latest session of a
+    /// file is never dropped.
+    pub max_bytes: Option<u64>,
+}
 
 /// Initializes logging for a given list of categories.
 /// A file named `category.log` will be created (or appended to) in the `log/` directory.
 pub fn init_logging(log_dir: &str, categories: &[&str]) -> Result<(), Error> {
+    init_logging_with_retention(log_dir, categories, LogRetention::default())
+}
+
+/// Initializes logging like `init_logging`, first trimming the previous sessions of the
+/// files according to `retention`.
+/// Categories already opened by this process are left untouched, so the current session is
+/// neither trimmed nor cut by a separator.
+pub fn init_logging_with_retention(
+    log_dir: &str,
+    categories: &[&str],
+    retention: LogRetention,
+) -> Result<(), Error> {
+    init_logging_with_format(log_dir, categories, retention, LogFormat::Text)
+}
+
+/// Initializes logging like `init_logging_with_retention`, writing the entries of the new
+/// categories in `format`. Categories already opened keep the format they were opened with.
+pub fn init_logging_with_format(
+    log_dir: &str,
+    categories: &[&str],
+    retention: LogRetention,
+    format: LogFormat,
+) -> Result<(), Error> {
     std::fs::create_dir_all(log_dir)
         .map_err(|e| LogError::DirectoryCreationFailed(e.to_string()))?;
 
+    let already_open = |category: &str| {
+        LOG_MAP.get().is_some_and(|mutex_map| {
+            mutex_map
+                .lock()
+                .map(|map| map.contains_key(category))
+                .unwrap_or(true)
+        })
+    };
+    let new_categories: Vec<&str> = categories
+        .iter()
+        .copied()
+        .filter(|category| !already_open(category))
+        .collect();
+    apply_retention(log_dir, &new_categories, retention)?;
+
     let mut new_map = HashMap::new();
-    for &category in categories {
+    for &category in &new_categories {
         let path = format!("{}/{}.log", log_dir, category);
 
         let mut file = OpenOptions::new()
@@ -32,7 +103,7 @@ pub fn init_logging(log_dir: &str, categories: &[&str]) -> Result<(), Error> {
             .map_err(|e| LogError::MetadataFailed(e.to_string()))?;
         write_separator(path, &mut file, metadata)?;
 
-        new_map.insert(category.to_string(), file);
+        new_map.insert(category.to_string(), LogFile { file, format });
     }
 
     match LOG_MAP.set(Mutex::new(new_map)) {
@@ -42,33 +113,23 @@ pub fn init_logging(log_dir: &str, categories: &[&str]) -> Result<(), Error> {
             Ok(())
         }
         // LOG_MAP was already initialized, so let's merge in any new categories:
-        Err(_) => {
+        Err(rejected) => {
             info!("init_logging: LOG_MAP was already initialized; merging categories.");
             if let Some(mutex_map) = LOG_MAP.get() {
                 let mut global_map = mutex_map
                     .lock()
                     .map_err(|e| LogError::MutexPoisoned(e.to_string()))?;
+                let new_files = rejected
+                    .into_inner()
+                    .map_err(|e| LogError::MutexPoisoned(e.to_string()))?;
 
-                for &category in categories {
-                    if !global_map.contains_key(category) {
-                        // If this category is truly new, open again just in case
-                        let path = format!("{}/{}.log", log_dir, category);
-                        let mut file = OpenOptions::new()
-                            .create(true)
-                            .append(true)
-                            .open(&path)
-                            .map_err(|e| LogError::FileOpenFailed(e.to_string()))?;
-
-                        let metadata = file
-                            .metadata()
-                            .map_err(|e| LogError::MetadataFailed(e.to_string()))?;
-                        write_separator(path, &mut file, metadata)?;
-
-                        global_map.insert(category.to_string(), file);
+                for (category, file) in new_files {
+                    if let Entry::Vacant(entry) = global_map.entry(category) {
                         info!(
                             "Added new category '{}' during re-initialization.",
-                            category
+                            entry.key()
                         );
+                        entry.insert(file);
                     }
                 }
             }
@@ -81,13 +142,94 @@ fn write_separator(_path: String, file: &mut File, metadata: Metadata) -> Result
     if metadata.len() > 0 {
         file.seek(SeekFrom::End(0))
             .map_err(|e| LogError::WriteFailed(e.to_string()))?;
-        let separator = "\n\n\n########## NEW SESSION ##########\n";
-        file.write_all(separator.as_bytes())
+        file.write_all(SESSION_SEPARATOR.as_bytes())
             .map_err(|e| LogError::WriteFailed(e.to_string()))?;
     }
     Ok(())
 }
 
+/// Drops the old session blocks of the category files beyond what `retention` allows,
+/// rewriting only the files that changed.
+fn apply_retention(
+    log_dir: &str,
+    categories: &[&str],
+    retention: LogRetention,
+) -> Result<(), Error> {
+    if retention == LogRetention::default() {
+        return Ok(());
+    }
+
+    let mut files = Vec::new();
+    for &category in categories {
+        let path = format!("{}/{}.log", log_dir, category);
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(LogError::FileOpenFailed(e.to_string()).into()),
+        };
+        let sessions = split_sessions(&contents);
+        let mut first_kept = 0;
+        if let Some(max_sessions) = retention.max_sessions {
+            first_kept = sessions.len().saturating_sub(max_sessions);
+        }
+        files.push((path, sessions, first_kept));
+    }
+
+    if let Some(max_bytes) = retention.max_bytes {
+        let kept_size = |sessions: &[Vec<u8>], first_kept: usize| -> u64 {
+            let blocks = &sessions[first_kept..];
+            let separators = blocks.len().saturating_sub(1) * SESSION_SEPARATOR.len();
+            (blocks.iter().map(Vec::len).sum::<usize>() + separators) as u64
+        };
+        loop {
+            let total: u64 = files
+                .iter()
+                .map(|(_, sessions, first_kept)| kept_size(sessions, *first_kept))
+                .sum();
+            if total <= max_bytes {
+                break;
+            }
+            let largest = files
+                .iter_mut()
+                .filter(|(_, sessions, first_kept)| *first_kept + 1 < sessions.len())
+                .max_by_key(|(_, sessions, first_kept)| kept_size(sessions, *first_kept));
+            match largest {
+                Some((_, _, first_kept)) => *first_kept += 1,
+                None => break,
+            }
+        }
+    }
+
+    for (path, sessions, first_kept) in files {
+        if first_kept == 0 {
+            continue;
+        }
+        let trimmed = sessions[first_kept..].join(SESSION_SEPARATOR.as_bytes());
+        std::fs::write(&path, trimmed).map_err(|e| LogError::WriteFailed(e.to_string()))?;
+        info!("Dropped {} old session(s) from {}.", first_kept, path);
+    }
+    Ok(())
+}
+
+/// Splits the contents of a log file into its session blocks, oldest first.
+fn split_sessions(contents: &[u8]) -> Vec<Vec<u8>> {
+    let separator = SESSION_SEPARATOR.as_bytes();
+    let mut sessions = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+    while index + separator.len() <= contents.len() {
+        if &contents[index..index + separator.len()] == separator {
+            sessions.push(contents[start..index].to_vec());
+            index += separator.len();
+            start = index;
+        } else {
+            index += 1;
+        }
+    }
+    sessions.push(contents[start..].to_vec());
+    sessions
+}
+
 /// Writes a single line (with a trailing newline) to the specified log category.
 ///
 /// # Arguments
@@ -103,9 +245,13 @@ pub fn log_message(category: &str, message: &str) -> Result<(), Error> {
         });
 
         // Fetch the file handle for the requested category:
-        if let Some(file) = map.get_mut(category) {
+        if let Some(LogFile { file, format }) = map.get_mut(category) {
             // Try writing to the file; log an error if something goes wrong.
-            writeln!(file, "{}", message).map_err(|e| LogError::WriteFailed(e.to_string()))?;
+            match format {
+                LogFormat::Text => writeln!(file, "{}", message),
+                LogFormat::Json => writeln!(file, "{}", json_entry(category, message)),
+            }
+            .map_err(|e| LogError::WriteFailed(e.to_string()))?;
             info!("{}: {}", category, message);
             Ok(())
         } else {
@@ -126,6 +272,24 @@ pub fn log_message(category: &str, message: &str) -> Result<(), Error> {
     }
 }
 
+/// Builds the JSON entry of a message, timestamped and tagged with the current thread.
+fn json_entry(category: &str, message: &str) -> serde_json::Value {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64);
+    let thread = std::thread::current();
+    let thread_name = match thread.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", thread.id()),
+    };
+    serde_json::json!({
+        "ts": ts,
+        "category": category,
+        "thread": thread_name,
+        "message": message,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -286,4 +450,91 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_init_logging_keeps_only_retained_sessions() -> Result<(), Error> {
+        let temp_dir = tempdir().map_err(|e| LogError::DirectoryCreationFailed(e.to_string()))?;
+        let log_dir = temp_dir.path().join("test");
+        let log_dir_str = log_dir.to_str().unwrap();
+        fs::create_dir_all(&log_dir)
+            .map_err(|e| LogError::DirectoryCreationFailed(e.to_string()))?;
+        let file_path = log_dir.join("retained.log");
+        let sessions = ["first session", "second session", "third session"];
+        fs::write(&file_path, sessions.join(SESSION_SEPARATOR))
+            .map_err(|e| LogError::WriteFailed(e.to_string()))?;
+
+        let retention = LogRetention {
+            max_sessions: Some(1),
+            max_bytes: None,
+        };
+        init_logging_with_retention(log_dir_str, &["retained"], retention)?;
+
+        let contents = read_file_to_string(&file_path);
+        assert_eq!(contents, format!("third session{}", SESSION_SEPARATOR));
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_logging_caps_total_size_across_categories() -> Result<(), Error> {
+        let temp_dir = tempdir().map_err(|e| LogError::DirectoryCreationFailed(e.to_string()))?;
+        let log_dir = temp_dir.path().join("test");
+        let log_dir_str = log_dir.to_str().unwrap();
+        fs::create_dir_all(&log_dir)
+            .map_err(|e| LogError::DirectoryCreationFailed(e.to_string()))?;
+        let big_path = log_dir.join("capped_big.log");
+        let small_path = log_dir.join("capped_small.log");
+        let old = "o".repeat(100);
+        fs::write(
+            &big_path,
+            [old.as_str(), "big latest"].join(SESSION_SEPARATOR),
+        )
+        .map_err(|e| LogError::WriteFailed(e.to_string()))?;
+        fs::write(
+            &small_path,
+            ["small old", "small latest"].join(SESSION_SEPARATOR),
+        )
+        .map_err(|e| LogError::WriteFailed(e.to_string()))?;
+
+        let retention = LogRetention {
+            max_sessions: None,
+            max_bytes: Some(80),
+        };
+        init_logging_with_retention(log_dir_str, &["capped_big", "capped_small"], retention)?;
+
+        // Only the large old session had to go to fit in the budget.
+        let big = read_file_to_string(&big_path);
+        assert_eq!(big, format!("big latest{}", SESSION_SEPARATOR));
+        let small = read_file_to_string(&small_path);
+        assert!(small.starts_with("small old"));
+        assert!(small.contains("small latest"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_log_entries_parse_back() -> Result<(), Error> {
+        let temp_dir = tempdir().map_err(|e| LogError::DirectoryCreationFailed(e.to_string()))?;
+        let log_dir = temp_dir.path().join("test");
+        let log_dir_str = log_dir.to_str().unwrap();
+
+        init_logging_with_format(
+            log_dir_str,
+            &["json_cat"],
+            LogRetention::default(),
+            LogFormat::Json,
+        )?;
+        log_message("json_cat", "Player \"Nino\" moved\nFront")?;
+
+        let contents = read_file_to_string(log_dir.join("json_cat.log"));
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert!(entry["ts"].as_u64().unwrap() > 0);
+        assert_eq!(entry["category"], "json_cat");
+        assert_eq!(
+            entry["thread"],
+            std::thread::current().name().unwrap_or_default()
+        );
+        assert_eq!(entry["message"], "Player \"Nino\" moved\nFront");
+        Ok(())
+    }
 }
@@ -0,0 +1,234 @@
+/// ASCII drawing of a 3x3 radar view, shared by the client (what it decoded)
+/// and the server (what it sent), so both sides can be compared line by line.
+///
+/// Passages are given as their 2-bit codes: 0 undefined, 1 open, 2 wall, 3 error.
+/// Cells are given as their 4-bit codes: item in the high 2 bits (01 hint, 10 goal),
+/// entity in the low 2 bits (01 ally, 10 enemy, 11 monster). 0b1111 (undefined) and 0b1101
+/// (a cell the player already stood on, server option --mark-visited) are reserved codes for
+/// the whole cell, told apart before the item and entity bits: a visited cell has no ally.
+///
+/// Symbols:
+/// - '#' for undefined cells and passages
+/// - ' ' for empty cells and open passages
+/// - 'G', 'H' for goal and hint cells, 'A', 'E', 'M' for allies, enemies and monsters
+/// - '.' for visited cells
+/// - '-' for walls in horizontal passages, '|' for walls in vertical passages
+/// - '?' for unreadable passages
+/// - '•' for joints between passages
+pub fn render_radar(h_passages: &[u8], v_passages: &[u8], cells: &[u8]) -> String {
+    render(h_passages, v_passages, cells, false)
+}
+
+/// Same drawing as `render_radar`, colored with ANSI codes for a terminal:
+/// walls in red, open passages in green, the player (center cell) on a blue background,
+/// allies in green, enemies and monsters in magenta, the goal in yellow and hints in cyan.
+/// Every colored symbol is followed by a reset code.
+pub fn render_radar_colored(h_passages: &[u8], v_passages: &[u8], cells: &[u8]) -> String {
+    render(h_passages, v_passages, cells, true)
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+const BLUE_BACKGROUND: &str = "\x1b[44m";
+const RESET: &str = "\x1b[0m";
+
+/// Appends a symbol to a line, wrapped in the color codes when colored output is on.
+fn push_symbol(line: &mut String, symbol: char, color: Option<&str>, colored: bool) {
+    match color {
+        Some(color) if colored => {
+            line.push_str(color);
+            line.push(symbol);
+            line.push_str(RESET);
+        }
+        _ => line.push(symbol),
+    }
+}
+
+fn passage_color(code: u8) -> Option<&'static str> {
+    match code {
+        1 => Some(GREEN),
+        2 => Some(RED),
+        _ => None,
+    }
+}
+
+fn cell_color(symbol: char) -> Option<&'static str> {
+    match symbol {
+        'A' => Some(GREEN),
+        'E' | 'M' => Some(MAGENTA),
+        'G' => Some(YELLOW),
+        'H' => Some(CYAN),
+        _ => None,
+    }
+}
+
+fn render(h_passages: &[u8], v_passages: &[u8], cells: &[u8], colored: bool) -> String {
+    let joint = '•';
+    let mut lines: Vec<String> = Vec::new();
+
+    // Horizontal passages are 4 rows of 3, vertical passages 3 rows of 4
+    let horizontal = |row: usize, column: usize| h_passages[row * 3 + column];
+    let vertical = |row: usize, column: usize| v_passages[row * 4 + column];
+
+    for i in 0..7 {
+        let mut line = String::new();
+
+        if i % 2 == 0 {
+            // Line of horizontal passages and joints
+            let row = i / 2;
+            for j in 0..7 {
+                if j % 2 != 0 {
+                    let code = horizontal(row, j / 2);
+                    push_symbol(
+                        &mut line,
+                        passage_symbol(code, '-'),
+                        passage_color(code),
+                        colored,
+                    );
+                } else {
+                    // A joint is drawn next to any known passage:
+                    // in the first half of the line the passage after it counts, in the second half the one before
+                    let known = |column: usize| horizontal(row, column) != 0;
+                    let has_joint = if j < 3 {
+                        known(j / 2) || (j != 0 && known((j - 1) / 2))
+                    } else {
+                        known((j - 1) / 2) || (j != 6 && known(j / 2))
+                    };
+                    line.push(if has_joint { joint } else { '#' });
+                }
+            }
+        } else {
+            // Line of vertical passages and cells
+            let row = (i - 1) / 2;
+            for j in 0..7 {
+                if j % 2 == 0 {
+                    let code = vertical(row, j / 2);
+                    push_symbol(
+                        &mut line,
+                        passage_symbol(code, '|'),
+                        passage_color(code),
+                        colored,
+                    );
+                } else {
+                    let index = (i / 2) * 3 + j / 2;
+                    let symbol = cell_symbol(cells[index]);
+                    // The player always stands in the center of their radar
+                    let color = if index == 4 {
+                        Some(BLUE_BACKGROUND)
+                    } else {
+                        cell_color(symbol)
+                    };
+                    push_symbol(&mut line, symbol, color, colored);
+                }
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Draws the 11 bytes of a decoded radar view (see `render_radar`).
+/// Returns None if the data is not 11 bytes long.
+pub fn render_radar_bytes(data: &[u8]) -> Option<String> {
+    if data.len() != 11 {
+        return None;
+    }
+    let h_passages = passage_codes(&data[0..3]);
+    let v_passages = passage_codes(&data[3..6]);
+    let cells = cell_codes(&data[6..11]);
+    Some(render_radar(&h_passages, &v_passages, &cells))
+}
+
+/// Reads the 12 passage codes of 3 bytes, sent least significant byte first.
+/// The first passage is in the 2 highest bits: 0 undefined, 1 open, 2 wall, 3 error.
+pub fn passage_codes(bytes: &[u8]) -> Vec<u8> {
+    let bits = ((bytes[2] as u32) << 16) | ((bytes[1] as u32) << 8) | (bytes[0] as u32);
+    (0..12)
+        .map(|i| ((bits >> ((11 - i) * 2)) & 0b11) as u8)
+        .collect()
+}
+
+/// Reads the 9 cell codes of 5 bytes, the last 4 bits being padding.
+fn cell_codes(bytes: &[u8]) -> Vec<u8> {
+    let bits = bytes
+        .iter()
+        .fold(0u64, |bits, &byte| (bits << 8) | byte as u64)
+        >> 4;
+    (0..9)
+        .rev()
+        .map(|i| ((bits >> (i * 4)) & 0b1111) as u8)
+        .collect()
+}
+
+fn passage_symbol(code: u8, wall: char) -> char {
+    match code {
+        0 => '#',
+        1 => ' ',
+        2 => wall,
+        _ => '?',
+    }
+}
+
+/// Entities are drawn over items, since a player standing on a hint hides it.
+fn cell_symbol(code: u8) -> char {
+    // Reserved codes for the whole cell
+    match code {
+        0b1111 => return '#',
+        0b1101 => return '.',
+        _ => {}
+    }
+    match (code & 0b11, code >> 2) {
+        (0b01, _) => 'A',
+        (0b10, _) => 'E',
+        (0b11, _) => 'M',
+        (_, 0b01) => 'H',
+        (_, 0b10) => 'G',
+        _ => ' ',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_radar() {
+        // Open corridor going north: walls on both sides, undefined outside the view
+        let h_passages = [0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0];
+        let v_passages = [0, 0, 0, 0, 0, 2, 2, 0, 0, 0, 0, 0];
+        let cells = [15, 15, 15, 15, 0, 15, 15, 15, 15];
+        let ascii = render_radar(&h_passages, &v_passages, &cells);
+        assert_eq!(
+            ascii,
+            "#######\n#######\n##• •##\n##| |##\n##• •##\n#######\n#######\n"
+        );
+
+        assert_eq!(render_radar_bytes(&[0; 10]), None);
+        assert_eq!(cell_symbol(0b1101), '.');
+        assert_eq!(cell_symbol(0b0001), 'A');
+    }
+
+    #[test]
+    fn test_render_radar_colored() {
+        let h_passages = [0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0];
+        let v_passages = [0, 0, 0, 0, 0, 2, 2, 0, 0, 0, 0, 0];
+        let cells = [15, 15, 15, 15, 0, 15, 15, 15, 15];
+
+        let plain = render_radar(&h_passages, &v_passages, &cells);
+        let colored = render_radar_colored(&h_passages, &v_passages, &cells);
+        assert!(!plain.contains(RESET));
+        assert!(colored.contains(&format!("{}|{}", RED, RESET)));
+        assert!(colored.contains(&format!("{} {}", BLUE_BACKGROUND, RESET)));
+
+        // Without the color codes, both drawings are the same
+        let stripped = [RED, GREEN, YELLOW, MAGENTA, CYAN, BLUE_BACKGROUND, RESET]
+            .iter()
+            .fold(colored, |text, code| text.replace(code, ""));
+        assert_eq!(stripped, plain);
+    }
+}
@@ -1,17 +1,26 @@
 use crate::error::{Error, NetworkError, ProtocolError};
 use crate::logger::log_message;
 use serde::Serialize;
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::io::{ErrorKind, Read, Write};
 
 const LOG_MESSAGE_CATEGORY: &str = "server_message";
 
+/// Version of the protocol spoken by this client and server, sent in `RegisterTeam` and
+/// echoed in its result. Bump it when a message changes in a way the other side cannot ignore.
+pub const PROTOCOL_VERSION: u32 = 1;
+
 ///Send a message to the server
 ///
-/// @param stream: &mut TcpStream - The TCP stream to send the message <br>
+/// Every message is framed as its length, a u32 in little-endian, then its JSON payload:
+/// `{"Action":"Noop"}` goes out as `11 00 00 00` then the 17 bytes of JSON. The client and the
+/// server share this function and `receive_message`, and the reference server uses the same
+/// byte order, so it must not change. The length and the payload go out in a single write, so
+/// a writer shared by several threads never interleaves two frames. <br>
+///
+/// @param stream: &mut impl Write - The stream to send the message, a TcpStream or an in-memory buffer in tests <br>
 /// @param message: &Message - The message to send <br>
 /// @return io::Result<()> - The result of the operation
-pub fn send_message(stream: &mut TcpStream, message: &impl Serialize) -> Result<(), Error> {
+pub fn send_message(stream: &mut impl Write, message: &impl Serialize) -> Result<(), Error> {
     // Log the preparation step
     log_message(LOG_MESSAGE_CATEGORY, "Preparing to send message...")?;
 
@@ -24,31 +33,37 @@ pub fn send_message(stream: &mut TcpStream, message: &impl Serialize) -> Result<
         &format!("Serialized message: {}", serialized_message),
     )?;
 
-    // Send the message length (u32 in little-endian)
+    // Frame the message: its length (u32 in little-endian), then the JSON
     let message_length = serialized_message.len() as u32;
+    let mut frame = Vec::with_capacity(4 + serialized_message.len());
+    frame.extend_from_slice(&message_length.to_le_bytes());
+    frame.extend_from_slice(serialized_message.as_bytes());
+
+    // Send the whole frame at once
     stream
-        .write_all(&message_length.to_le_bytes())
-        .map_err(|e| NetworkError::SendLengthFailed(format!("IO error: {}", e)))?;
+        .write_all(&frame)
+        .map_err(|e| NetworkError::SendPayloadFailed(format!("IO error: {}", e)))?;
     log_message(
         LOG_MESSAGE_CATEGORY,
         &format!("Sent message length: {}", message_length),
     )?;
-
-    // Send the JSON message
-    stream
-        .write_all(serialized_message.as_bytes())
-        .map_err(|e| NetworkError::SendPayloadFailed(format!("IO error: {}", e)))?;
     log_message(LOG_MESSAGE_CATEGORY, "Message sent successfully.")?;
 
     Ok(())
 }
 
-pub fn receive_message(stream: &mut TcpStream) -> Result<String, Error> {
+/// Receive a length-prefixed message
+///
+/// Reads the u32 length in little-endian written by `send_message`, then that many bytes of JSON. <br>
+///
+/// @param stream: &mut impl Read - The stream to read from, a TcpStream or an in-memory buffer in tests <br>
+/// @return Result<String, Error> - The JSON message received, `ReadPayloadFailed("empty message")` for a blank one
+pub fn receive_message(stream: &mut impl Read) -> Result<String, Error> {
     // Read the length of the incoming message
     let mut length_buffer = [0; 4];
     stream
         .read_exact(&mut length_buffer)
-        .map_err(|e| NetworkError::ReadLengthFailed(format!("IO error: {}", e)))?;
+        .map_err(|e| read_error(e, NetworkError::ReadLengthFailed))?;
     let message_length = u32::from_le_bytes(length_buffer) as usize;
     log_message(
         LOG_MESSAGE_CATEGORY,
@@ -61,19 +76,12 @@ pub fn receive_message(stream: &mut TcpStream) -> Result<String, Error> {
 
     while total_read < message_length {
         match stream.read(&mut message_buffer[total_read..]) {
-            Ok(0) => {
-                return Err(NetworkError::ReadPayloadFailed(
-                    "Connection closed by peer".to_string(),
-                )
-                .into());
-            }
+            Ok(0) => return Err(NetworkError::ConnectionClosed.into()),
             Ok(n) => {
                 total_read += n;
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
-            Err(e) => {
-                return Err(NetworkError::ReadPayloadFailed(format!("IO error: {}", e)).into())
-            }
+            Err(e) => return Err(read_error(e, NetworkError::ReadPayloadFailed).into()),
         }
     }
 
@@ -81,15 +89,170 @@ pub fn receive_message(stream: &mut TcpStream) -> Result<String, Error> {
         NetworkError::Utf8ConversionFailed(format!("Invalid UTF-8 sequence: {}", e))
     })?;
 
+    // A zero-length (or blank) payload is no message at all, say so instead of failing later as bad JSON
+    if message.trim().is_empty() {
+        return Err(NetworkError::ReadPayloadFailed("empty message".to_string()).into());
+    }
+
     Ok(message)
 }
 
+/// Converts an io error raised while reading into a NetworkError.
+/// A read timeout (`WouldBlock` or `TimedOut`, depending on the platform) becomes
+/// `NetworkError::Timeout` so callers can retry, anything else uses the given variant.
+///
+/// @param error: std::io::Error - The error returned by the read <br>
+/// @param failed: fn(String) -> NetworkError - The variant used for hard failures <br>
+/// @return NetworkError - The classified error
+pub fn read_error(error: std::io::Error, failed: fn(String) -> NetworkError) -> NetworkError {
+    match error.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+            NetworkError::Timeout(format!("IO error: {}", error))
+        }
+        _ => failed(format!("IO error: {}", error)),
+    }
+}
+
+/// Reads the registration token out of the server's answer to RegisterTeam.
+/// When the server refused the registration (`{"RegisterTeamResult":{"Error":...}}`),
+/// the error carries the server's message instead of a bare `TokenNotFound`.
+///
+/// @param response: &str - The JSON response of the server <br>
+/// @return Result<String, Error> - The registration token, or why there is none
 pub fn parse_token_from_response(response: &str) -> Result<String, Error> {
     let registration_result: serde_json::Value = serde_json::from_str(response)
         .map_err(|e| ProtocolError::ResponseParsingFailed(format!("Invalid JSON: {}", e)))?;
 
-    registration_result["RegisterTeamResult"]["Ok"]["registration_token"]
+    let result = &registration_result["RegisterTeamResult"];
+    if let Some(error) = result.get("Error") {
+        let message = match error.as_str() {
+            Some(message) => message.to_string(),
+            None => error.to_string(),
+        };
+        return Err(ProtocolError::RegistrationFailed(message).into());
+    }
+
+    result["Ok"]["registration_token"]
         .as_str()
         .map(String::from)
         .ok_or_else(|| ProtocolError::TokenNotFound.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_read_error_timeout() {
+        let error = read_error(
+            io::Error::new(ErrorKind::TimedOut, "timed out"),
+            NetworkError::ReadLengthFailed,
+        );
+        assert!(matches!(error, NetworkError::Timeout(_)));
+
+        let error = read_error(
+            io::Error::new(ErrorKind::WouldBlock, "would block"),
+            NetworkError::ReadPayloadFailed,
+        );
+        assert!(matches!(error, NetworkError::Timeout(_)));
+
+        // Other failures keep the variant of the read that failed
+        let error = read_error(
+            io::Error::new(ErrorKind::ConnectionReset, "reset"),
+            NetworkError::ReadPayloadFailed,
+        );
+        assert_eq!(
+            error,
+            NetworkError::ReadPayloadFailed("IO error: reset".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_token_from_response() {
+        let ok =
+            r#"{"RegisterTeamResult":{"Ok":{"expected_players":3,"registration_token":"abc123"}}}"#;
+        assert_eq!(parse_token_from_response(ok), Ok("abc123".to_string()));
+
+        let refused = r#"{"RegisterTeamResult":{"Error":"AlreadyRegistered"}}"#;
+        assert_eq!(
+            parse_token_from_response(refused),
+            Err(ProtocolError::RegistrationFailed("AlreadyRegistered".to_string()).into())
+        );
+
+        let no_token = r#"{"RegisterTeamResult":{"Ok":{"expected_players":3}}}"#;
+        assert_eq!(
+            parse_token_from_response(no_token),
+            Err(ProtocolError::TokenNotFound.into())
+        );
+        assert!(matches!(
+            parse_token_from_response("{\"RegisterTeamResult\""),
+            Err(Error::Protocol(ProtocolError::ResponseParsingFailed(_)))
+        ));
+    }
+
+    #[test]
+    fn test_receive_empty_message() {
+        let log_dir = tempfile::tempdir().unwrap();
+        crate::logger::init_logging(log_dir.path().to_str().unwrap(), &[LOG_MESSAGE_CATEGORY])
+            .unwrap();
+        let empty_message =
+            Error::Network(NetworkError::ReadPayloadFailed("empty message".to_string()));
+
+        let zero_length = 0u32.to_le_bytes();
+        assert_eq!(
+            receive_message(&mut io::Cursor::new(zero_length)),
+            Err(empty_message)
+        );
+
+        let mut blank = 3u32.to_le_bytes().to_vec();
+        blank.extend_from_slice(b" \n ");
+        assert!(matches!(
+            receive_message(&mut io::Cursor::new(blank)),
+            Err(Error::Network(NetworkError::ReadPayloadFailed(_)))
+        ));
+    }
+
+    #[test]
+    fn test_framing_length_is_little_endian() {
+        let log_dir = tempfile::tempdir().unwrap();
+        crate::logger::init_logging(log_dir.path().to_str().unwrap(), &[LOG_MESSAGE_CATEGORY])
+            .unwrap();
+        // 300 bytes of JSON: the length does not fit in one byte, so the order of the bytes shows
+        let message = serde_json::json!({ "Secret": "x".repeat(287) });
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(json.len(), 300);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        send_message(&mut buffer, &message).unwrap();
+        assert_eq!(&buffer[..4], &[0x2c, 0x01, 0x00, 0x00]);
+        assert_eq!(&buffer[4..], json.as_bytes());
+
+        assert_eq!(receive_message(&mut io::Cursor::new(buffer)), Ok(json));
+    }
+
+    #[test]
+    fn test_frame_is_sent_in_one_write() {
+        let log_dir = tempfile::tempdir().unwrap();
+        crate::logger::init_logging(log_dir.path().to_str().unwrap(), &[LOG_MESSAGE_CATEGORY])
+            .unwrap();
+
+        // Records the buffer of each call to write
+        struct Writes(Vec<Vec<u8>>);
+        impl Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.push(buf.to_vec());
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writes = Writes(Vec::new());
+        send_message(&mut writes, &serde_json::json!({ "Action": "Noop" })).unwrap();
+        assert_eq!(writes.0.len(), 1);
+        assert_eq!(&writes.0[0][..4], &[0x11, 0x00, 0x00, 0x00]);
+        assert_eq!(&writes.0[0][4..], br#"{"Action":"Noop"}"#);
+    }
+}
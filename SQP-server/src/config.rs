@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{CompassNoiseModel, MapDirection, RadarOrientation};
+
+/// Longest team or player name accepted when no --max-name-length is given.
+pub(crate) const DEFAULT_MAX_NAME_LENGTH: usize = 64;
+
+/// Players per team when no --max-team-size is given, as on the reference server.
+pub(crate) const DEFAULT_MAX_TEAM_SIZE: usize = 3;
+
+/// Options given on the command line that change how a game is played.
+/// The defaults match the behaviour of the reference server.
+/// Everything but the statistics and maze files, debug output, profiling, player cap, map print
+/// and socket options is part of the maze id (see maze_id.rs).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ServerConfig {
+    /// Send hints inside the radar view message instead of as a separate message.
+    pub(crate) combine_hint_with_radar: bool,
+    /// Accept admin messages that change the labyrinth while a game is running.
+    pub(crate) allow_cheats: bool,
+    /// File the game statistics are written to when the server shuts down.
+    #[serde(skip)]
+    pub(crate) stats_out: Option<String>,
+    /// File each generated labyrinth is written to as JSON (--save-maze).
+    #[serde(skip)]
+    pub(crate) save_maze: Option<String>,
+    /// Direction every player faces when joining, instead of the default per-player facing.
+    pub(crate) start_facing: Option<MapDirection>,
+    /// Number of ticks (moves of any player) after which a cell collapses once a player left it.
+    pub(crate) collapse_after: Option<u64>,
+    /// Probability of flipping one random passage bit in each radar view sent (--radar-noise).
+    pub(crate) radar_noise: Option<f64>,
+    /// Seed of the labyrinth generator. A random one is picked for each arena when None.
+    pub(crate) seed: Option<u64>,
+    /// Log every radar view sent as ASCII in the "radar" log category.
+    #[serde(skip)]
+    pub(crate) log_radar: bool,
+    /// Log the duration of each phase of the maze generation in the "main" log category.
+    #[serde(skip)]
+    pub(crate) profile: bool,
+    /// Longest team or player name accepted, DEFAULT_MAX_NAME_LENGTH when None.
+    pub(crate) max_name_length: Option<usize>,
+    /// Largest team a client can ask for at registration, DEFAULT_MAX_TEAM_SIZE when None.
+    pub(crate) max_team_size: Option<usize>,
+    /// Hide the radar cells that cannot be reached from the center without passing through a wall.
+    pub(crate) line_of_sight: bool,
+    /// Only count the exit as found when the player arrives facing its open passage.
+    pub(crate) strict_exit: bool,
+    /// Practice mode: a player finding the exit keeps playing, the exit moving to the cell farthest from them.
+    pub(crate) endless: bool,
+    /// Mark the cells a player already stood on in their radar views.
+    pub(crate) mark_visited: bool,
+    /// Point compass hints at the exit, with noise depending on the distance to it.
+    /// Hints are random when None.
+    pub(crate) compass_noise_model: Option<CompassNoiseModel>,
+    /// How radar views are turned (--radar-orientation), relative to the facing when None.
+    pub(crate) radar_orientation: Option<RadarOrientation>,
+    /// Distance (diagonals included) within which a radar view gets a 12th byte with the bearing
+    /// of the exit, when the exit is not in view itself (--sense-radius).
+    pub(crate) sense_radius: Option<usize>,
+    /// Number of moves after which a player gets a SecretSumModulo challenge to solve
+    /// before moving again (--challenge-every). No challenges when None.
+    pub(crate) challenge_every: Option<usize>,
+    /// Fewest moves between the generation start and the exit, retrying generation to reach it.
+    pub(crate) min_exit_distance: Option<usize>,
+    /// Most players connected at once over every arena, unlimited when None.
+    #[serde(skip)]
+    pub(crate) max_players: Option<usize>,
+    /// After each move, print only the cells within this many cells of the player who moved.
+    #[serde(skip)]
+    pub(crate) reveal_radius: Option<usize>,
+    /// Set SO_REUSEADDR on the game listeners (--reuse-addr).
+    #[serde(skip)]
+    pub(crate) reuse_address: bool,
+    /// Connections waiting to be accepted by each game listener, DEFAULT_BACKLOG when None.
+    #[serde(skip)]
+    pub(crate) backlog: Option<i32>,
+}
+
+impl ServerConfig {
+    /// Longest team or player name accepted at registration and subscription.
+    pub(crate) fn max_name_length(&self) -> usize {
+        self.max_name_length.unwrap_or(DEFAULT_MAX_NAME_LENGTH)
+    }
+
+    /// Number of players granted to a team asking for `requested` players: the request,
+    /// clamped between 1 and the largest team size, or the largest size without a request.
+    pub(crate) fn granted_team_size(&self, requested: Option<usize>) -> usize {
+        let max_team_size = self.max_team_size.unwrap_or(DEFAULT_MAX_TEAM_SIZE);
+        requested.unwrap_or(max_team_size).clamp(1, max_team_size)
+    }
+}
@@ -0,0 +1,63 @@
+use log::debug;
+use socket2::{Domain, Socket, Type};
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+
+use crate::config::ServerConfig;
+
+/// Connections waiting to be accepted when --backlog is not given, as with `TcpListener::bind`.
+pub(crate) const DEFAULT_BACKLOG: i32 = 128;
+
+/// Binds a game listener to `address` (host:port) with the socket options of the config:
+/// SO_REUSEADDR with --reuse-addr, so a restarted server binds while connections of the
+/// previous one linger, and the accept backlog of --backlog.
+/// Without either option, the listener is bound by `TcpListener::bind` as before.
+pub(crate) fn bind_listener(address: &str, config: &ServerConfig) -> io::Result<TcpListener> {
+    if !config.reuse_address && config.backlog.is_none() {
+        return TcpListener::bind(address);
+    }
+
+    let socket_address: SocketAddr = address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} resolves to no address", address),
+        )
+    })?;
+    let socket = Socket::new(Domain::for_address(socket_address), Type::STREAM, None)?;
+    socket.set_reuse_address(config.reuse_address)?;
+    socket.bind(&socket_address.into())?;
+    let backlog = config.backlog.unwrap_or(DEFAULT_BACKLOG);
+    socket.listen(backlog)?;
+    debug!(
+        "Listener for {} built with SO_REUSEADDR {} and a backlog of {}",
+        address, config.reuse_address, backlog
+    );
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpStream;
+
+    #[test]
+    fn test_reuse_addr_rebinds_a_port_just_released() {
+        let config = ServerConfig {
+            reuse_address: true,
+            backlog: Some(16),
+            ..ServerConfig::default()
+        };
+        let listener = bind_listener("127.0.0.1:0", &config).unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // Closing the accepted connection first leaves it in TIME_WAIT on the server's port
+        let client = TcpStream::connect(&address).unwrap();
+        let (accepted, _) = listener.accept().unwrap();
+        drop(accepted);
+        drop(listener);
+        drop(client);
+
+        let listener = bind_listener(&address, &config).unwrap();
+        assert_eq!(listener.local_addr().unwrap().to_string(), address);
+    }
+}
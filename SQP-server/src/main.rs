@@ -1,37 +1,106 @@
 use clap::{App, Arg, SubCommand};
-use log::{debug, error, info};
-use rand::Rng;
+use log::{debug, error, info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
-use std::sync::{Arc, Mutex};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
+use std::time::Instant;
 
-use SQP_common::error::{Error as SqpError, Error};
-use SQP_common::server_utils::{parse_token_from_response, receive_message, send_message};
+use SQP_common::error::{Error as SqpError, Error, NetworkError};
+use SQP_common::radar::render_radar_bytes;
+use SQP_common::server_utils::{
+    parse_token_from_response, receive_message, send_message, PROTOCOL_VERSION,
+};
+
+mod config;
+use config::ServerConfig;
+
+mod stats;
+use stats::{maze_stats, Stats};
+
+mod status;
+use status::serve_status;
 
 mod maze_generator;
-use maze_generator::generate_maze;
+use maze_generator::{generate_maze, generate_maze_with_min_exit_distance, Maze};
+
+mod maze_id;
+use maze_id::{maze_id, parse_maze_id};
 
 mod encoder;
 use encoder::encode;
 
-mod server_request_models;
-use crate::server_request_models::Direction;
-use server_request_models::{Action, Message, RegisterTeam, SubscribePlayer};
+mod warmup;
+use warmup::{run_warmup, WARMUP_DURATION};
+
+mod listener;
+use listener::bind_listener;
+
+mod solver;
+use solver::{compare_strategies, comparison_table};
+
+mod wire;
+use crate::wire::Direction;
+use wire::{
+    Action, IncomingMessage, Message, RegisterTeam, ShareSecret, SubscribePlayer, Teleport,
+    ToggleWall,
+};
 use SQP_common::error::NetworkError::SendPayloadFailed;
 use SQP_common::logger;
 
+#[derive(Clone, Serialize, Deserialize)]
 struct Labyrinth {
     width: usize,
     height: usize,
     cells: Vec<Vec<Cell>>,
     exit_position: (usize, usize),
+    // Cells whose floor collapsed (--collapse-after): nobody can enter them anymore
+    #[serde(skip)]
+    collapsed_cells: HashSet<(usize, usize)>,
 }
 
-#[derive(Clone)]
+impl Labyrinth {
+    /// Whether a player can leave the cell in this direction: no wall and still inside the labyrinth.
+    fn is_open(&self, (x, y): (usize, usize), direction: MapDirection) -> bool {
+        let cell = &self.cells[y][x];
+        match direction {
+            MapDirection::North => !cell.north_wall && y > 0,
+            MapDirection::East => !cell.east_wall && x + 1 < self.width,
+            MapDirection::South => !cell.south_wall && y + 1 < self.height,
+            MapDirection::West => !cell.west_wall && x > 0,
+        }
+    }
+
+    /// Moves the exit to another cell.
+    fn move_exit(&mut self, (x, y): (usize, usize)) {
+        let (exit_x, exit_y) = self.exit_position;
+        self.cells[exit_y][exit_x].has_exit = false;
+        self.cells[y][x].has_exit = true;
+        self.exit_position = (x, y);
+    }
+
+    /// Open passage of the exit cell (the first one clockwise from north if there are several),
+    /// which a player must face when arriving with --strict-exit.
+    fn exit_passage(&self) -> Option<MapDirection> {
+        [
+            MapDirection::North,
+            MapDirection::East,
+            MapDirection::South,
+            MapDirection::West,
+        ]
+        .into_iter()
+        .find(|&direction| self.is_open(self.exit_position, direction))
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 struct Cell {
     north_wall: bool,
     east_wall: bool,
@@ -48,6 +117,18 @@ struct Player {
     position: (usize, usize),
     direction: MapDirection,
     moves: usize,
+    // Every cell the player has stood on, drawn as a trail on the server map
+    visited: HashSet<(usize, usize)>,
+    // Number of times the player found the exit, which moves away each time with --endless
+    solves: usize,
+    // Draws the player's hints, so their sequence does not depend on the other players
+    rng: StdRng,
+    // Sent in secret hints, for the team's SecretSumModulo challenges
+    secret: u64,
+    // Challenge to solve before moving again (--challenge-every)
+    challenge: Option<PendingChallenge>,
+    // Hints sent to the player, oldest first, returned by GetHints
+    hints: Vec<wire::SentHint>,
 }
 
 struct Team {
@@ -55,6 +136,81 @@ struct Team {
     registration_token: String,
     expected_players: usize,
     players: Vec<String>,
+    // Secrets shared by the players of the team, by player name, to check SecretSumModulo answers
+    secrets: HashMap<String, u64>,
+}
+
+/// The registration answer of a team: its token and the number of players it expects.
+impl From<&Team> for wire::RegisterTeamResponseOk {
+    fn from(team: &Team) -> Self {
+        wire::RegisterTeamResponseOk {
+            expected_players: team.expected_players,
+            registration_token: team.registration_token.clone(),
+        }
+    }
+}
+
+impl Team {
+    /// Whether the team already has all its expected players.
+    fn is_full(&self) -> bool {
+        self.players.len() >= self.expected_players
+    }
+
+    /// Adds a player to the team, unless the name is taken or the team is full.
+    fn add_player(&mut self, name: &str) -> Result<(), String> {
+        if self.players.iter().any(|player| player == name) {
+            return Err(format!("Player name '{}' is already taken", name));
+        }
+        if self.is_full() {
+            debug!(
+                "Team '{}' already has its {} players",
+                self.name, self.expected_players
+            );
+            return Err("team full".to_string());
+        }
+        self.players.push(name.to_string());
+        Ok(())
+    }
+}
+
+/// Write side of a connection, shared by the thread serving it and the threads pushing messages
+/// to its player. Each write sends the whole buffer under the lock, so the frames written by
+/// `send_message`, one write each, never interleave.
+#[derive(Clone)]
+struct SharedWriter(Arc<Mutex<TcpStream>>);
+
+impl SharedWriter {
+    fn new(stream: TcpStream) -> SharedWriter {
+        SharedWriter(Arc::new(Mutex::new(stream)))
+    }
+}
+
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut stream = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        stream.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let mut stream = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        stream.flush()
+    }
+}
+
+impl std::fmt::Debug for SharedWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.try_lock() {
+            Ok(stream) => write!(f, "{:?}", *stream),
+            Err(_) => write!(f, "SharedWriter(<locked>)"),
+        }
+    }
 }
 
 struct ServerState {
@@ -62,6 +218,224 @@ struct ServerState {
     players: HashMap<String, Player>,
     labyrinth: Labyrinth,
     next_player_id: usize,
+    config: ServerConfig,
+    // Write side of each player's connection, to push radar views they did not ask for
+    player_streams: HashMap<String, SharedWriter>,
+    stats: Stats,
+    // Number of moves made by all players, the clock of collapsing cells
+    tick: u64,
+    // Tick at which each cell left by a player collapses
+    collapse_timers: HashMap<(usize, usize), u64>,
+    // Players connected, shared by every arena so --max-players caps the whole server
+    active_players: Arc<AtomicUsize>,
+}
+
+impl ServerState {
+    /// Creates the state of a game with no team yet.
+    fn new(labyrinth: Labyrinth, config: ServerConfig) -> ServerState {
+        ServerState {
+            teams: HashMap::new(),
+            players: HashMap::new(),
+            labyrinth,
+            next_player_id: 0,
+            config,
+            player_streams: HashMap::new(),
+            stats: Stats::default(),
+            tick: 0,
+            collapse_timers: HashMap::new(),
+            active_players: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Registers a team and returns its registration token, unique among all teams, with the
+    /// number of players granted to it (the size asked for, clamped to --max-team-size).
+    /// A name already taken is refused with the `AlreadyRegistered` error of the protocol.
+    fn register_team(
+        &mut self,
+        name: &str,
+        requested_players: Option<usize>,
+    ) -> Result<(String, usize), String> {
+        if self.teams.contains_key(name) {
+            return Err("AlreadyRegistered".to_string());
+        }
+
+        let mut registration_token = generate_token();
+        while self
+            .teams
+            .values()
+            .any(|team| team.registration_token == registration_token)
+        {
+            registration_token = generate_token();
+        }
+
+        let expected_players = self.config.granted_team_size(requested_players);
+        self.teams.insert(
+            name.to_string(),
+            Team {
+                name: name.to_string(),
+                registration_token: registration_token.clone(),
+                expected_players,
+                players: Vec::new(),
+                secrets: HashMap::new(),
+            },
+        );
+        Ok((registration_token, expected_players))
+    }
+
+    /// Adds a player to the team of the registration token and places them in the labyrinth.
+    /// Returns the key of the new player, or why they could not join.
+    fn subscribe_player(&mut self, token: &str, player_name: &str) -> Result<String, String> {
+        let team_name = self
+            .teams
+            .iter()
+            .find(|(_, team)| team.registration_token == token)
+            .map(|(name, _)| name.clone())
+            .ok_or_else(|| "Invalid registration token".to_string())?;
+
+        // Take a slot first, so that two arenas cannot both fill the last one
+        let max_players = self.config.max_players.unwrap_or(usize::MAX);
+        self.active_players
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < max_players).then_some(count + 1)
+            })
+            .map_err(|_| "server at capacity".to_string())?;
+
+        let added = self.add_player(&team_name, player_name);
+        if added.is_err() {
+            self.active_players.fetch_sub(1, Ordering::SeqCst);
+        }
+        added
+    }
+
+    /// Removes a disconnected player from the game and frees their slot.
+    fn remove_player(&mut self, player_key: &str) -> bool {
+        self.player_streams.remove(player_key);
+        if self.players.remove(player_key).is_none() {
+            return false;
+        }
+        self.active_players.fetch_sub(1, Ordering::SeqCst);
+        true
+    }
+
+    /// Moves a player straight to a cell, facing the given direction (admin Teleport, --allow-cheats).
+    /// The cell must be inside the labyrinth and not collapsed.
+    fn teleport(
+        &mut self,
+        player_key: &str,
+        (x, y): (i64, i64),
+        facing: MapDirection,
+    ) -> Result<(), String> {
+        if !self.config.allow_cheats {
+            return Err("the server was not started with --allow-cheats".to_string());
+        }
+        let in_bounds = |value: i64, size: usize| (0..size as i64).contains(&value);
+        if !in_bounds(x, self.labyrinth.width) || !in_bounds(y, self.labyrinth.height) {
+            return Err(format!(
+                "({}, {}) is outside the {}x{} labyrinth",
+                x, y, self.labyrinth.width, self.labyrinth.height
+            ));
+        }
+        let position = (x as usize, y as usize);
+        if self.labyrinth.collapsed_cells.contains(&position) {
+            return Err(format!("({}, {}) has collapsed", x, y));
+        }
+
+        let player = self
+            .players
+            .get_mut(player_key)
+            .ok_or_else(|| format!("Player {} not found in game state", player_key))?;
+        player.position = position;
+        player.direction = facing;
+        player.visited.insert(position);
+        Ok(())
+    }
+
+    /// Adds a player to a team and places them in the labyrinth.
+    /// Returns the key of the new player, or why they could not join.
+    fn add_player(&mut self, team_name: &str, player_name: &str) -> Result<String, String> {
+        let team = self
+            .teams
+            .get_mut(team_name)
+            .ok_or_else(|| format!("Team '{}' not found", team_name))?;
+        team.add_player(player_name)?;
+        // Index of the player within their team, so every team starts the same way
+        let team_index = team.players.len() - 1;
+
+        let player_id = self.next_player_id;
+        self.next_player_id += 1;
+
+        let (position, direction) = spawn_point(
+            team_index,
+            self.config.start_facing,
+            (self.labyrinth.width, self.labyrinth.height),
+        );
+        let mut rng = player_rng(self.config.seed, player_id);
+        let player = Player {
+            id: player_id,
+            name: player_name.to_string(),
+            team_name: team_name.to_string(),
+            position,
+            direction,
+            moves: 0,
+            visited: HashSet::from([position]),
+            solves: 0,
+            secret: rng.gen(),
+            rng,
+            challenge: None,
+            hints: Vec::new(),
+        };
+
+        let player_key = format!("{}/{}", team_name, player_name);
+        self.players.insert(player_key.clone(), player);
+        Ok(player_key)
+    }
+}
+
+/// Locks the state of an arena. A mutex poisoned by a thread that panicked while holding it
+/// is recovered with a warning, so one failing connection does not bring down the others.
+pub(crate) fn lock_state(state: &Mutex<ServerState>) -> MutexGuard<'_, ServerState> {
+    state.lock().unwrap_or_else(|poisoned| {
+        warn!("Recovering the game state, poisoned by a thread that panicked");
+        state.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Random generator of one player, seeded from the maze seed and the player id: a player
+/// gets the same hints under the same maze, whatever the other players do.
+fn player_rng(maze_seed: Option<u64>, player_id: usize) -> StdRng {
+    StdRng::seed_from_u64(maze_seed.unwrap_or_default().wrapping_add(player_id as u64))
+}
+
+/// Bounds of the modulo of SecretSumModulo challenges, as on the reference server.
+const CHALLENGE_MODULO_RANGE: std::ops::RangeInclusive<u64> = 2..=20;
+
+/// A SecretSumModulo challenge sent to a player, who cannot move until they answer it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PendingChallenge {
+    modulo: u64,
+    // Sum of the secrets the team shared when the challenge was sent, modulo `modulo`
+    answer: u64,
+}
+
+impl PendingChallenge {
+    /// Draws a challenge on the secrets of the player's team.
+    fn secret_sum_modulo(rng: &mut StdRng, secrets: impl Iterator<Item = u64>) -> Self {
+        let modulo = rng.gen_range(CHALLENGE_MODULO_RANGE);
+        // Summed as u128 like the client does, so large secrets cannot overflow
+        let sum: u128 = secrets.map(u128::from).sum();
+        PendingChallenge {
+            modulo,
+            answer: (sum % u128::from(modulo)) as u64,
+        }
+    }
+
+    /// The message asking the player to solve the challenge.
+    fn response(&self) -> wire::ChallengeResponse {
+        wire::ChallengeResponse {
+            challenge: wire::Challenge::SecretSumModulo(self.modulo),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -72,50 +446,91 @@ enum MapDirection {
     West,
 }
 
-// Message types from client
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
-enum ClientMessage {
-    #[serde(rename_all = "camelCase")]
-    RegisterTeam { name: String },
-    #[serde(rename_all = "camelCase")]
-    SubscribePlayer {
-        name: String,
-        registration_token: String,
-    },
-    #[serde(rename_all = "camelCase")]
-    Action { action: PlayerAction },
+impl FromStr for MapDirection {
+    type Err = String;
+
+    /// Parses a direction given on the command line, case-insensitive: "north" or "n", etc.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "north" | "n" => Ok(MapDirection::North),
+            "south" | "s" => Ok(MapDirection::South),
+            "east" | "e" => Ok(MapDirection::East),
+            "west" | "w" => Ok(MapDirection::West),
+            _ => Err(format!(
+                "unknown direction '{}', expected north/n, south/s, east/e or west/w",
+                s
+            )),
+        }
+    }
+}
+
+/// How the angle of compass hints strays from the bearing of the exit (--compass-noise-model).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+enum CompassNoiseModel {
+    /// Exact far from the exit, noisier as the player gets closer.
+    Near,
+    /// Exact next to the exit, noisier as the player gets farther.
+    Far,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
-enum PlayerAction {
-    #[serde(rename_all = "camelCase")]
-    MoveTo(Direction),
-    #[serde(rename_all = "camelCase")]
-    SolveChallenge { answer: String },
+/// How radar views are turned (--radar-orientation).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+enum RadarOrientation {
+    /// The front of the player at the top, as on the reference server.
+    Relative,
+    /// North at the top, whatever the player faces.
+    Absolute,
 }
 
-#[derive(Debug, Serialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
-enum RegisterTeamResult {
-    Ok {
-        expected_players: usize,
-        registration_token: String,
-    },
-    Error(String),
+impl FromStr for RadarOrientation {
+    type Err = String;
+
+    /// Parses a radar orientation given on the command line, case-insensitive: "absolute" or
+    /// "relative".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "relative" => Ok(RadarOrientation::Relative),
+            "absolute" => Ok(RadarOrientation::Absolute),
+            _ => Err(format!(
+                "unknown radar orientation '{}', expected absolute or relative",
+                s
+            )),
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
-enum SubscribePlayerResult {
-    Ok,
-    Error(String),
+/// Largest deviation of a compass hint, in degrees, whatever the distance.
+const MAX_COMPASS_NOISE: f64 = 90.0;
+
+/// Moves between two secret hints: every fourth hint gives the player's secret instead of a compass.
+const SECRET_HINT_EVERY: usize = 32;
+
+impl CompassNoiseModel {
+    /// Largest deviation, in degrees, of a hint given this many moves away from the exit.
+    fn max_deviation(self, distance: usize) -> f64 {
+        match self {
+            CompassNoiseModel::Near => MAX_COMPASS_NOISE / (distance as f64 + 1.0),
+            CompassNoiseModel::Far => {
+                (10.0 * distance.saturating_sub(1) as f64).min(MAX_COMPASS_NOISE)
+            }
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct RelativeCompass {
-    angle: f64,
+impl FromStr for CompassNoiseModel {
+    type Err = String;
+
+    /// Parses a noise model given on the command line, case-insensitive: "near" or "far".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "near" => Ok(CompassNoiseModel::Near),
+            "far" => Ok(CompassNoiseModel::Far),
+            _ => Err(format!(
+                "unknown compass noise model '{}', expected near or far",
+                s
+            )),
+        }
+    }
 }
 
 fn main() {
@@ -132,10 +547,13 @@ fn main() {
             "challenge",
             "hint",
             "server_message",
+            "radar",
         ],
     )
     .expect("Failed to initialize logging");
 
+    let started = Instant::now();
+
     // Parse command line arguments
     let matches = App::new("SQP Server")
         .version("1.0.0")
@@ -167,6 +585,278 @@ fn main() {
                         .help("Maze dimensions in format WIDTHxHEIGHT (e.g., 5,5)")
                         .takes_value(true)
                         .default_value("5,5"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .value_name("SEED")
+                        .help("Seed of the labyrinth generator, to serve the same maze on every run (random by default)")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<u64>().map(|_| ()).map_err(|e| e.to_string())),
+                )
+                .arg(
+                    Arg::with_name("combined-hint")
+                        .long("combined-hint")
+                        .help("Send hints inside the radar view message")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("allow-cheats")
+                        .long("allow-cheats")
+                        .help("Accept admin messages that change the labyrinth at runtime")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("stats-out")
+                        .long("stats-out")
+                        .value_name("PATH")
+                        .help("Write the game statistics as JSON to this file on shutdown")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("save-maze")
+                        .long("save-maze")
+                        .value_name("PATH")
+                        .help("Write the generated labyrinth as JSON to this file, to serve it again with --load-maze")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("load-maze")
+                        .long("load-maze")
+                        .value_name("PATH")
+                        .help("Serve the labyrinth of a file written by --save-maze instead of generating one")
+                        .takes_value(true)
+                        .conflicts_with("count-only"),
+                )
+                .arg(
+                    Arg::with_name("start-facing")
+                        .long("start-facing")
+                        .value_name("DIRECTION")
+                        .help("Direction every player faces when joining (e.g. north)")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<MapDirection>().map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("collapse-after")
+                        .long("collapse-after")
+                        .value_name("TICKS")
+                        .help("Collapse a cell this many moves after a player left it")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<u64>() {
+                            Ok(ticks) if ticks > 0 => Ok(()),
+                            _ => Err("expected a positive number of ticks".to_string()),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("radar-noise")
+                        .long("radar-noise")
+                        .value_name("PROBABILITY")
+                        .help("Flip a random passage bit in radar views with this probability")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<f64>() {
+                            Ok(probability) if (0.0..=1.0).contains(&probability) => Ok(()),
+                            _ => Err("expected a probability between 0 and 1".to_string()),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("arenas")
+                        .long("arenas")
+                        .value_name("PORTS")
+                        .help("Host one independent game per port (e.g. 8778,8779), instead of --port")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("maze-id")
+                        .long("maze-id")
+                        .value_name("ID")
+                        .help("Recreate the arena of a maze id printed by a previous run (overrides --maze and game options)")
+                        .takes_value(true)
+                        .validator(|value| parse_maze_id(&value).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("count-only")
+                        .long("count-only")
+                        .help("Generate the maze, print its statistics as JSON and exit without serving it")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("profile")
+                        .long("profile")
+                        .help("Log how long each phase of the maze generation takes in server-log/main.log")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("log-radar")
+                        .long("log-radar")
+                        .help("Log every radar view sent as an ASCII map in server-log/radar.log")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("max-team-size")
+                        .long("max-team-size")
+                        .value_name("PLAYERS")
+                        .help("Largest team a client can ask for when registering (default 3)")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<usize>() {
+                            Ok(size) if size > 0 => Ok(()),
+                            _ => Err("expected a positive number of players".to_string()),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("max-name-length")
+                        .long("max-name-length")
+                        .value_name("CHARACTERS")
+                        .help("Longest team or player name accepted (default 64)")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<usize>() {
+                            Ok(length) if length > 0 => Ok(()),
+                            _ => Err("expected a positive number of characters".to_string()),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("los")
+                        .long("los")
+                        .help("Hide the radar cells a wall blocks the line of sight to")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("compass-noise-model")
+                        .long("compass-noise-model")
+                        .value_name("MODEL")
+                        .help("Point hints at the exit, noisier near it (near) or far from it (far)")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<CompassNoiseModel>().map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("radar-orientation")
+                        .long("radar-orientation")
+                        .value_name("ORIENTATION")
+                        .help("Turn radar views with the player (relative, the default) or keep north at the top (absolute)")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<RadarOrientation>().map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("mark-visited")
+                        .long("mark-visited")
+                        .help("Mark the cells a player already stood on in their radar views")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("reveal-radius")
+                        .long("reveal-radius")
+                        .value_name("CELLS")
+                        .help("After each move, print only the cells within CELLS cells of the player who moved")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<usize>().map(|_| ()).map_err(|e| e.to_string())),
+                )
+                .arg(
+                    Arg::with_name("sense-radius")
+                        .long("sense-radius")
+                        .value_name("CELLS")
+                        .help("Add the bearing of the exit to radar views when it is out of view but within CELLS cells")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<usize>() {
+                            Ok(radius) if radius > RADAR_RADIUS => Ok(()),
+                            _ => Err(format!(
+                                "expected a number of cells larger than the radar radius ({})",
+                                RADAR_RADIUS
+                            )),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("challenge-every")
+                        .long("challenge-every")
+                        .value_name("MOVES")
+                        .help("Every MOVES moves, give the player a SecretSumModulo challenge to solve before moving again")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<usize>() {
+                            Ok(moves) if moves > 0 => Ok(()),
+                            _ => Err("expected a positive number of moves".to_string()),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("min-exit-distance")
+                        .long("min-exit-distance")
+                        .value_name("MOVES")
+                        .help("Regenerate the maze until its exit is at least MOVES moves from the start")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<usize>().map(|_| ()).map_err(|e| e.to_string())),
+                )
+                .arg(
+                    Arg::with_name("warmup")
+                        .long("warmup")
+                        .value_name("CLIENTS")
+                        .help("Benchmark: run CLIENTS mock clients making random moves, report throughput and p99 latency, then exit")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<usize>().map(|_| ()).map_err(|e| e.to_string())),
+                )
+                .arg(
+                    Arg::with_name("endless")
+                        .long("endless")
+                        .help("Practice mode: the exit moves away each time a player finds it")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("strict-exit")
+                        .long("strict-exit")
+                        .help("Only win when arriving on the exit facing its open passage")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("max-players")
+                        .long("max-players")
+                        .value_name("PLAYERS")
+                        .help("Most players connected at once over every arena (default unlimited)")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<usize>() {
+                            Ok(players) if players > 0 => Ok(()),
+                            _ => Err("expected a positive number of players".to_string()),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("reuse-addr")
+                        .long("reuse-addr")
+                        .help("Set SO_REUSEADDR on the game listeners, to restart on ports of connections still closing")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("backlog")
+                        .long("backlog")
+                        .value_name("CONNECTIONS")
+                        .help("Connections waiting to be accepted by each game listener (default 128)")
+                        .takes_value(true)
+                        .validator(|value| match value.parse::<i32>() {
+                            Ok(backlog) if backlog > 0 => Ok(()),
+                            _ => Err("expected a positive number of connections".to_string()),
+                        }),
+                )
+                .arg(
+                    Arg::with_name("http-status")
+                        .long("http-status")
+                        .value_name("PORT")
+                        .help("Serve a JSON status page (uptime, teams, players, moves) over HTTP on this port")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<u16>().map(|_| ()).map_err(|e| e.to_string())),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Run every solver strategy on the same labyrinth and print their moves to the exit")
+                .arg(
+                    Arg::with_name("maze")
+                        .long("maze")
+                        .value_name("DIMENSIONS")
+                        .help("Maze dimensions in format WIDTH,HEIGHT (e.g., 5,5)")
+                        .takes_value(true)
+                        .default_value("5,5"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .value_name("SEED")
+                        .help("Seed of the labyrinth generator (random by default)")
+                        .takes_value(true)
+                        .validator(|value| value.parse::<u64>().map(|_| ()).map_err(|e| e.to_string())),
                 ),
         )
         .arg(
@@ -177,6 +867,29 @@ fn main() {
         )
         .get_matches();
 
+    // Compare the solver strategies instead of serving games
+    if let Some(compare_matches) = matches.subcommand_matches("compare") {
+        let Some((width, height)) =
+            parse_maze_dimensions(compare_matches.value_of("maze").unwrap())
+        else {
+            error!("Invalid maze dimensions. Expected format: WIDTH,HEIGHT, both above 0");
+            std::process::exit(1);
+        };
+        let seed = compare_matches
+            .value_of("seed")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or_else(|| rand::thread_rng().gen());
+
+        let labyrinth = generate_labyrinth(width, height, seed, None, false);
+        let (start, facing) = spawn_point(0, None, (width, height));
+        println!("Maze {}x{} with seed {}", width, height, seed);
+        print!(
+            "{}",
+            comparison_table(&compare_strategies(&labyrinth, start, facing))
+        );
+        return;
+    }
+
     // Check for the "run" subcommand
     let run_matches = if let Some(matches) = matches.subcommand_matches("run") {
         matches
@@ -192,7 +905,6 @@ fn main() {
         .parse::<u16>()
         .expect("Invalid port number");
     let host = run_matches.value_of("host-address").unwrap();
-    let address = format!("{}:{}", host, port);
 
     // Parse maze dimensions
     let maze_dimensions = run_matches.value_of("maze").unwrap();
@@ -211,89 +923,429 @@ fn main() {
         .parse::<usize>()
         .expect("Invalid maze height");
 
-    // Initialize server state
-    let state = Arc::new(Mutex::new(ServerState {
-        teams: HashMap::new(),
-        players: HashMap::new(),
-        labyrinth: generate_labyrinth(width, height),
-        next_player_id: 0,
-    }));
+    let config = ServerConfig {
+        combine_hint_with_radar: run_matches.is_present("combined-hint"),
+        allow_cheats: run_matches.is_present("allow-cheats"),
+        stats_out: run_matches.value_of("stats-out").map(String::from),
+        save_maze: run_matches.value_of("save-maze").map(String::from),
+        start_facing: run_matches
+            .value_of("start-facing")
+            .map(|value| value.parse().unwrap()),
+        collapse_after: run_matches
+            .value_of("collapse-after")
+            .map(|value| value.parse().unwrap()),
+        radar_noise: run_matches
+            .value_of("radar-noise")
+            .map(|value| value.parse().unwrap()),
+        seed: run_matches
+            .value_of("seed")
+            .map(|value| value.parse().unwrap()),
+        log_radar: run_matches.is_present("log-radar"),
+        profile: run_matches.is_present("profile"),
+        max_name_length: run_matches
+            .value_of("max-name-length")
+            .map(|value| value.parse().unwrap()),
+        max_team_size: run_matches
+            .value_of("max-team-size")
+            .map(|value| value.parse().unwrap()),
+        max_players: run_matches
+            .value_of("max-players")
+            .map(|value| value.parse().unwrap()),
+        line_of_sight: run_matches.is_present("los"),
+        strict_exit: run_matches.is_present("strict-exit"),
+        endless: run_matches.is_present("endless"),
+        mark_visited: run_matches.is_present("mark-visited"),
+        compass_noise_model: run_matches
+            .value_of("compass-noise-model")
+            .map(|value| value.parse().unwrap()),
+        radar_orientation: run_matches
+            .value_of("radar-orientation")
+            .map(|value| value.parse().unwrap()),
+        min_exit_distance: run_matches
+            .value_of("min-exit-distance")
+            .map(|value| value.parse().unwrap()),
+        sense_radius: run_matches
+            .value_of("sense-radius")
+            .map(|value| value.parse().unwrap()),
+        challenge_every: run_matches
+            .value_of("challenge-every")
+            .map(|value| value.parse().unwrap()),
+        reveal_radius: run_matches
+            .value_of("reveal-radius")
+            .map(|value| value.parse().unwrap()),
+        reuse_address: run_matches.is_present("reuse-addr"),
+        backlog: run_matches
+            .value_of("backlog")
+            .map(|value| value.parse().unwrap()),
+    };
+
+    // A maze id brings back the dimensions, seed and game options of a previous arena
+    let (width, height, mut config) = match run_matches.value_of("maze-id") {
+        Some(id) => {
+            let (width, height, id_config) = parse_maze_id(id).unwrap();
+            let config = ServerConfig {
+                stats_out: config.stats_out,
+                save_maze: config.save_maze,
+                max_players: config.max_players,
+                reveal_radius: config.reveal_radius,
+                profile: config.profile,
+                reuse_address: config.reuse_address,
+                backlog: config.backlog,
+                ..id_config
+            };
+            (width, height, config)
+        }
+        None => (width, height, config),
+    };
+
+    // A saved labyrinth replaces the generated one, whatever the generation options
+    let loaded_labyrinth = run_matches.value_of("load-maze").map(|path| {
+        let generation_args: Vec<String> = ["maze", "seed", "maze-id", "min-exit-distance"]
+            .into_iter()
+            .filter(|arg| run_matches.occurrences_of(arg) > 0)
+            .map(|arg| format!("--{}", arg))
+            .collect();
+        if !generation_args.is_empty() {
+            warn!(
+                "--load-maze {} wins over {}: the labyrinth is not generated",
+                path,
+                generation_args.join(", ")
+            );
+        }
+        match load_labyrinth(path) {
+            Ok(labyrinth) => labyrinth,
+            Err(e) => {
+                error!("Failed to load the labyrinth from {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    });
+    let (width, height) = match &loaded_labyrinth {
+        Some(labyrinth) => (labyrinth.width, labyrinth.height),
+        None => (width, height),
+    };
+
+    // The radar is fixed by the protocol, only warn when the maze cannot fill it
+    if fit_view_radius(RADAR_RADIUS, width, height).1.is_some() {
+        warn!(
+            "The maze is {}x{}, smaller than the 3x3 radar views: they will mostly show the maze edges",
+            width, height
+        );
+    }
+    if let Some(radius) = config.reveal_radius {
+        let (fitted, warning) = fit_view_radius(radius, width, height);
+        if let Some(warning) = warning {
+            warn!("--reveal-radius: {}", warning);
+        }
+        config.reveal_radius = Some(fitted);
+    }
+
+    // Print the statistics of the maze instead of serving it, to tune the generator
+    if run_matches.is_present("count-only") {
+        let seed = *config.seed.get_or_insert_with(|| rand::thread_rng().gen());
+        let maze = generate_arena_maze(
+            width,
+            height,
+            seed,
+            config.min_exit_distance,
+            config.profile,
+        );
+        let mut stats = maze_stats(&labyrinth_from_maze(&maze), maze.exit_distance);
+        stats["seed"] = json!(seed);
+        stats["maze_id"] = json!(maze_id(width, height, &config));
+        println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+        return;
+    }
+
+    // One arena per port, each with its own state and labyrinth
+    let ports: Vec<u16> = match run_matches.value_of("arenas") {
+        Some(arenas) => arenas
+            .split(',')
+            .map(|port| port.trim().parse::<u16>().expect("Invalid arena port"))
+            .collect(),
+        None => vec![port],
+    };
+    // The player cap counts the players of every arena
+    let active_players = Arc::new(AtomicUsize::new(0));
+    let arenas: Vec<(String, Arc<Mutex<ServerState>>)> = ports
+        .iter()
+        .map(|port| {
+            let state = match &loaded_labyrinth {
+                Some(labyrinth) => Arc::new(Mutex::new(ServerState::new(
+                    labyrinth.clone(),
+                    config.clone(),
+                ))),
+                None => new_arena_state(width, height, config.clone()),
+            };
+            lock_state(&state).active_players = Arc::clone(&active_players);
+            (format!("{}:{}", host, port), state)
+        })
+        .collect();
 
-    // Print the initial labyrinth
+    // Write the statistics and exit on Ctrl-C or SIGTERM
     {
-        let state_lock = state.lock().unwrap();
-        print_labyrinth(&state_lock);
-        drop(state_lock);
+        let arenas = arenas.clone();
+        let stats_out = config.stats_out.clone();
+        ctrlc::set_handler(move || {
+            info!("Shutting down the server");
+            if let Some(path) = &stats_out {
+                if let Err(e) = write_arenas_stats(&arenas, path) {
+                    error!("Failed to write statistics to {}: {}", path, e);
+                }
+            }
+            std::process::exit(0);
+        })
+        .expect("Failed to set the shutdown handler");
     }
 
-    info!("Server is running on {}", address);
-    println!("Server is running on {}:{}", host, port);
     println!("Maze dimensions: {}x{}", width, height);
 
-    // Start server
-    match TcpListener::bind(&address) {
-        Ok(listener) => {
-            debug!("Listener bound successfully to {}", address);
-
-            for stream in listener.incoming() {
-                match stream {
-                    Ok(stream) => {
-                        debug!("New connection from {:?}", stream.peer_addr());
-                        let state_clone = Arc::clone(&state);
-                        thread::spawn(move || {
-                            if let Err(e) = handle_client(stream, state_clone) {
-                                error!("Error handling client: {}", e);
-                            }
-                        });
-                    }
-                    Err(e) => {
-                        error!("Connection failed: {}", e);
-                    }
-                }
+    // Serve the status page next to the games
+    if let Some(status_port) = run_matches.value_of("http-status") {
+        let address = format!("{}:{}", host, status_port);
+        match TcpListener::bind(&address) {
+            Ok(listener) => {
+                info!("Status page is served on http://{}", address);
+                let states = arenas.iter().map(|(_, state)| Arc::clone(state)).collect();
+                thread::spawn(move || serve_status(listener, states, started));
             }
-        }
-        Err(e) => {
-            error!("Failed to bind to {}: {}", address, e);
+            Err(e) => error!("Failed to bind the status page to {}: {}", address, e),
         }
     }
-}
-
-/// Generate a labyrinth using the recursive backtracking algorithm
-fn generate_labyrinth(width: usize, height: usize) -> Labyrinth {
-    let maze = generate_maze(width, height);
 
-    // Convert the maze cells to our Labyrinth format
-    let mut cells = Vec::with_capacity(height);
+    // Start one accept loop per arena
+    let mut handles = Vec::new();
+    let mut bound_addresses = Vec::new();
+    for (address, state) in arenas {
+        // Print the initial labyrinth
+        {
+            let state_lock = lock_state(&state);
+            print_labyrinth(&state_lock);
+            drop(state_lock);
+        }
 
-    for y in 0..height {
-        let mut row = Vec::with_capacity(width);
-        for x in 0..width {
-            let maze_cell = &maze.cells[y][x];
-            row.push(Cell {
-                north_wall: maze_cell.north_wall,
-                east_wall: maze_cell.east_wall,
-                south_wall: maze_cell.south_wall,
-                west_wall: maze_cell.west_wall,
-                has_hint: maze_cell.has_hint,
-                has_exit: maze_cell.has_exit,
-            });
+        match bind_listener(&address, &config) {
+            Ok(listener) => {
+                debug!("Listener bound successfully to {}", address);
+                info!("Server is running on {}", address);
+                println!("Server is running on {}", address);
+                if let Ok(local_address) = listener.local_addr() {
+                    bound_addresses.push(local_address);
+                }
+                handles.push(thread::spawn(move || serve_arena(listener, state)));
+            }
+            Err(e) => {
+                error!("Failed to bind to {}: {}", address, e);
+            }
         }
-        cells.push(row);
     }
 
-    let exit_position = maze.exit_position;
-
-    // Print info about the generated maze
-    println!(
-        "Created new {}x{} labyrinth with exit at ({}, {})",
-        width, height, exit_position.0, exit_position.1
+    // Benchmark the first arena with mock clients instead of waiting for players
+    if let Some(clients) = run_matches.value_of("warmup") {
+        let clients = clients.parse().unwrap();
+        let Some(&address) = bound_addresses.first() else {
+            error!("No arena to warm up");
+            std::process::exit(1);
+        };
+        match run_warmup(address, clients, WARMUP_DURATION) {
+            Ok(report) => {
+                info!("{}", report);
+                println!("{}", report);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                error!("Warmup failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    for handle in handles {
+        if handle.join().is_err() {
+            error!("An arena stopped unexpectedly");
+        }
+    }
+}
+
+/// Cells a radar view spans from the player to its edge: radar views are 3x3.
+const RADAR_RADIUS: usize = 1;
+
+/// Fits a view of `radius` cells around the player, 2 * radius + 1 cells wide, in a maze of
+/// `width` by `height` cells. A view larger than the maze is clamped to the largest one that
+/// fits, returned with the warning to log.
+fn fit_view_radius(radius: usize, width: usize, height: usize) -> (usize, Option<String>) {
+    let largest = (width.min(height).max(1) - 1) / 2;
+    if radius <= largest {
+        return (radius, None);
+    }
+    let warning = format!(
+        "radius {} needs a maze of at least {}x{} cells, the maze is {}x{}: using radius {}",
+        radius,
+        2 * radius + 1,
+        2 * radius + 1,
+        width,
+        height,
+        largest
     );
+    (largest, Some(warning))
+}
 
-    Labyrinth {
+/// Creates the state of one arena, with a freshly generated labyrinth.
+/// Without a seed in the config, a random one is picked; the maze id printed recreates the arena.
+/// With --save-maze, the labyrinth is also written to that file.
+fn new_arena_state(
+    width: usize,
+    height: usize,
+    mut config: ServerConfig,
+) -> Arc<Mutex<ServerState>> {
+    let seed = *config.seed.get_or_insert_with(|| rand::thread_rng().gen());
+    println!("Maze id: {}", maze_id(width, height, &config));
+    let labyrinth = generate_labyrinth(
+        width,
+        height,
+        seed,
+        config.min_exit_distance,
+        config.profile,
+    );
+    if let Some(path) = &config.save_maze {
+        match save_labyrinth(&labyrinth, path) {
+            Ok(()) => println!("Labyrinth saved to {}", path),
+            Err(e) => error!("Failed to save the labyrinth to {}: {}", path, e),
+        }
+    }
+    Arc::new(Mutex::new(ServerState::new(labyrinth, config)))
+}
+
+/// Writes a labyrinth as JSON: its dimensions, cells and exit, not the cells collapsed in a game.
+fn save_labyrinth(labyrinth: &Labyrinth, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(path, serde_json::to_string_pretty(labyrinth)?)?;
+    Ok(())
+}
+
+/// Reads a labyrinth written by save_labyrinth, checking its cells fill its dimensions and its
+/// exit is one of them.
+fn load_labyrinth(path: &str) -> Result<Labyrinth, Box<dyn std::error::Error>> {
+    let labyrinth: Labyrinth = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let (exit_x, exit_y) = labyrinth.exit_position;
+    if labyrinth.cells.len() != labyrinth.height
+        || labyrinth
+            .cells
+            .iter()
+            .any(|row| row.len() != labyrinth.width)
+        || exit_x >= labyrinth.width
+        || exit_y >= labyrinth.height
+    {
+        return Err(format!(
+            "the cells or the exit do not fit a {}x{} labyrinth",
+            labyrinth.width, labyrinth.height
+        )
+        .into());
+    }
+    Ok(labyrinth)
+}
+
+/// Accepts the connections of one arena, handling each client in its own thread.
+fn serve_arena(listener: TcpListener, state: Arc<Mutex<ServerState>>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                debug!("New connection from {:?}", stream.peer_addr());
+                let state_clone = Arc::clone(&state);
+                thread::spawn(move || {
+                    if let Err(e) = handle_client(stream, state_clone) {
+                        error!("Error handling client: {}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                error!("Connection failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Generate a labyrinth using the recursive backtracking algorithm
+/// The same seed and dimensions always give the same labyrinth
+/// With a minimum exit distance, the exit is at least that many moves from where generation started
+/// With `profile`, the time spent in each phase of the generation is logged to the "main" category
+fn generate_labyrinth(
+    width: usize,
+    height: usize,
+    seed: u64,
+    min_exit_distance: Option<usize>,
+    profile: bool,
+) -> Labyrinth {
+    let labyrinth = labyrinth_from_maze(&generate_arena_maze(
         width,
         height,
+        seed,
+        min_exit_distance,
+        profile,
+    ));
+    let exit_position = labyrinth.exit_position;
+
+    // Print info about the generated maze
+    println!(
+        "Created new {}x{} labyrinth with exit at ({}, {})",
+        width, height, exit_position.0, exit_position.1
+    );
+
+    labyrinth
+}
+
+/// Generates the maze of an arena, see generate_labyrinth for the options.
+fn generate_arena_maze(
+    width: usize,
+    height: usize,
+    seed: u64,
+    min_exit_distance: Option<usize>,
+    profile: bool,
+) -> Maze {
+    let maze = match min_exit_distance {
+        Some(distance) => {
+            generate_maze_with_min_exit_distance(width, height, seed, distance, profile)
+        }
+        None => generate_maze(width, height, seed, profile),
+    };
+    debug!(
+        "Maze {}x{} carved from {:?}, exit at {:?} {} moves away",
+        width, height, maze.start_position, maze.exit_position, maze.exit_distance
+    );
+    if let Some(profile) = maze.profile {
+        let message = format!("Maze {}x{} generated: {}", width, height, profile);
+        info!("{}", message);
+        if let Err(e) = logger::log_message("main", &message) {
+            error!("Failed to log the maze generation profile: {}", e);
+        }
+    }
+    maze
+}
+
+/// Converts the cells of a generated maze to our Labyrinth format.
+fn labyrinth_from_maze(maze: &Maze) -> Labyrinth {
+    let cells = maze
+        .cells
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|maze_cell| Cell {
+                    north_wall: maze_cell.north_wall,
+                    east_wall: maze_cell.east_wall,
+                    south_wall: maze_cell.south_wall,
+                    west_wall: maze_cell.west_wall,
+                    has_hint: maze_cell.has_hint,
+                    has_exit: maze_cell.has_exit,
+                })
+                .collect()
+        })
+        .collect();
+
+    Labyrinth {
+        width: maze.width,
+        height: maze.height,
         cells,
-        exit_position,
+        exit_position: maze.exit_position,
+        collapsed_cells: HashSet::new(),
     }
 }
 
@@ -305,22 +1357,19 @@ fn handle_client(
     let peer_addr = stream.peer_addr()?;
     debug!("New connection from {}", peer_addr);
 
+    // Every answer goes through the writer that other players' threads push messages to
+    let mut writer = SharedWriter::new(stream.try_clone()?);
     let mut player_key: Option<String> = None;
 
     // Keep the connection open and handle multiple messages
     loop {
         let message_str = match receive_message(&mut stream) {
             Ok(msg) => msg,
+            Err(SqpError::Network(NetworkError::ConnectionClosed)) => {
+                debug!("Connection from {} closed by client", peer_addr);
+                break;
+            }
             Err(e) => {
-                match e {
-                    SqpError::Network(ref ne) => {
-                        if ne.to_string().contains("Connection closed by peer") {
-                            debug!("Connection from {} closed by client", peer_addr);
-                            break;
-                        }
-                    }
-                    _ => {}
-                }
                 error!("Failed to receive message from {}: {}", peer_addr, e);
                 break;
             }
@@ -329,7 +1378,7 @@ fn handle_client(
         debug!("Read string message: {}", message_str);
 
         // Parse the message using our request models
-        let message: Message = match serde_json::from_str(&message_str) {
+        let IncomingMessage { message, seq } = match serde_json::from_str(&message_str) {
             Ok(v) => v,
             Err(e) => {
                 error!("Failed to parse message as JSON: {}", e);
@@ -337,44 +1386,41 @@ fn handle_client(
             }
         };
 
+        // Reject messages that parsed but make no sense, telling the client why
+        let max_name_length = lock_state(&state).config.max_name_length();
+        if let Err(reason) = validate_message(&message, max_name_length) {
+            error!("Invalid message from {}: {}", peer_addr, reason);
+            if let Err(e) = send_validation_error(&mut writer, &message, reason) {
+                error!("Failed to send validation error: {}", e);
+                break;
+            }
+            continue;
+        }
+
         // Handle different message types
         match message {
             Message::RegisterTeam(register_team) => {
-                if let Err(e) = handle_register_team(&mut stream, &register_team, state.clone()) {
+                if let Err(e) = handle_register_team(&mut writer, &register_team, state.clone()) {
                     error!("Error handling team registration: {}", e);
                     break;
                 }
             }
             Message::SubscribePlayer(subscribe_player) => {
                 // When a player subscribes, remember their key
-                let player_name = &subscribe_player.name;
-                let token = &subscribe_player.registration_token;
-
-                // Find the team with this token
-                let team_name = {
-                    let state = state.lock().unwrap();
-                    state
-                        .teams
-                        .iter()
-                        .find(|(_, team)| team.registration_token == *token)
-                        .map(|(name, _)| name.clone())
-                };
-
-                if let Some(team_name) = team_name {
-                    player_key = Some(format!("{}/{}", team_name, player_name));
-                }
-
-                if let Err(e) =
-                    handle_subscribe_player(&mut stream, &subscribe_player, state.clone())
-                {
-                    error!("Error handling player subscription: {}", e);
-                    break;
+                match handle_subscribe_player(&mut writer, &subscribe_player, state.clone()) {
+                    Ok(Some(key)) => player_key = Some(key),
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("Error handling player subscription: {}", e);
+                        break;
+                    }
                 }
             }
             Message::Action(action) => {
                 if let Err(e) = handle_action(
-                    &mut stream,
+                    &mut writer,
                     &action,
+                    seq,
                     state.clone(),
                     peer_addr,
                     player_key.clone(),
@@ -383,13 +1429,34 @@ fn handle_client(
                     break;
                 }
             }
+            Message::ToggleWall(toggle_wall) => {
+                if let Err(e) = handle_toggle_wall(&mut writer, &toggle_wall, state.clone()) {
+                    error!("Error handling wall toggle: {}", e);
+                    break;
+                }
+            }
+            Message::Teleport(teleport) => {
+                if let Err(e) =
+                    handle_teleport(&mut writer, &teleport, state.clone(), player_key.clone())
+                {
+                    error!("Error handling teleport: {}", e);
+                    break;
+                }
+            }
+            Message::ShareSecret(share_secret) => {
+                if let Err(e) =
+                    handle_share_secret(&share_secret, state.clone(), player_key.clone())
+                {
+                    error!("Error handling shared secret: {}", e);
+                    break;
+                }
+            }
         }
     }
 
     // Clean up player if they were registered
     if let Some(key) = player_key {
-        let mut state = state.lock().unwrap();
-        if state.players.remove(&key).is_some() {
+        if lock_state(&state).remove_player(&key) {
             info!("Player {} disconnected and removed from game", key);
         }
     }
@@ -398,8 +1465,74 @@ fn handle_client(
     Ok(())
 }
 
+/// Checks the content of a message the client sent: team and player names must not be blank
+/// nor longer than `max_name_length` characters, and registration tokens must be 16 hex digits.
+fn validate_message(message: &Message, max_name_length: usize) -> Result<(), String> {
+    match message {
+        Message::RegisterTeam(register_team) => {
+            validate_name("Team", &register_team.name, max_name_length)?;
+        }
+        Message::SubscribePlayer(subscribe_player) => {
+            validate_name("Player", &subscribe_player.name, max_name_length)?;
+            let token = &subscribe_player.registration_token;
+            if token.len() != 16 || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!(
+                    "Registration token must be 16 hexadecimal characters, got '{}'",
+                    token
+                ));
+            }
+        }
+        Message::Action(_)
+        | Message::ToggleWall(_)
+        | Message::Teleport(_)
+        | Message::ShareSecret(_) => {}
+    }
+    Ok(())
+}
+
+/// Checks a team or player name, `kind` being used in the error message.
+fn validate_name(kind: &str, name: &str, max_name_length: usize) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(format!("{} name must not be empty", kind));
+    }
+    let length = name.chars().count();
+    if length > max_name_length {
+        return Err(format!(
+            "{} name must be at most {} characters, got {}",
+            kind, max_name_length, length
+        ));
+    }
+    Ok(())
+}
+
+/// Answers a message rejected by validate_message with the Error result of its kind.
+fn send_validation_error(
+    stream: &mut impl Write,
+    message: &Message,
+    reason: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match message {
+        Message::RegisterTeam(_) => {
+            send_message(stream, &wire::RegisterTeamResponse::from(Err(reason)))?;
+        }
+        Message::SubscribePlayer(_) => {
+            send_message(stream, &wire::SubscribePlayerResponse::from(Err(reason)))?;
+        }
+        Message::Action(_)
+        | Message::ToggleWall(_)
+        | Message::Teleport(_)
+        | Message::ShareSecret(_) => {}
+    }
+    Ok(())
+}
+
+/// Error result of a RegisterTeam whose protocol_version is not PROTOCOL_VERSION.
+const VERSION_MISMATCH: &str = "VersionMismatch";
+
+/// Registers the team and sends back its registration token.
+/// Only writes to the stream, so tests can drive it with an in-memory buffer.
 fn handle_register_team(
-    stream: &mut TcpStream,
+    stream: &mut (impl Write + std::fmt::Debug),
     message: &RegisterTeam,
     state: Arc<Mutex<ServerState>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -411,33 +1544,30 @@ fn handle_register_team(
     let team_name = &message.name;
     debug!("Subscribing for team '{}' from {:?}", team_name, stream);
 
-    // Generate a registration token (16 hex characters)
-    let registration_token = generate_token();
-
-    // Store team information
-    let mut state = state.lock().unwrap();
-    state.teams.insert(
-        team_name.to_string(),
-        Team {
-            name: team_name.to_string(),
-            registration_token: registration_token.clone(),
-            expected_players: 3, // Default to 3 players
-            players: Vec::new(),
-        },
-    );
-
-    // Create response using proper serializable structs
-    let response = server_request_models::RegisterTeamResponse {
-        RegisterTeamResult: server_request_models::RegisterTeamResponseResult::Ok(
-            server_request_models::RegisterTeamResponseOk {
-                expected_players: 3,
-                registration_token: registration_token.clone(),
-            },
-        ),
+    // Register the team, refusing names already taken and clients of another protocol version
+    let registration = match message.protocol_version {
+        Some(version) if version != PROTOCOL_VERSION => {
+            warn!(
+                "Team '{}' speaks protocol version {}, the server version {}",
+                team_name, version, PROTOCOL_VERSION
+            );
+            Err(VERSION_MISMATCH.to_string())
+        }
+        _ => {
+            let mut state = lock_state(&state);
+            state
+                .register_team(team_name, message.expected_players)
+                .map(|_| wire::RegisterTeamResponseOk::from(&state.teams[team_name]))
+        }
+    };
+    match &registration {
+        Ok(ok) => debug!("Write struct message: ClientSide(Registration(RegisterTeamResult(Ok {{ expected_players: {}, registration_token: \"{}\" }})))", ok.expected_players, ok.registration_token),
+        Err(reason) => error!("Refusing team '{}': {}", team_name, reason),
+    }
+    let response = wire::RegisterTeamResponse {
+        protocol_version: message.protocol_version.map(|_| PROTOCOL_VERSION),
+        ..wire::RegisterTeamResponse::from(registration)
     };
-
-    // Send response using utility function
-    debug!("Write struct message: ClientSide(Registration(RegisterTeamResult(Ok {{ expected_players: 3, registration_token: \"{}\" }})))", registration_token);
 
     // Send the response
     send_message(stream, &response)
@@ -446,140 +1576,129 @@ fn handle_register_team(
     Ok(())
 }
 
+/// Adds the player to the team of the registration token and sends the first radar view.
+/// Returns the key of the new player, or None if the subscription was refused.
 fn handle_subscribe_player(
-    stream: &mut TcpStream,
+    stream: &mut SharedWriter,
     message: &SubscribePlayer,
     state: Arc<Mutex<ServerState>>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
     debug!(
         "Read struct message: Registration(SubscribePlayer({:?}))",
         message
     );
 
-    let player_name = message.name.clone();
-    let token = message.registration_token.clone();
-
-    if let (player_name, token) = (player_name, token) {
-        let mut state = state.lock().unwrap();
-
-        // Find the team with this token
-        let team_name = state
-            .teams
-            .iter()
-            .find(|(_, team)| team.registration_token == *token)
-            .map(|(name, _)| name.clone());
-
-        if let Some(team_name) = team_name {
-            debug!(
-                "Subscribing for player '{}' in team '{}' from {:?}",
-                player_name, team_name, stream
-            );
-
-            // Add player to team
-            if let Some(team) = state.teams.get_mut(&team_name) {
-                team.players.push(player_name.clone());
-            }
-
-            // Create player with initial position
-            let player_id = state.next_player_id;
-            state.next_player_id += 1;
-
-            // Initialize player at different positions based on ID
-            let position = match player_id % 3 {
-                0 => (3, 4), // First player at (3, 4)
-                1 => (4, 2), // Second player at (4, 2)
-                _ => (4, 4), // Third player at (4, 4)
-            };
-
-            // Initialize player facing different directions
-            let direction = match player_id % 3 {
-                0 => MapDirection::West, // First player facing West
-                1 => MapDirection::East, // Second player facing East
-                _ => MapDirection::East, // Third player facing East
-            };
+    let player_name = &message.name;
+    let token = &message.registration_token;
+    debug!(
+        "Subscribing for player '{}' with token {} from {:?}",
+        player_name, token, stream
+    );
 
-            // Create and store player
-            let player = Player {
-                id: player_id,
-                name: player_name.clone(),
-                team_name: team_name.clone(),
-                position,
-                direction,
-                moves: 0,
-            };
+    let mut state = lock_state(&state);
 
-            let player_key = format!("{}/{}", team_name, player_name);
-            state.players.insert(player_key.clone(), player);
+    // Add player to the team of the token, refusing unknown tokens, duplicate names and full teams
+    let player_key = match state.subscribe_player(token, player_name) {
+        Ok(player_key) => player_key,
+        Err(reason) => {
+            error!("Refusing player '{}': {}", player_name, reason);
+            let response = wire::SubscribePlayerResponse::from(Err(reason));
+            send_message(stream, &response).map_err(|e| {
+                error!("Failed to send error response: {}", e);
+                Box::new(e) as Box<dyn std::error::Error>
+            })?;
+            return Ok(None);
+        }
+    };
+    state
+        .player_streams
+        .insert(player_key.clone(), stream.clone());
 
-            // Store info we need for logging
-            let player_position = position;
-            let player_direction = direction;
+    // Print the labyrinth to show player's initial position
+    let player = &state.players[&player_key];
+    info!(
+        "Player {} joined the game at position {:?} facing {:?}",
+        player_key, player.position, player.direction
+    );
+    print_labyrinth(&state);
 
-            // Print the labyrinth to show player's initial position
-            info!(
-                "Player {} joined the game at position {:?} facing {:?}",
-                player_key, player_position, player_direction
-            );
-            print_labyrinth(&state);
+    // Send OK response
+    let response = wire::SubscribePlayerResponse::from(Ok(()));
 
-            // Send OK response
-            let response = server_request_models::SubscribePlayerResponse {
-                SubscribePlayerResult: server_request_models::SubscribePlayerResponseResult::Ok,
-            };
+    debug!("Write struct message: ClientSide(Registration(SubscribePlayerResult(Ok)))");
+    send_message(stream, &response).map_err(|e| {
+        error!("Failed to send subscription response: {}", e);
+        Box::new(e) as Box<dyn std::error::Error>
+    })?;
 
-            debug!("Write struct message: ClientSide(Registration(SubscribePlayerResult(Ok)))");
-            send_message(stream, &response).map_err(|e| {
-                error!("Failed to send subscription response: {}", e);
-                Box::new(e) as Box<dyn std::error::Error>
-            })?;
+    // Send initial radar view
+    let player = &state.players[&player_key];
+    debug!(
+        "Player {{ player_id: {} }} at {:?} towards {:?}",
+        player.id, player.position, player.direction
+    );
 
-            // Send initial radar view
-            let player = state.players.get(&player_key).unwrap();
-            debug!(
-                "Player {{ player_id: {} }} at {:?} towards {:?}",
-                player.id, player.position, player.direction
-            );
+    // Generate radar view using our encode_player_radar_view function
+    let encoded_view = encode_player_radar_view(&state, player);
 
-            // Generate radar view using our encode_radar_view function
-            let encoded_view =
-                encode_radar_view(player.position, player.direction, &state.labyrinth);
+    let radar_response = wire::RadarViewResponse {
+        radar_view: encoded_view.clone(),
+        seq: None,
+        facing: Some(player.direction),
+        orientation: state.config.radar_orientation,
+        pushed: None,
+    };
 
-            let radar_response = server_request_models::RadarViewResponse {
-                RadarView: encoded_view.clone(),
-            };
+    debug!(
+        "Write struct message: ClientSide(Loop(RadarView(EncodedRadarView(\"{}\"))",
+        encoded_view
+    );
+    send_message(stream, &radar_response).map_err(|e| {
+        error!("Failed to send radar view: {}", e);
+        Box::new(e) as Box<dyn std::error::Error>
+    })?;
+    Ok(Some(player_key))
+}
 
-            debug!(
-                "Write struct message: ClientSide(Loop(RadarView(EncodedRadarView(\"{}\"))",
-                encoded_view
-            );
-            send_message(stream, &radar_response).map_err(|e| {
-                error!("Failed to send radar view: {}", e);
-                Box::new(e) as Box<dyn std::error::Error>
-            })?;
-        } else {
-            error!("Invalid registration token: {}", token);
-            // Send error response
-            let response = server_request_models::SubscribePlayerResponse {
-                SubscribePlayerResult: server_request_models::SubscribePlayerResponseResult::Error(
-                    "Invalid registration token".to_string(),
-                ),
-            };
+/// Parses maze dimensions given as "WIDTH,HEIGHT", refusing anything else and empty mazes.
+fn parse_maze_dimensions(value: &str) -> Option<(usize, usize)> {
+    let (width, height) = value.split_once(',')?;
+    let width = width.trim().parse().ok().filter(|&width| width > 0)?;
+    let height = height.trim().parse().ok().filter(|&height| height > 0)?;
+    Some((width, height))
+}
 
-            send_message(stream, &response).map_err(|e| {
-                error!("Failed to send error response: {}", e);
-                Box::new(e) as Box<dyn std::error::Error>
-            })?;
-        }
-    } else {
-        error!("Invalid SubscribePlayer message: {:?}", message);
-    }
+/// Starting cell and facing of the n-th player of a team (counting from 0) in a labyrinth of
+/// the given width and height. The facing can be forced for everyone with --start-facing.
+fn spawn_point(
+    team_index: usize,
+    start_facing: Option<MapDirection>,
+    (width, height): (usize, usize),
+) -> ((usize, usize), MapDirection) {
+    let (x, y) = match team_index % 3 {
+        0 => (3, 4), // First player at (3, 4)
+        1 => (4, 2), // Second player at (4, 2)
+        _ => (4, 4), // Third player at (4, 4)
+    };
+    // Spawn points are made for 5x5 labyrinths, keep them inside smaller ones
+    let position = (x.min(width - 1), y.min(height - 1));
+
+    let direction = match (start_facing, team_index % 3) {
+        (Some(direction), _) => direction,
+        (None, 0) => MapDirection::West, // First player facing West
+        (None, 1) => MapDirection::East, // Second player facing East
+        (None, _) => MapDirection::East, // Third player facing East
+    };
 
-    Ok(())
+    (position, direction)
 }
 
+/// Applies an action of a player and sends back the responses.
+/// The `seq` of the action, if any, is echoed in the radar view response.
 fn handle_action(
-    stream: &mut TcpStream,
+    stream: &mut impl Write,
     message: &Action,
+    seq: Option<u64>,
     state: Arc<Mutex<ServerState>>,
     peer_addr: std::net::SocketAddr,
     player_key: Option<String>,
@@ -588,7 +1707,7 @@ fn handle_action(
 
     // Find the player associated with this connection
     let player_key = player_key.unwrap_or_else(|| {
-        let state = state.lock().unwrap();
+        let state = lock_state(&state);
         state
             .players
             .iter()
@@ -610,148 +1729,86 @@ fn handle_action(
         Action::MoveTo(direction) => {
             debug!("Action MoveTo({:?}) for '{}'", direction, player_key);
 
-            // Values we'll collect and use after dropping the lock
-            let mut hit_wall = false;
-            let mut found_exit = false;
-            let mut give_hint = false;
-            let mut player_id = 0;
-            let mut encoded_view = String::new();
-            let new_position;
-            let new_direction;
-            let mut team_name = String::new();
-            let mut player_name = String::new();
-            let mut moves = 0;
-
-            {
-                let mut state_lock = state.lock().unwrap();
-
-                // Check if player exists
-                if !state_lock.players.contains_key(&player_key) {
-                    error!("Player {} not found in game state", player_key);
+            // Apply the move, then answer the player once the lock is dropped
+            let (outcome, hint_angle, secret_hint, combine_hint_with_radar, orientation) = {
+                let mut state_lock = lock_state(&state);
+                // A player with a challenge to solve cannot move, they get the challenge again
+                let pending = state_lock
+                    .players
+                    .get(&player_key)
+                    .and_then(|player| player.challenge);
+                if let Some(challenge) = pending {
+                    drop(state_lock);
+                    debug!(
+                        "Move of '{}' refused until the challenge is solved",
+                        player_key
+                    );
+                    send_message(stream, &challenge.response()).map_err(|e| {
+                        error!("Failed to send challenge: {}", e);
+                        Box::new(e) as Box<dyn std::error::Error>
+                    })?;
                     return Ok(());
                 }
-
-                // First, collect all the information we need in local variables
-                let current_position;
-                let current_direction;
-
-                // Scope for the immutable borrow of player to get current position/direction
-                {
-                    let player = state_lock.players.get(&player_key).unwrap();
-                    current_position = player.position;
-                    current_direction = player.direction;
-                }
-
-                // Calculate the potential new position
-                let move_result = process_move(
-                    current_position.0,
-                    current_position.1,
-                    &current_direction,
-                    direction,
-                );
-
-                let mut new_x = move_result.0;
-                let mut new_y = move_result.1;
-                let direction = move_result.2;
-
-                // Check for walls before allowing movement
-                let mut can_move = true;
-                if new_x != current_position.0 || new_y != current_position.1 {
-                    // Determine which wall to check based on movement direction
-                    if new_y < current_position.1 {
-                        // Moving North
-                        if state_lock.labyrinth.cells[current_position.1][current_position.0]
-                            .north_wall
-                        {
-                            can_move = false;
-                        }
-                    } else if new_y > current_position.1 {
-                        // Moving South
-                        if state_lock.labyrinth.cells[current_position.1][current_position.0]
-                            .south_wall
-                        {
-                            can_move = false;
-                        }
-                    } else if new_x > current_position.0 {
-                        // Moving East
-                        if state_lock.labyrinth.cells[current_position.1][current_position.0]
-                            .east_wall
-                        {
-                            can_move = false;
-                        }
-                    } else if new_x < current_position.0 {
-                        // Moving West
-                        if state_lock.labyrinth.cells[current_position.1][current_position.0]
-                            .west_wall
-                        {
-                            can_move = false;
-                        }
-                    }
-
-                    // If we can't move, keep the original position
-                    if !can_move {
-                        hit_wall = true;
-                        new_x = current_position.0;
-                        new_y = current_position.1;
-                        debug!("Player {} cannot move through wall", player_key);
-                    }
-                }
-
-                // Get exit position for checking later
-                let exit_position = state_lock.labyrinth.exit_position;
-
-                // Now update the player with a mutable borrow
-                // Scope for the mutable borrow of player to update it
-                {
-                    let player = state_lock.players.get_mut(&player_key).unwrap();
-
-                    // Only update position if movement is valid
-                    if can_move {
-                        player.position = (new_x, new_y);
+                let outcome = match state_lock.apply_move(&player_key, direction) {
+                    Ok(outcome) => outcome,
+                    Err(reason) => {
+                        error!("{}", reason);
+                        return Ok(());
                     }
-
-                    player.direction = direction;
-                    player.moves += 1;
-
-                    // Check if player found the exit
-                    found_exit = player.position.0 == exit_position.0
-                        && player.position.1 == exit_position.1;
-
-                    // Sometimes provide a hint
-                    give_hint = player.moves > 0 && player.moves % 8 == 0;
-                    player_id = player.id;
-                    team_name = player.team_name.clone();
-                    player_name = player.name.clone();
-                    moves = player.moves;
-
-                    // Remember the player's new position and direction for generating radar view
-                    new_position = player.position;
-                    new_direction = player.direction;
-                }
-
-                // Generate the radar view while still holding the lock
-                encoded_view =
-                    encode_radar_view(new_position, new_direction, &state_lock.labyrinth);
-
-                // Now we can safely print the labyrinth since the mutable borrow is dropped
-                if can_move {
-                    info!(
-                        "Player {} moved to ({}, {}) facing {:?}",
-                        player_key, new_x, new_y, direction
-                    );
-                } else {
-                    info!(
-                        "Player {} tried to move through a wall, stayed at position",
-                        player_key
+                };
+                print_labyrinth_for(&state_lock, &player_key);
+                let state = &mut *state_lock;
+                let give_secret = outcome.give_hint && outcome.moves % SECRET_HINT_EVERY == 0;
+                let hint_angle = match state.players.get_mut(&player_key) {
+                    Some(player) if outcome.give_hint && !give_secret => Some(compass_angle(
+                        &state.labyrinth,
+                        outcome.new_position,
+                        outcome.new_direction,
+                        state.config.compass_noise_model,
+                        &mut player.rng,
+                    )),
+                    _ => None,
+                };
+                let secret_hint = state
+                    .players
+                    .get(&player_key)
+                    .filter(|_| give_secret)
+                    .map(|player| player.secret);
+                if let Some(player) = state.players.get_mut(&player_key) {
+                    let compass = hint_angle
+                        .map(|angle| wire::SentHint::RelativeCompass(wire::CompassData { angle }));
+                    player.hints.extend(
+                        compass
+                            .into_iter()
+                            .chain(secret_hint.map(wire::SentHint::Secret)),
                     );
                 }
-                print_labyrinth(&state_lock);
-            }
+                (
+                    outcome,
+                    hint_angle,
+                    secret_hint,
+                    state_lock.config.combine_hint_with_radar,
+                    state_lock.config.radar_orientation,
+                )
+            };
+            let MoveOutcome {
+                hit_wall,
+                found_exit,
+                give_hint: _,
+                player_id,
+                encoded_view,
+                new_position,
+                new_direction,
+                team_name,
+                player_name,
+                moves,
+                challenge,
+            } = outcome;
 
             if hit_wall {
                 // Send wall message
-                let wall_response = server_request_models::CannotPassThroughWallResponse {
-                    CannotPassThroughWall: true,
+                let wall_response = wire::CannotPassThroughWallResponse {
+                    cannot_pass_through_wall: true,
                 };
 
                 send_message(stream, &wall_response).map_err(|e| {
@@ -760,35 +1817,48 @@ fn handle_action(
                 })?;
             }
 
-            if give_hint {
-                // Send a hint (compass)
-                let angle = rand::thread_rng().gen_range(0.0..360.0);
-
-                let hint_response = server_request_models::HintResponse {
-                    Hint: server_request_models::RelativeCompassResponse {
-                        RelativeCompass: server_request_models::CompassData { angle },
-                    },
-                };
-
+            // A secret hint always comes on its own, the combined radar view only carries compasses
+            if let Some(secret) = secret_hint {
                 debug!(
-                    "Write struct message: ClientSide(Loop(Hint(RelativeCompass {{ angle: {} }})))",
-                    angle
+                    "Write struct message: ClientSide(Loop(Hint(Secret({}))))",
+                    secret
                 );
-                send_message(stream, &hint_response).map_err(|e| {
-                    error!("Failed to send hint: {}", e);
+                send_message(stream, &wire::SecretHintResponse::from(secret)).map_err(|e| {
+                    error!("Failed to send secret hint: {}", e);
                     Box::new(e) as Box<dyn std::error::Error>
                 })?;
             }
 
-            if found_exit {
-                // Player found the exit
-                info!(
+            // Build a hint (compass) if one is due
+            let mut hint = None;
+            if let Some(angle) = hint_angle {
+                debug!(
+                    "Write struct message: ClientSide(Loop(Hint(RelativeCompass {{ angle: {} }})))",
+                    angle
+                );
+                hint = Some(wire::RelativeCompassResponse::from(angle));
+            }
+
+            // Unless it travels with the radar view, send the hint on its own
+            if !combine_hint_with_radar || challenge.is_some() {
+                if let Some(hint) = hint.take() {
+                    let hint_response = wire::HintResponse { hint };
+                    send_message(stream, &hint_response).map_err(|e| {
+                        error!("Failed to send hint: {}", e);
+                        Box::new(e) as Box<dyn std::error::Error>
+                    })?;
+                }
+            }
+
+            if found_exit {
+                // Player found the exit
+                info!(
                     "Team {}/{} found the exit in {} moves",
                     team_name, player_name, moves
                 );
 
                 // Send found exit message
-                let exit_response = server_request_models::FoundExitResponse { FoundExit: true };
+                let exit_response = wire::FoundExitResponse { found_exit: true };
 
                 send_message(stream, &exit_response).map_err(|e| {
                     error!("Failed to send exit notification: {}", e);
@@ -796,42 +1866,623 @@ fn handle_action(
                 })?;
             }
 
-            // Always send a radar view, regardless of movement outcome
+            // A challenge comes instead of the radar view, sent once it is solved
+            if let Some(challenge) = challenge {
+                info!(
+                    "Challenge SecretSumModulo({}) sent to {}",
+                    challenge.modulo, player_key
+                );
+                send_message(stream, &challenge.response()).map_err(|e| {
+                    error!("Failed to send challenge: {}", e);
+                    Box::new(e) as Box<dyn std::error::Error>
+                })?;
+                return Ok(());
+            }
+
+            // Otherwise always send a radar view, regardless of movement outcome
             debug!(
                 "Player {{ player_id: {} }} at {:?} towards {:?} with encoded view {}",
                 player_id, new_position, new_direction, encoded_view
             );
 
-            let radar_response = server_request_models::RadarViewResponse {
-                RadarView: encoded_view.clone(),
-            };
-
             debug!(
                 "Write struct message: ClientSide(Loop(RadarView(EncodedRadarView(\"{}\"))",
                 encoded_view
             );
+            let sent = match hint {
+                Some(hint) => {
+                    let radar_response = wire::RadarViewWithHintResponse {
+                        radar_view: encoded_view.clone(),
+                        hint,
+                        seq,
+                        facing: Some(new_direction),
+                        orientation,
+                    };
+                    send_message(stream, &radar_response)
+                }
+                None => {
+                    let radar_response = wire::RadarViewResponse {
+                        radar_view: encoded_view.clone(),
+                        seq,
+                        facing: Some(new_direction),
+                        orientation,
+                        pushed: None,
+                    };
+                    send_message(stream, &radar_response)
+                }
+            };
+            sent.map_err(|e| {
+                error!("Failed to send radar view: {}", e);
+                Box::new(e) as Box<dyn std::error::Error>
+            })?;
+        }
+        Action::Noop => {
+            debug!("Action Noop for '{}'", player_key);
+
+            // Keep-alive: answer with the current radar view, leaving the game untouched
+            let (encoded_view, facing, orientation) = {
+                let state = lock_state(&state);
+                let Some(player) = state.players.get(&player_key) else {
+                    error!("Player {} not found in game state", player_key);
+                    return Ok(());
+                };
+                let encoded_view = encode_player_radar_view(&state, player);
+                (
+                    encoded_view,
+                    player.direction,
+                    state.config.radar_orientation,
+                )
+            };
+
+            let radar_response = wire::RadarViewResponse {
+                radar_view: encoded_view,
+                seq,
+                facing: Some(facing),
+                orientation,
+                pushed: None,
+            };
             send_message(stream, &radar_response).map_err(|e| {
                 error!("Failed to send radar view: {}", e);
                 Box::new(e) as Box<dyn std::error::Error>
             })?;
         }
+        Action::GetOpenDirections => {
+            debug!("Action GetOpenDirections for '{}'", player_key);
+
+            let open_directions = {
+                let state = lock_state(&state);
+                let Some(player) = state.players.get(&player_key) else {
+                    error!("Player {} not found in game state", player_key);
+                    return Ok(());
+                };
+                open_directions(&state.labyrinth, player.position, player.direction)
+            };
+
+            let response = wire::OpenDirectionsResponse { open_directions };
+            send_message(stream, &response).map_err(|e| {
+                error!("Failed to send open directions: {}", e);
+                Box::new(e) as Box<dyn std::error::Error>
+            })?;
+        }
+        Action::GetHints => {
+            debug!("Action GetHints for '{}'", player_key);
+
+            let hints = {
+                let state = lock_state(&state);
+                let Some(player) = state.players.get(&player_key) else {
+                    error!("Player {} not found in game state", player_key);
+                    return Ok(());
+                };
+                player.hints.clone()
+            };
+
+            let response = wire::HintHistoryResponse { hints };
+            send_message(stream, &response).map_err(|e| {
+                error!("Failed to send hint history: {}", e);
+                Box::new(e) as Box<dyn std::error::Error>
+            })?;
+        }
         Action::SolveChallenge(answer) => {
-            // Handle the SolveChallenge action
             debug!("Action SolveChallenge({:?}) for '{}'", answer, player_key);
-            // Implement challenge solving logic here
+
+            // A right answer frees the player, who gets the radar view held back by the challenge
+            let (challenge, encoded_view, facing, orientation) = {
+                let mut state_lock = lock_state(&state);
+                let Some(player) = state_lock.players.get_mut(&player_key) else {
+                    error!("Player {} not found in game state", player_key);
+                    return Ok(());
+                };
+                let Some(challenge) = player.challenge else {
+                    warn!("Answer of '{}' ignored: no challenge to solve", player_key);
+                    return Ok(());
+                };
+                if answer.answer.trim().parse() == Ok(challenge.answer) {
+                    player.challenge = None;
+                }
+                let player = &state_lock.players[&player_key];
+                (
+                    player.challenge,
+                    encode_player_radar_view(&state_lock, player),
+                    player.direction,
+                    state_lock.config.radar_orientation,
+                )
+            };
+
+            if let Some(challenge) = challenge {
+                info!(
+                    "Wrong answer '{}' of {} to SecretSumModulo({})",
+                    answer.answer, player_key, challenge.modulo
+                );
+                send_message(stream, &challenge.response()).map_err(|e| {
+                    error!("Failed to send challenge: {}", e);
+                    Box::new(e) as Box<dyn std::error::Error>
+                })?;
+                return Ok(());
+            }
+
+            info!("Player {} solved the challenge", player_key);
+            let radar_response = wire::RadarViewResponse {
+                radar_view: encoded_view,
+                seq,
+                facing: Some(facing),
+                orientation,
+                pushed: None,
+            };
+            send_message(stream, &radar_response).map_err(|e| {
+                error!("Failed to send radar view: {}", e);
+                Box::new(e) as Box<dyn std::error::Error>
+            })?;
         }
     }
 
     Ok(())
 }
 
-// Process player movement
-fn process_move(
+/// Which moves relative to the facing would not hit a wall nor leave the labyrinth.
+fn open_directions(
+    labyrinth: &Labyrinth,
+    position: (usize, usize),
+    facing: MapDirection,
+) -> wire::OpenDirections {
+    let is_open = |direction| labyrinth.is_open(position, solver::absolute(facing, &direction));
+    wire::OpenDirections {
+        front: is_open(Direction::Front),
+        right: is_open(Direction::Right),
+        back: is_open(Direction::Back),
+        left: is_open(Direction::Left),
+    }
+}
+
+/// What happened to a player after a MoveTo action, used to build the responses.
+struct MoveOutcome {
+    hit_wall: bool,
+    found_exit: bool,
+    give_hint: bool,
+    player_id: usize,
+    encoded_view: String,
+    new_position: (usize, usize),
+    new_direction: MapDirection,
+    team_name: String,
+    player_name: String,
+    moves: usize,
+    // Challenge the player must solve before moving again, sent instead of the radar view
+    challenge: Option<PendingChallenge>,
+}
+
+impl ServerState {
+    /// Moves the player in the given direction unless a wall, a collapsed cell or
+    /// the edge of the labyrinth is in the way, then records the move and encodes
+    /// the player's new radar view.
+    /// Returns an error if the player is not in the game.
+    fn apply_move(
+        &mut self,
+        player_key: &str,
+        direction: &Direction,
+    ) -> Result<MoveOutcome, String> {
+        let player = self
+            .players
+            .get(player_key)
+            .ok_or_else(|| format!("Player {} not found in game state", player_key))?;
+        let current_position = player.position;
+        let current_direction = player.direction;
+
+        self.tick += 1;
+        collapse_due_cells(self);
+
+        // Calculate the potential new position, nobody can leave the labyrinth
+        let (dx, dy, direction) = process_move(&current_direction, direction);
+        let (mut new_x, mut new_y, mut can_move) = match (
+            current_position.0.checked_add_signed(dx),
+            current_position.1.checked_add_signed(dy),
+        ) {
+            (Some(x), Some(y)) if x < self.labyrinth.width && y < self.labyrinth.height => {
+                (x, y, true)
+            }
+            _ => (current_position.0, current_position.1, false),
+        };
+
+        // Check for walls before allowing movement
+        let cell = &self.labyrinth.cells[current_position.1][current_position.0];
+        if can_move {
+            // Determine which wall to check based on movement direction
+            if new_y < current_position.1 {
+                // Moving North
+                can_move = !cell.north_wall;
+            } else if new_y > current_position.1 {
+                // Moving South
+                can_move = !cell.south_wall;
+            } else if new_x > current_position.0 {
+                // Moving East
+                can_move = !cell.east_wall;
+            } else if new_x < current_position.0 {
+                // Moving West
+                can_move = !cell.west_wall;
+            }
+
+            // Nobody can enter a collapsed cell
+            if self.labyrinth.collapsed_cells.contains(&(new_x, new_y)) {
+                can_move = false;
+            }
+        }
+
+        // If we can't move, keep the original position
+        if !can_move {
+            new_x = current_position.0;
+            new_y = current_position.1;
+            debug!("Player {} cannot move through wall", player_key);
+        }
+
+        let exit_position = self.labyrinth.exit_position;
+        if can_move && (new_x, new_y) != current_position {
+            // The cell left behind starts collapsing, the one entered is safe again
+            if let Some(collapse_after) = self.config.collapse_after {
+                self.collapse_timers
+                    .insert(current_position, self.tick + collapse_after);
+            }
+            self.collapse_timers.remove(&(new_x, new_y));
+        }
+        let player = self.players.get_mut(player_key).unwrap();
+
+        // Only update position and direction if movement is valid
+        if can_move {
+            player.position = (new_x, new_y);
+            player.visited.insert(player.position);
+            player.direction = direction;
+        }
+        player.moves += 1;
+
+        // With --strict-exit, the player must also arrive facing the exit passage
+        let found_exit = player.position == exit_position
+            && (!self.config.strict_exit
+                || Some(player.direction) == self.labyrinth.exit_passage());
+        if found_exit {
+            player.solves += 1;
+        }
+        // With --challenge-every, every that many moves the player must solve a challenge
+        let challenge_due = self
+            .config
+            .challenge_every
+            .is_some_and(|every| !found_exit && player.moves.is_multiple_of(every));
+        if challenge_due {
+            let secrets: Vec<u64> = self
+                .teams
+                .get(&player.team_name)
+                .map(|team| team.secrets.values().copied().collect())
+                .unwrap_or_default();
+            player.challenge = Some(PendingChallenge::secret_sum_modulo(
+                &mut player.rng,
+                secrets.into_iter(),
+            ));
+        }
+        let challenge = player.challenge;
+        let (moves, solves) = (player.moves, player.solves);
+        let (player_id, new_position, new_direction) =
+            (player.id, player.position, player.direction);
+        let (team_name, player_name) = (player.team_name.clone(), player.name.clone());
+
+        // With --endless, the exit moves to the cell farthest from the player, who keeps solving
+        if found_exit && self.config.endless {
+            let new_exit = solver::farthest_cell(&self.labyrinth, new_position);
+            self.labyrinth.move_exit(new_exit);
+            info!(
+                "Player {} solved the labyrinth {} time(s), exit moved to {:?}",
+                player_key, solves, new_exit
+            );
+        }
+
+        let outcome = MoveOutcome {
+            hit_wall: !can_move,
+            found_exit,
+            // Sometimes provide a hint
+            give_hint: moves > 0 && moves % 8 == 0,
+            player_id,
+            encoded_view: encode_player_radar_view(self, &self.players[player_key]),
+            new_position,
+            new_direction,
+            team_name,
+            player_name,
+            moves,
+            challenge,
+        };
+
+        if found_exit {
+            self.stats.record_exit(player_key, moves);
+        } else {
+            self.stats.record_move(player_key, moves);
+        }
+
+        if can_move {
+            info!(
+                "Player {} moved to ({}, {}) facing {:?}",
+                player_key, new_x, new_y, direction
+            );
+        } else {
+            info!(
+                "Player {} tried to move through a wall, stayed at position",
+                player_key
+            );
+        }
+
+        Ok(outcome)
+    }
+}
+
+/// Collapses the cells whose timer is over at the current tick.
+fn collapse_due_cells(state: &mut ServerState) {
+    let tick = state.tick;
+    let due: Vec<(usize, usize)> = state
+        .collapse_timers
+        .iter()
+        .filter(|(_, &collapse_at)| collapse_at <= tick)
+        .map(|(&position, _)| position)
+        .collect();
+
+    for position in due {
+        state.collapse_timers.remove(&position);
+        state.labyrinth.collapsed_cells.insert(position);
+        info!("Cell ({}, {}) collapsed", position.0, position.1);
+    }
+}
+
+/// Flips a wall (admin message, --allow-cheats), answers the sender with the result and pushes
+/// a fresh radar view to the players who can see the wall.
+fn handle_toggle_wall(
+    stream: &mut impl Write,
+    message: &ToggleWall,
+    state: Arc<Mutex<ServerState>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    debug!("Read struct message: Admin(ToggleWall({:?}))", message);
+
+    let mut state = lock_state(&state);
+    let toggled = if state.config.allow_cheats {
+        toggle_wall(&mut state.labyrinth, message.x, message.y, message.side).ok_or_else(|| {
+            format!(
+                "No inner wall on the {:?} side of ({}, {})",
+                message.side, message.x, message.y
+            )
+        })
+    } else {
+        Err("The server was not started with --allow-cheats".to_string())
+    };
+    let (neighbor_x, neighbor_y) = match toggled {
+        Ok(neighbor) => neighbor,
+        Err(reason) => {
+            drop(state);
+            error!("ToggleWall refused: {}", reason);
+            send_message(stream, &wire::ToggleWallResponse::from(Err(reason)))?;
+            return Ok(());
+        }
+    };
+    info!(
+        "Toggled the wall between ({}, {}) and ({}, {})",
+        message.x, message.y, neighbor_x, neighbor_y
+    );
+    print_labyrinth(&state);
+
+    // Players who can see either side of the wall get a fresh radar view
+    let is_in_view = |position: (usize, usize), x: usize, y: usize| {
+        position.0.abs_diff(x) <= 1 && position.1.abs_diff(y) <= 1
+    };
+    let orientation = state.config.radar_orientation;
+    let radar_views: Vec<(String, SharedWriter, wire::RadarViewResponse)> = state
+        .players
+        .iter()
+        .filter(|(_, player)| {
+            is_in_view(player.position, message.x, message.y)
+                || is_in_view(player.position, neighbor_x, neighbor_y)
+        })
+        .filter_map(|(player_key, player)| {
+            let player_stream = state.player_streams.get(player_key)?.clone();
+            let radar_response = wire::RadarViewResponse {
+                radar_view: encode_player_radar_view(&state, player),
+                seq: None,
+                facing: Some(player.direction),
+                orientation,
+                pushed: Some(true),
+            };
+            Some((player_key.clone(), player_stream, radar_response))
+        })
+        .collect();
+    // A player who stops reading must not hold the whole arena up
+    drop(state);
+
+    send_message(stream, &wire::ToggleWallResponse::from(Ok(())))?;
+    for (player_key, mut player_stream, radar_response) in radar_views {
+        debug!(
+            "Write struct message: ClientSide(Loop(RadarView(EncodedRadarView(\"{}\"))",
+            radar_response.radar_view
+        );
+        if let Err(e) = send_message(&mut player_stream, &radar_response) {
+            error!("Failed to send radar view to {}: {}", player_key, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Relays a secret found by the player of the connection to the other players of their team.
+/// The relayed message names the subscribed player, whatever name the sender gave.
+fn handle_share_secret(
+    message: &ShareSecret,
+    state: Arc<Mutex<ServerState>>,
+    player_key: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    debug!("Read struct message: ShareSecret({:?})", message);
+
+    let Some(player_key) = player_key else {
+        error!("Shared secret refused: no player subscribed on this connection");
+        return Ok(());
+    };
+    let mut state = lock_state(&state);
+    let Some(player) = state.players.get(&player_key) else {
+        error!("Player {} not found in game state", player_key);
+        return Ok(());
+    };
+
+    let (player_name, team_name) = (player.name.clone(), player.team_name.clone());
+
+    // Kept for the team's SecretSumModulo challenges
+    if let Some(team) = state.teams.get_mut(&team_name) {
+        team.secrets.insert(player_name.clone(), message.secret);
+    }
+
+    let relayed = Message::ShareSecret(ShareSecret {
+        player: player_name,
+        secret: message.secret,
+    });
+    let teammates: Vec<(String, SharedWriter)> = state
+        .players
+        .iter()
+        .filter(|(key, teammate)| **key != player_key && teammate.team_name == team_name)
+        .filter_map(|(key, _)| Some((key.clone(), state.player_streams.get(key)?.clone())))
+        .collect();
+    // A teammate who stops reading must not hold the whole arena up
+    drop(state);
+
+    for (teammate, mut teammate_stream) in teammates {
+        if let Err(e) = send_message(&mut teammate_stream, &relayed) {
+            error!("Failed to relay secret to {}: {}", teammate, e);
+        }
+    }
+    info!("Relayed the secret of {} to their team", player_key);
+
+    Ok(())
+}
+
+/// Teleports the player of the connection (admin message, --allow-cheats) and sends their new radar view.
+fn handle_teleport(
+    stream: &mut impl Write,
+    message: &Teleport,
+    state: Arc<Mutex<ServerState>>,
+    player_key: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    debug!("Read struct message: Admin(Teleport({:?}))", message);
+
+    let Some(player_key) = player_key else {
+        error!("Teleport refused: no player subscribed on this connection");
+        return Ok(());
+    };
+    let mut state = lock_state(&state);
+    if let Err(reason) = state.teleport(&player_key, (message.x, message.y), message.facing) {
+        error!("Teleport refused: {}", reason);
+        return Ok(());
+    }
+    info!(
+        "Player {} teleported to ({}, {}) facing {:?}",
+        player_key, message.x, message.y, message.facing
+    );
+    print_labyrinth(&state);
+
+    let player = &state.players[&player_key];
+    let radar_response = wire::RadarViewResponse {
+        radar_view: encode_player_radar_view(&state, player),
+        seq: None,
+        facing: Some(player.direction),
+        orientation: state.config.radar_orientation,
+        pushed: None,
+    };
+    send_message(stream, &radar_response).map_err(|e| {
+        error!("Failed to send radar view: {}", e);
+        Box::new(e) as Box<dyn std::error::Error>
+    })?;
+    Ok(())
+}
+
+/// Flips the wall on one side of the cell at (x, y) and the matching wall of its neighbor,
+/// so both cells always agree. The outer border cannot be toggled.
+/// Returns the neighbor's coordinates, or None if there is no neighbor on that side.
+fn toggle_wall(
+    labyrinth: &mut Labyrinth,
     x: usize,
     y: usize,
+    side: MapDirection,
+) -> Option<(usize, usize)> {
+    if x >= labyrinth.width || y >= labyrinth.height {
+        return None;
+    }
+
+    let (neighbor_x, neighbor_y) = match side {
+        MapDirection::North => (x, y.checked_sub(1)?),
+        MapDirection::South => (x, y + 1),
+        MapDirection::East => (x + 1, y),
+        MapDirection::West => (x.checked_sub(1)?, y),
+    };
+    if neighbor_x >= labyrinth.width || neighbor_y >= labyrinth.height {
+        return None;
+    }
+
+    let cell = &labyrinth.cells[y][x];
+    let has_wall = match side {
+        MapDirection::North => cell.north_wall,
+        MapDirection::South => cell.south_wall,
+        MapDirection::East => cell.east_wall,
+        MapDirection::West => cell.west_wall,
+    };
+
+    let cell = &mut labyrinth.cells[y][x];
+    match side {
+        MapDirection::North => cell.north_wall = !has_wall,
+        MapDirection::South => cell.south_wall = !has_wall,
+        MapDirection::East => cell.east_wall = !has_wall,
+        MapDirection::West => cell.west_wall = !has_wall,
+    }
+    let neighbor = &mut labyrinth.cells[neighbor_y][neighbor_x];
+    match side {
+        MapDirection::North => neighbor.south_wall = !has_wall,
+        MapDirection::South => neighbor.north_wall = !has_wall,
+        MapDirection::East => neighbor.west_wall = !has_wall,
+        MapDirection::West => neighbor.east_wall = !has_wall,
+    }
+
+    Some((neighbor_x, neighbor_y))
+}
+
+/// Writes the JSON summary of the games to the given file.
+/// With several arenas, the summaries are keyed by the address of their arena.
+fn write_arenas_stats(
+    arenas: &[(String, Arc<Mutex<ServerState>>)],
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = match arenas {
+        [(_, state)] => Stats::snapshot(&lock_state(state)),
+        _ => serde_json::Value::Object(
+            arenas
+                .iter()
+                .map(|(address, state)| (address.clone(), Stats::snapshot(&lock_state(state))))
+                .collect(),
+        ),
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&snapshot)?)?;
+    info!("Statistics written to {}", path);
+    Ok(())
+}
+
+/// Step (dx, dy) and new facing of a player facing `current_direction` who moves toward
+/// `move_direction`. The caller checks that the step stays inside the labyrinth.
+fn process_move(
     current_direction: &MapDirection,
     move_direction: &Direction,
-) -> (usize, usize, MapDirection) {
+) -> (isize, isize, MapDirection) {
     let (dx, dy, new_direction) = match (current_direction, move_direction) {
         // Front movement preserves direction and moves in that direction
         (MapDirection::North, Direction::Front) => (0, -1, MapDirection::North),
@@ -858,41 +2509,67 @@ fn process_move(
         (MapDirection::West, Direction::Right) => (0, -1, MapDirection::North),
     };
 
-    // Calculate potential new position
-    let new_x = if dx < 0 && x > 0 {
-        x - 1
-    } else if dx > 0 && x < 4 {
-        // Assuming 5x5 grid (0-4 indices)
-        x + 1
-    } else {
-        x
-    };
-
-    let new_y = if dy < 0 && y > 0 {
-        y - 1
-    } else if dy > 0 && y < 4 {
-        // Assuming 5x5 grid (0-4 indices)
-        y + 1
-    } else {
-        y
-    };
-
-    (new_x, new_y, new_direction)
+    (dx, dy, new_direction)
 }
 
 // Print the labyrinth to console for debugging
 fn print_labyrinth(state: &ServerState) {
+    println!("\n=== Labyrinth Map ===");
+    print!("{}", render_labyrinth(state, &|_, _| true));
+    println!("Legend: ^ v > < = Players, X = Exit, H = Hint, . = Trail, # = Collapsed");
+    println!("Players:");
+    print!("{}", player_list(state));
+    println!();
+}
+
+/// Prints the labyrinth as the player who just moved knows it, with --reveal-radius,
+/// or the whole labyrinth otherwise.
+fn print_labyrinth_for(state: &ServerState, player_key: &str) {
+    let perspective = state
+        .config
+        .reveal_radius
+        .and_then(|radius| render_from_player_perspective(state, player_key, radius));
+    match perspective {
+        Some(map) => {
+            println!("\n=== Labyrinth Map seen by {} ===", player_key);
+            print!("{}", map);
+            println!(
+                "Legend: ^ v > < = Players, X = Exit, H = Hint, . = Trail, # = Collapsed, ? = Fog"
+            );
+            println!();
+        }
+        None => print_labyrinth(state),
+    }
+}
+
+/// Draws the labyrinth with only the cells within `radius` cells (horizontally, vertically or
+/// diagonally) of a player revealed, the others shown as `?`, to mimic what the player knows.
+/// None if there is no such player.
+fn render_from_player_perspective(
+    state: &ServerState,
+    player_key: &str,
+    radius: usize,
+) -> Option<String> {
+    let (player_x, player_y) = state.players.get(player_key)?.position;
+    Some(render_labyrinth(state, &|x, y| {
+        x.abs_diff(player_x) <= radius && y.abs_diff(player_y) <= radius
+    }))
+}
+
+/// Draws the labyrinth, its walls and what stands on each cell.
+/// Cells for which `revealed` is false are drawn as `?`, and only the walls they share
+/// with a revealed cell are shown.
+fn render_labyrinth(state: &ServerState, revealed: &dyn Fn(usize, usize) -> bool) -> String {
     let labyrinth = &state.labyrinth;
     let width = labyrinth.width;
     let height = labyrinth.height;
-
-    println!("\n=== Labyrinth Map ===");
+    let mut map = String::new();
 
     // Create a grid to show player positions
     let mut display_grid: Vec<Vec<String>> = vec![vec![" ".to_string(); width]; height];
 
     // Mark player positions
-    for (_, player) in &state.players {
+    for (_, player) in sorted_players(state) {
         let (x, y) = player.position;
         if x < width && y < height {
             // Use direction symbols for players: ^ v > <
@@ -921,72 +2598,112 @@ fn print_labyrinth(state: &ServerState) {
         }
     }
 
+    // Mark collapsed cells
+    for &(x, y) in &labyrinth.collapsed_cells {
+        if display_grid[y][x] == " " {
+            display_grid[y][x] = "#".to_string();
+        }
+    }
+
+    // Mark the trails of the players on the cells left empty
+    for (_, player) in sorted_players(state) {
+        for &(x, y) in &player.visited {
+            if x < width && y < height && display_grid[y][x] == " " {
+                display_grid[y][x] = ".".to_string();
+            }
+        }
+    }
+
+    // Hide the cells out of sight
+    for (y, row) in display_grid.iter_mut().enumerate() {
+        for (x, content) in row.iter_mut().enumerate() {
+            if !revealed(x, y) {
+                *content = "?".to_string();
+            }
+        }
+    }
+    let wall = |shown: bool, segment: &'static str| if shown { segment } else { "   " };
+
     // Print top border
-    print!("  ");
+    map.push_str("  ");
     for x in 0..width {
-        print!("{}   ", x);
+        map.push_str(&format!("{}   ", x));
     }
-    println!();
+    map.push('\n');
 
     // Print northern walls for the first row
-    print!("  ");
+    map.push_str("  ");
     for x in 0..width {
-        print!("+");
-        if labyrinth.cells[0][x].north_wall {
-            print!("---");
-        } else {
-            print!("   ");
-        }
+        map.push('+');
+        map.push_str(wall(
+            revealed(x, 0) && labyrinth.cells[0][x].north_wall,
+            "---",
+        ));
     }
-    println!("+");
+    map.push_str("+\n");
 
     // Print each row
     for y in 0..height {
         // Print row number
-        print!("{} ", y);
+        map.push_str(&format!("{} ", y));
 
         // Print cells and vertical walls
         for x in 0..width {
             // Print west wall
-            if labyrinth.cells[y][x].west_wall {
-                print!("|");
+            let shown = revealed(x, y) || (x > 0 && revealed(x - 1, y));
+            if shown && labyrinth.cells[y][x].west_wall {
+                map.push('|');
             } else {
-                print!(" ");
+                map.push(' ');
             }
 
             // Print cell content (player or space)
-            print!(" {} ", display_grid[y][x]);
+            map.push_str(&format!(" {} ", display_grid[y][x]));
         }
 
         // Print east wall of the last cell in the row
-        if labyrinth.cells[y][width - 1].east_wall {
-            println!("|");
+        if revealed(width - 1, y) && labyrinth.cells[y][width - 1].east_wall {
+            map.push_str("|\n");
         } else {
-            println!(" ");
+            map.push_str(" \n");
         }
 
         // Print southern walls for this row
-        print!("  ");
+        map.push_str("  ");
         for x in 0..width {
-            print!("+");
-            if labyrinth.cells[y][x].south_wall {
-                print!("---");
-            } else {
-                print!("   ");
-            }
+            map.push('+');
+            let shown = revealed(x, y) || (y + 1 < height && revealed(x, y + 1));
+            map.push_str(wall(shown && labyrinth.cells[y][x].south_wall, "---"));
         }
-        println!("+");
+        map.push_str("+\n");
     }
 
-    println!("Legend: ^ v > < = Players, X = Exit, H = Hint");
-    println!("Players:");
-    for (player_key, player) in &state.players {
-        println!(
-            "  {} at ({}, {}) facing {:?}, moves: {}",
-            player_key, player.position.0, player.position.1, player.direction, player.moves
-        );
-    }
-    println!();
+    map
+}
+
+/// Players of the game sorted by key, so that printed output is the same from one run to the next.
+fn sorted_players(state: &ServerState) -> Vec<(&String, &Player)> {
+    let mut players: Vec<(&String, &Player)> = state.players.iter().collect();
+    players.sort_unstable_by_key(|(key, _)| *key);
+    players
+}
+
+/// The "Players:" list printed under the labyrinth, one line per player in key order.
+fn player_list(state: &ServerState) -> String {
+    sorted_players(state)
+        .into_iter()
+        .map(|(player_key, player)| {
+            format!(
+                "  {} at ({}, {}) facing {:?}, moves: {}, solves: {}\n",
+                player_key,
+                player.position.0,
+                player.position.1,
+                player.direction,
+                player.moves,
+                player.solves
+            )
+        })
+        .collect()
 }
 
 // Generate a random token (16 hex characters)
@@ -1011,6 +2728,15 @@ fn encode_cell(labyrinth: &Labyrinth, x: isize, y: isize) -> u8 {
         return 0xF;
     }
 
+    if labyrinth
+        .collapsed_cells
+        .contains(&(x as usize, y as usize))
+    {
+        // Collapsed floor: the otherwise unused item value 0b11, no entity
+        debug!("Cell at ({}, {}) collapsed", x, y);
+        return 0b1100;
+    }
+
     let cell = &labyrinth.cells[y as usize][x as usize];
 
     let item_bits = if cell.has_exit {
@@ -1034,111 +2760,400 @@ fn encode_cell(labyrinth: &Labyrinth, x: isize, y: isize) -> u8 {
     result
 }
 
-/// Encode a radar view from the labyrinth for the player's 3×3 view.
-/// The encoding is as follows:
-/// - 12 horizontal passages (2 bits each) → 24 bits (3 bytes little‑endian)
-/// - 12 vertical passages (2 bits each)   → 24 bits (3 bytes little‑endian)
-/// - 9 cell values (4 bits each)            → 36 bits, then left‑shifted by 4 (padding) → 40 bits (5 bytes little‑endian)
-///
-/// The passages and cells are taken in natural order (top‑left first, row‑major).
+/// The 9 cell values of the 3x3 view around the center, row by row from the top-left.
+/// With line of sight (--los), cells hidden behind walls are encoded as undefined (0xF).
+/// Given the cells the player stood on (--mark-visited), those around them are marked as visited.
+/// Given the entity bits of the cells where other players stand, they are set in the low 2 bits,
+/// next to the item bits.
+fn radar_cell_values(
+    (x_center, y_center): (usize, usize),
+    facing: MapDirection,
+    labyrinth: &Labyrinth,
+    line_of_sight: bool,
+    visited: Option<&HashSet<(usize, usize)>>,
+    entities: Option<&HashMap<(usize, usize), u8>>,
+) -> Vec<u8> {
+    let mut cell_values = Vec::new();
+    for y_offset in -1..=1 {
+        for x_offset in -1..=1 {
+            let (x, y) = radar_cell_position((x_center, y_center), facing, (x_offset, y_offset));
+            let mut cell_value =
+                if line_of_sight && !in_line_of_sight(labyrinth, (x_center, y_center), (x, y)) {
+                    0xF
+                } else {
+                    encode_cell(labyrinth, x, y)
+                };
+            // Undefined cells (0xF) show nothing, and nobody stands on a collapsed one
+            if cell_value != 0xF && cell_value != 0b1100 {
+                if let Some(&entity_bits) =
+                    entities.and_then(|entities| entities.get(&(x as usize, y as usize)))
+                {
+                    cell_value |= entity_bits;
+                }
+            }
+            // An empty cell the player already stood on (--mark-visited) gets 0b1101, a code
+            // reserved for the whole cell like 0xF (undefined) and 0b1100 (collapsed floor):
+            // its low bit is not an ally, decoders must read it before the item and entity bits.
+            // The center is where the player stands, so it is never marked.
+            if cell_value == 0b0000
+                && (x_offset, y_offset) != (0, 0)
+                && visited.is_some_and(|visited| visited.contains(&(x as usize, y as usize)))
+            {
+                cell_value = 0b1101;
+            }
+            debug!(
+                "Cell at relative position ({}, {}) [absolute: ({}, {})] encoded as: {:#06b}",
+                x_offset, y_offset, x, y, cell_value
+            );
+            cell_values.push(cell_value);
+        }
+    }
+    cell_values
+}
+
+/// Absolute position of the cell drawn `column` cells right and `row` cells down of the center of
+/// a radar view (both from -1 to 1): radar views are in the player's frame, the front at the top.
+fn radar_cell_position(
+    (x, y): (usize, usize),
+    facing: MapDirection,
+    (column, row): (isize, isize),
+) -> (isize, isize) {
+    let (dx, dy) = match facing {
+        MapDirection::North => (column, row),
+        MapDirection::East => (-row, column),
+        MapDirection::South => (-column, -row),
+        MapDirection::West => (row, -column),
+    };
+    (x as isize + dx, y as isize + dy)
+}
+
+/// Whether a cell of the 3x3 view can be seen from its center: it must be reachable
+/// without passing through a wall nor leaving the view.
+fn in_line_of_sight(labyrinth: &Labyrinth, center: (usize, usize), target: (isize, isize)) -> bool {
+    let in_view = |(x, y): (usize, usize)| {
+        (x as isize - center.0 as isize).abs() <= 1 && (y as isize - center.1 as isize).abs() <= 1
+    };
+    let mut reached = vec![center];
+    let mut queue = vec![center];
+    while let Some((x, y)) = queue.pop() {
+        if (x as isize, y as isize) == target {
+            return true;
+        }
+        for direction in [
+            MapDirection::North,
+            MapDirection::East,
+            MapDirection::South,
+            MapDirection::West,
+        ] {
+            if !labyrinth.is_open((x, y), direction) {
+                continue;
+            }
+            let next = match direction {
+                MapDirection::North => (x, y - 1),
+                MapDirection::East => (x + 1, y),
+                MapDirection::South => (x, y + 1),
+                MapDirection::West => (x - 1, y),
+            };
+            if in_view(next) && !reached.contains(&next) {
+                reached.push(next);
+                queue.push(next);
+            }
+        }
+    }
+    false
+}
+
+/// Encode a radar view from the labyrinth for the player's 3×3 view.
+/// The encoding is as follows:
+/// - 12 horizontal passages (2 bits each) → 24 bits (3 bytes little‑endian)
+/// - 12 vertical passages (2 bits each)   → 24 bits (3 bytes little‑endian)
+/// - 9 cell values (4 bits each)            → 36 bits, then left‑shifted by 4 (padding) → 40 bits (5 bytes little‑endian)
+///
+/// The passages and cells are taken in natural order (top‑left first, row‑major).
+/// Players get theirs from encode_player_radar_view, which adds the server options; this plain
+/// view is what the tests check the layout with.
+#[cfg(test)]
 pub(crate) fn encode_radar_view(
     player_position: (usize, usize),
     player_direction: MapDirection,
     labyrinth: &Labyrinth,
 ) -> String {
-    info!(
-        "Encoding radar view for player at position ({}, {}) facing {:?}",
-        player_position.0, player_position.1, player_direction
+    let encoded = encoder::encode(&radar_view_bytes(
+        player_position,
+        player_direction,
+        labyrinth,
+        false,
+        None,
+        None,
+    ));
+    info!("Base64 encoded result: {}", encoded);
+    encoded
+}
+
+/// Encodes the radar view sent to a player, adding noise if the server runs with --radar-noise,
+/// marking the cells they already stood on with --mark-visited,
+/// adding the bearing of a nearby exit with --sense-radius, keeping north at the top with
+/// --radar-orientation absolute
+/// and logging it as ASCII if the server runs with --log-radar.
+fn encode_player_radar_view(state: &ServerState, player: &Player) -> String {
+    let entities = player_entities(state, player);
+    // An absolute radar view is the one of a player facing north
+    let radar_facing = match state.config.radar_orientation {
+        Some(RadarOrientation::Absolute) => MapDirection::North,
+        Some(RadarOrientation::Relative) | None => player.direction,
+    };
+    let mut data = radar_view_bytes(
+        player.position,
+        radar_facing,
+        &state.labyrinth,
+        state.config.line_of_sight,
+        state.config.mark_visited.then_some(&player.visited),
+        Some(&entities),
+    );
+    if let Some(probability) = state.config.radar_noise {
+        add_radar_noise(&mut data, probability, &mut rand::thread_rng());
+    }
+    let mut payload = data.to_vec();
+    if let Some(radius) = state.config.sense_radius {
+        payload.extend(exit_sense(
+            &state.labyrinth,
+            player.position,
+            radar_facing,
+            radius,
+        ));
+    }
+    let encoded = encoder::encode(&payload);
+    if state.config.radar_noise.is_some() {
+        info!("Base64 encoded result (with noise): {}", encoded);
+    } else {
+        info!("Base64 encoded result: {}", encoded);
+    }
+
+    if state.config.log_radar {
+        log_radar_view(&encoded, &data);
+    }
+    encoded
+}
+
+/// Entity bits of the cells where the other players stand, as seen by a player:
+/// 0b01 for a teammate (ally), 0b10 for a player of another team (enemy).
+fn player_entities(state: &ServerState, viewer: &Player) -> HashMap<(usize, usize), u8> {
+    let mut entities = HashMap::new();
+    for player in state.players.values() {
+        if player.id == viewer.id {
+            continue;
+        }
+        let entity_bits = if player.team_name == viewer.team_name {
+            0b01
+        } else {
+            0b10
+        };
+        // An ally sharing the cell with an enemy is shown, the team comes first
+        let bits = entities.entry(player.position).or_insert(entity_bits);
+        *bits = (*bits).min(entity_bits);
+    }
+    entities
+}
+
+/// Writes the radar view sent to a player as ASCII to the "radar" log (--log-radar),
+/// drawn the same way the client draws what it decoded.
+fn log_radar_view(encoded: &str, data: &[u8; 11]) {
+    let ascii = render_radar_bytes(data).unwrap_or_default();
+    if let Err(e) = logger::log_message("radar", &format!("{}\n{}", encoded, ascii)) {
+        error!("Failed to log radar view: {}", e);
+    }
+}
+
+/// With the given probability, flips one random bit of the passages (the first 6 bytes)
+/// of a radar view. A flipped passage can decode to any value, including the `11` error code.
+/// Returns true if a bit was flipped.
+fn add_radar_noise(data: &mut [u8; 11], probability: f64, rng: &mut impl Rng) -> bool {
+    if !rng.gen_bool(probability.clamp(0.0, 1.0)) {
+        return false;
+    }
+    let bit = rng.gen_range(0..48);
+    data[bit / 8] ^= 1 << (bit % 8);
+    debug!("Radar noise flipped passage bit {}", bit);
+    true
+}
+
+/// Angle of a compass hint, in degrees clockwise from the player's facing: the bearing of the exit.
+/// With a noise model, it is moved by up to the model's deviation for the BFS distance to the exit.
+fn compass_angle(
+    labyrinth: &Labyrinth,
+    position: (usize, usize),
+    facing: MapDirection,
+    model: Option<CompassNoiseModel>,
+    rng: &mut impl Rng,
+) -> f64 {
+    let bearing = compass_angle_to_exit(position, facing, labyrinth.exit_position);
+    let (Some(model), Some(distance)) = (model, solver::distance_to_exit(labyrinth, position))
+    else {
+        return bearing;
+    };
+
+    let max_deviation = model.max_deviation(distance);
+    let deviation = if max_deviation > 0.0 {
+        rng.gen_range(-max_deviation..=max_deviation)
+    } else {
+        0.0
+    };
+    let angle = (bearing + deviation).rem_euclid(360.0);
+    debug!(
+        "Compass hint {:.1} degrees, {} moves from the exit (up to {:.1} degrees of noise)",
+        angle, distance, max_deviation
     );
+    angle
+}
 
-    // Pour l'instant, on initialise les passages à "undefined" (0xF).
-    let mut horizontal_passages: u32 = 0x55_55_55; // (01 répété 12 fois)
-    let mut vertical_passages: u32 = 0x55_55_55; // (01 répété 12 fois)
+/// Optional 12th byte of a radar view (--sense-radius): the bearing of the exit in eighths of a
+/// turn clockwise from the facing (0 front, 2 right, 4 back, 6 left), when the exit is out of
+/// the radar view but at most `radius` cells away, diagonals included.
+fn exit_sense(
+    labyrinth: &Labyrinth,
+    (x, y): (usize, usize),
+    facing: MapDirection,
+    radius: usize,
+) -> Option<u8> {
+    let (exit_x, exit_y) = labyrinth.exit_position;
+    let distance = x.abs_diff(exit_x).max(y.abs_diff(exit_y));
+    if distance <= RADAR_RADIUS || distance > radius {
+        return None;
+    }
+    let eighths =
+        (compass_angle_to_exit((x, y), facing, labyrinth.exit_position) / 45.0).round() as u8 % 8;
+    debug!(
+        "Exit sensed {} cells away, {} eighths of a turn clockwise",
+        distance, eighths
+    );
+    Some(eighths)
+}
 
-    debug!("Initial horizontal passages: {:#034b}", horizontal_passages);
-    debug!("Initial vertical passages: {:#034b}", vertical_passages);
+/// Straight-line bearing of the exit, in degrees clockwise from the direction the player faces:
+/// 0 straight ahead, 90 to the right.
+fn compass_angle_to_exit(
+    (x, y): (usize, usize),
+    direction: MapDirection,
+    (exit_x, exit_y): (usize, usize),
+) -> f64 {
+    let dx = exit_x as f64 - x as f64;
+    let dy = exit_y as f64 - y as f64;
+    // Rows grow southward, so north is -dy
+    let from_north = dx.atan2(-dy).to_degrees();
+    let facing_angle = match direction {
+        MapDirection::North => 0.0,
+        MapDirection::East => 90.0,
+        MapDirection::South => 180.0,
+        MapDirection::West => 270.0,
+    };
+    (from_north - facing_angle).rem_euclid(360.0)
+}
 
-    let x_center = player_position.0;
-    let y_center = player_position.1;
+/// Code of a passage in a radar view, on 2 bits: 0 undefined, 1 open, 2 wall (3 is an error).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Passage {
+    Undefined = 0b00,
+    Open = 0b01,
+    Wall = 0b10,
+}
 
-    match player_direction {
-        MapDirection::North => {
-            // cellule centrale.
-            let center_cell = &labyrinth.cells[y_center][x_center];
+/// 12 passages of a radar view, all undefined (`0x55_55_55` would be all open).
+const UNDEFINED_PASSAGES: u32 = Passage::Undefined as u32 * 0x55_55_55;
 
-            if center_cell.north_wall {
-                horizontal_passages &= !(0b11 << 6);
-            }
-            if center_cell.east_wall {
-                vertical_passages &= !(0b11 << 6);
-            }
-            if center_cell.south_wall {
-                horizontal_passages &= !(0b11 << 8);
-            }
-            if center_cell.west_wall {
-                vertical_passages &= !(0b11 << 4);
-            }
+/// Code of the passage between two neighbouring cells, given by their absolute positions:
+/// a wall if either cell has one on that side, or if the passage is the edge of the labyrinth.
+/// A passage outside the labyrinth, or out of sight, is undefined.
+fn passage_between(
+    labyrinth: &Labyrinth,
+    from: (isize, isize),
+    to: (isize, isize),
+    in_sight: bool,
+) -> Passage {
+    let cell = |(x, y): (isize, isize)| {
+        let inside =
+            x >= 0 && y >= 0 && (x as usize) < labyrinth.width && (y as usize) < labyrinth.height;
+        inside.then(|| &labyrinth.cells[y as usize][x as usize])
+    };
+    let (from_cell, to_cell) = (cell(from), cell(to));
+    if !in_sight || (from_cell.is_none() && to_cell.is_none()) {
+        return Passage::Undefined;
+    }
 
-            debug!(
-                "Processing center cell at ({}, {}) with walls N:{} E:{} S:{} W:{}",
-                x_center,
-                y_center,
-                center_cell.north_wall,
-                center_cell.east_wall,
-                center_cell.south_wall,
-                center_cell.west_wall
-            );
+    // Wall of a cell on the side of a step (dx, dy) from it
+    let wall_towards = |cell: &Cell, step: (isize, isize)| match step {
+        (0, -1) => cell.north_wall,
+        (1, 0) => cell.east_wall,
+        (0, 1) => cell.south_wall,
+        _ => cell.west_wall,
+    };
+    let step = (to.0 - from.0, to.1 - from.1);
+    let wall = match (from_cell, to_cell) {
+        (Some(from_cell), Some(to_cell)) => {
+            wall_towards(from_cell, step) || wall_towards(to_cell, (-step.0, -step.1))
         }
-        MapDirection::South => {
-            let center_cell = &labyrinth.cells[y_center][x_center];
+        _ => true,
+    };
+    if wall {
+        Passage::Wall
+    } else {
+        Passage::Open
+    }
+}
 
-            if center_cell.south_wall {
-                horizontal_passages &= !(0b11 << 6);
-            }
-            if center_cell.west_wall {
-                vertical_passages &= !(0b11 << 6);
-            }
-            if center_cell.north_wall {
-                horizontal_passages &= !(0b11 << 8);
-            }
-            if center_cell.east_wall {
-                vertical_passages &= !(0b11 << 4);
-            }
-            info!("South orientation logic applied");
-        }
-        MapDirection::East => {
-            let center_cell = &labyrinth.cells[y_center][x_center];
+/// Writes the code of passage `index` (0 to 11) into 24 bits of passages: the first passage
+/// takes the 2 highest bits, as the client reads them.
+fn set_passage(passages: &mut u32, index: usize, passage: Passage) {
+    let shift = (11 - index) * 2;
+    *passages = (*passages & !(0b11 << shift)) | ((passage as u32) << shift);
+}
 
-            if center_cell.east_wall {
-                horizontal_passages &= !(0b11 << 6);
-            }
-            if center_cell.south_wall {
-                vertical_passages &= !(0b11 << 6);
-            }
-            if center_cell.west_wall {
-                horizontal_passages &= !(0b11 << 8);
-            }
-            if center_cell.north_wall {
-                vertical_passages &= !(0b11 << 4);
-            }
-            info!("East orientation logic applied");
-        }
-        MapDirection::West => {
-            let center_cell = &labyrinth.cells[y_center][x_center];
+/// Builds the 11 bytes of a radar view, see encode_radar_view for the layout.
+fn radar_view_bytes(
+    player_position: (usize, usize),
+    player_direction: MapDirection,
+    labyrinth: &Labyrinth,
+    line_of_sight: bool,
+    visited: Option<&HashSet<(usize, usize)>>,
+    entities: Option<&HashMap<(usize, usize), u8>>,
+) -> [u8; 11] {
+    info!(
+        "Encoding radar view for player at position ({}, {}) facing {:?}",
+        player_position.0, player_position.1, player_direction
+    );
 
-            if center_cell.west_wall {
-                horizontal_passages &= !(0b11 << 6);
-            }
-            if center_cell.north_wall {
-                vertical_passages &= !(0b11 << 6);
-            }
-            if center_cell.east_wall {
-                horizontal_passages &= !(0b11 << 8);
-            }
-            if center_cell.south_wall {
-                vertical_passages &= !(0b11 << 4);
-            }
-            info!("West orientation logic applied");
+    // Passages between two cells of the view, or between a cell and the outside of the view,
+    // in the player's frame like the cells
+    let visible = |position: (isize, isize)| {
+        !line_of_sight || in_line_of_sight(labyrinth, player_position, position)
+    };
+    let passage = |from: (isize, isize), to: (isize, isize)| {
+        let from = radar_cell_position(player_position, player_direction, from);
+        let to = radar_cell_position(player_position, player_direction, to);
+        passage_between(labyrinth, from, to, visible(from) || visible(to))
+    };
+
+    // Horizontal passages: 4 lines of 3, the first above the front row of the view
+    let mut horizontal_passages = UNDEFINED_PASSAGES;
+    for line in 0..4 {
+        for column in 0..3 {
+            let (column_offset, row_below) = (column as isize - 1, line as isize - 1);
+            set_passage(
+                &mut horizontal_passages,
+                line * 3 + column,
+                passage((column_offset, row_below - 1), (column_offset, row_below)),
+            );
+        }
+    }
+    // Vertical passages: 3 rows of 4, the first left of the left column of the view
+    let mut vertical_passages = UNDEFINED_PASSAGES;
+    for row in 0..3 {
+        for line in 0..4 {
+            let (column_right, row_offset) = (line as isize - 1, row as isize - 1);
+            set_passage(
+                &mut vertical_passages,
+                row * 4 + line,
+                passage((column_right - 1, row_offset), (column_right, row_offset)),
+            );
         }
     }
 
@@ -1153,24 +3168,20 @@ pub(crate) fn encode_radar_view(
 
     // Encodage des cellules du radar : pour chaque cellule de la grille 3×3,
     // on utilise 4 bits par cellule.
-    let mut cell_values = Vec::new();
-    for y_offset in -1..=1 {
-        for x_offset in -1..=1 {
-            let x = x_center as isize + x_offset;
-            let y = y_center as isize + y_offset;
-            let cell_value = encode_cell(labyrinth, x, y);
-            debug!(
-                "Cell at relative position ({}, {}) [absolute: ({}, {})] encoded as: {:#06b}",
-                x_offset, y_offset, x, y, cell_value
-            );
-            cell_values.push(cell_value);
-        }
-    }
+    let cell_values = radar_cell_values(
+        player_position,
+        player_direction,
+        labyrinth,
+        line_of_sight,
+        visited,
+        entities,
+    );
 
-    // On pack les 9 valeurs de 4 bits chacune dans un entier 64 bits.
+    // On pack les 9 valeurs de 4 bits chacune dans un entier 64 bits,
+    // la première cellule dans les bits de poids fort, comme le client les lit.
     let mut packed_cells: u64 = 0;
     for (i, &value) in cell_values.iter().enumerate() {
-        packed_cells |= (value as u64) << (i * 4);
+        packed_cells = (packed_cells << 4) | value as u64;
         debug!(
             "After adding cell {}: packed_cells = {:#066b}",
             i, packed_cells
@@ -1195,11 +3206,12 @@ pub(crate) fn encode_radar_view(
     data[4] = ((vertical_passages >> 8) & 0xFF) as u8;
     data[5] = ((vertical_passages >> 16) & 0xFF) as u8;
 
-    data[6] = (packed_cells & 0xFF) as u8;
-    data[7] = ((packed_cells >> 8) & 0xFF) as u8;
+    // Big endian, unlike the passages
+    data[6] = ((packed_cells >> 32) & 0xFF) as u8;
+    data[7] = ((packed_cells >> 24) & 0xFF) as u8;
     data[8] = ((packed_cells >> 16) & 0xFF) as u8;
-    data[9] = ((packed_cells >> 24) & 0xFF) as u8;
-    data[10] = ((packed_cells >> 32) & 0xFF) as u8;
+    data[9] = ((packed_cells >> 8) & 0xFF) as u8;
+    data[10] = (packed_cells & 0xFF) as u8;
 
     debug!("Final encoded data bytes:");
     debug!(
@@ -1215,109 +3227,2219 @@ pub(crate) fn encode_radar_view(
         data[6], data[7], data[8], data[9], data[10]
     );
 
-    let encoded = encoder::encode(&data);
-    info!("Base64 encoded result: {}", encoded);
-    encoded
+    data
 }
 
 // Include tests
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::VecDeque;
+    use std::time::Duration;
+    use SQP_common::radar::passage_codes;
+
+    /// Indexes of the passages around the center cell, among the 12 horizontal passages
+    /// (front, back) or the 12 vertical passages (left, right) of a radar view.
+    const FRONT_PASSAGE: usize = 4;
+    const BACK_PASSAGE: usize = 7;
+    const LEFT_PASSAGE: usize = 5;
+    const RIGHT_PASSAGE: usize = 6;
 
     #[test]
     fn test_encode_radar_view() {
-        let cells = vec![
-            vec![
-                // Row 0 (top): all cells with all walls true.
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
+        // A north-south corridor one cell wide, longer than the view: the passages above and
+        // below the view are open, the columns on each side are outside the labyrinth (undefined)
+        let labyrinth = labyrinth_from_masks(
+            &[&["0101"], &["0101"], &["0101"], &["0101"], &["0101"]],
+            (0, 4),
+        );
+        let player_position = (0, 2);
+        let encoded = encode_radar_view(player_position, MapDirection::North, &labyrinth);
+        assert_eq!(encoded, "beeqkcGO8p8p8pa");
+    }
+
+    /// Builds a labyrinth from one "NESW" wall mask per cell ('1' = wall, '0' = open).
+    fn labyrinth_from_masks(rows: &[&[&str]], exit_position: (usize, usize)) -> Labyrinth {
+        let cells: Vec<Vec<Cell>> = rows
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(x, mask)| {
+                        let walls: Vec<bool> = mask.chars().map(|c| c == '1').collect();
+                        Cell {
+                            north_wall: walls[0],
+                            east_wall: walls[1],
+                            south_wall: walls[2],
+                            west_wall: walls[3],
+                            has_hint: false,
+                            has_exit: (x, y) == exit_position,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Labyrinth {
+            width: cells[0].len(),
+            height: cells.len(),
+            cells,
+            exit_position,
+            collapsed_cells: HashSet::new(),
+        }
+    }
+
+    /// Asserts that every cell can be reached from (0, 0) through open walls,
+    /// panicking with the first unreachable cell (row by row) otherwise.
+    fn assert_connected(labyrinth: &Labyrinth) {
+        let mut reached = HashSet::from([(0, 0)]);
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::from([(0, 0)]);
+        while let Some((x, y)) = queue.pop_front() {
+            for (direction, next) in [
+                (MapDirection::North, (x, y.wrapping_sub(1))),
+                (MapDirection::East, (x + 1, y)),
+                (MapDirection::South, (x, y + 1)),
+                (MapDirection::West, (x.wrapping_sub(1), y)),
+            ] {
+                if labyrinth.is_open((x, y), direction) && reached.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        for y in 0..labyrinth.height {
+            for x in 0..labyrinth.width {
+                assert!(
+                    reached.contains(&(x, y)),
+                    "cell ({}, {}) cannot be reached from (0, 0)",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    /// Asserts the exact encoded radar string for each facing.
+    /// These golden vectors pin the wire format: if the encoding changes on purpose,
+    /// regenerate them and review the diff, never loosen the comparison.
+    fn assert_golden(
+        labyrinth: &Labyrinth,
+        position: (usize, usize),
+        expected: &[(MapDirection, &str)],
+    ) {
+        for (direction, golden) in expected {
+            let encoded = encode_radar_view(position, *direction, labyrinth);
+            assert_eq!(
+                encoded, *golden,
+                "radar at {:?} facing {:?} changed",
+                position, direction
+            );
+        }
+    }
+
+    #[test]
+    fn test_golden_radar_open_room() {
+        // Open 3x3 room, outer walls only, player in the middle.
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1100"],
+                &["0001", "0000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (2, 2),
+        );
+        assert_golden(
+            &labyrinth,
+            (1, 1),
+            &[
+                (MapDirection::North, "ALwPLPAwaaaaaia"),
+                (MapDirection::East, "ALwPLPAwaiaaaaa"),
+                (MapDirection::South, "ALwPLPAwGaaaaaa"),
+                (MapDirection::West, "ALwPLPAwaaaaGaa"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_golden_radar_north_south_corridor() {
+        // Only the middle column is connected, from top to bottom.
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1111", "1101", "1111"],
+                &["1111", "0101", "1111"],
+                &["1111", "0111", "1111"],
+            ],
+            (1, 0),
+        );
+        assert_golden(
+            &labyrinth,
+            (1, 1),
+            &[
+                (MapDirection::North, "QMMQQQQQcaaaaaa"),
+                (MapDirection::East, "QQQQQPAQaaGaaaa"),
+                (MapDirection::South, "QMMQQQQQaaaacaa"),
+                (MapDirection::West, "QQQQQPAQaaaiaaa"),
             ],
-            vec![
-                // Row 1: left and right cells with walls true; center cell with no walls.
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
-                Cell {
-                    north_wall: false,
-                    east_wall: false,
-                    south_wall: false,
-                    west_wall: false,
-                    has_hint: false,
-                    has_exit: false,
-                },
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
+        );
+    }
+
+    #[test]
+    fn test_golden_radar_top_left_corner() {
+        // Player in the top-left corner: the radar sees outside the labyrinth.
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1011", "1010", "1100"],
+                &["1001", "1100", "0101"],
+                &["0011", "0010", "0110"],
             ],
-            vec![
-                // Row 2 (bottom): all cells with all walls true.
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
-                Cell {
-                    north_wall: true,
-                    east_wall: true,
-                    south_wall: true,
-                    west_wall: true,
-                    has_hint: false,
-                    has_exit: false,
-                },
+            (2, 2),
+        );
+        assert_golden(
+            &labyrinth,
+            (0, 0),
+            &[
+                (MapDirection::North, "HAiajIua//8a8aa"),
+                (MapDirection::East, "GfiyacKP8a8a//a"),
+                (MapDirection::South, "aiPsafIyapap//a"),
+                (MapDirection::West, "jiucAgGa//apapa"),
             ],
-        ];
-        let labyrinth = Labyrinth {
-            width: 3,
-            height: 3,
-            cells,
-            exit_position: (1, 1),
+        );
+    }
+
+    #[test]
+    fn test_golden_radar_exit_and_hint_in_view() {
+        let mut labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1010", "1100"],
+                &["0001", "1000", "0110"],
+                &["0011", "0010", "1110"],
+            ],
+            (0, 2),
+        );
+        labyrinth.cells[0][2].has_hint = true;
+        assert_golden(
+            &labyrinth,
+            (1, 1),
+            &[
+                (MapDirection::North, "QPwPLPAwaeaaGaa"),
+                (MapDirection::East, "ALwPLQAAqaaaaia"),
+                (MapDirection::South, "ALAQLPAwaiaaqaa"),
+                (MapDirection::West, "ALwPPPQwGaaaaea"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_radar_view_with_hint_serialization() {
+        let response = wire::RadarViewWithHintResponse {
+            radar_view: "vvvvvvvvaaaaaia".to_string(),
+            hint: wire::RelativeCompassResponse::from(90.0),
+            seq: None,
+            facing: None,
+            orientation: None,
         };
-        let player_position = (1, 1);
-        let encoded = encode_radar_view(player_position, MapDirection::North, &labyrinth);
-        assert_eq!(encoded, "beeqkcGO8p8p8pa");
+        let json: serde_json::Value = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["RadarView"], "vvvvvvvvaaaaaia");
+        assert_eq!(json["Hint"]["RelativeCompass"]["angle"], 90.0);
+    }
+
+    #[test]
+    fn test_toggle_wall_keeps_neighbors_consistent() {
+        let mut labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1100"],
+                &["0001", "0000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (2, 2),
+        );
+
+        // Close the passage between (1, 1) and its eastern neighbor
+        assert_eq!(
+            toggle_wall(&mut labyrinth, 1, 1, MapDirection::East),
+            Some((2, 1))
+        );
+        assert!(labyrinth.cells[1][1].east_wall);
+        assert!(labyrinth.cells[1][2].west_wall);
+
+        // Toggling from the other side opens it again for both cells
+        assert_eq!(
+            toggle_wall(&mut labyrinth, 2, 1, MapDirection::West),
+            Some((1, 1))
+        );
+        assert!(!labyrinth.cells[1][1].east_wall);
+        assert!(!labyrinth.cells[1][2].west_wall);
+
+        // North/south walls are paired the same way
+        toggle_wall(&mut labyrinth, 0, 1, MapDirection::North);
+        assert!(labyrinth.cells[1][0].north_wall);
+        assert!(labyrinth.cells[0][0].south_wall);
+
+        // Outer border and out-of-range cells are refused
+        assert_eq!(toggle_wall(&mut labyrinth, 0, 0, MapDirection::West), None);
+        assert_eq!(toggle_wall(&mut labyrinth, 2, 2, MapDirection::South), None);
+        assert_eq!(toggle_wall(&mut labyrinth, 3, 0, MapDirection::North), None);
+        assert!(labyrinth.cells[0][0].west_wall);
+    }
+
+    #[test]
+    fn test_stats_snapshot() {
+        let mut state = ServerState::new(
+            labyrinth_from_masks(&[&["1111"]], (0, 0)),
+            ServerConfig::default(),
+        );
+        state.next_player_id = 2;
+        state.teams.insert(
+            "Team 1".to_string(),
+            Team {
+                name: "Team 1".to_string(),
+                registration_token: "token".to_string(),
+                expected_players: 3,
+                players: vec!["Nino".to_string(), "Paul".to_string()],
+                secrets: HashMap::new(),
+            },
+        );
+        // Nino is still playing, Paul found the exit and left
+        state.players.insert(
+            "Team 1/Nino".to_string(),
+            Player {
+                id: 0,
+                name: "Nino".to_string(),
+                team_name: "Team 1".to_string(),
+                position: (0, 0),
+                direction: MapDirection::North,
+                moves: 4,
+                visited: HashSet::from([(0, 0)]),
+                solves: 0,
+                rng: player_rng(None, 0),
+                secret: 0,
+                challenge: None,
+                hints: Vec::new(),
+            },
+        );
+        state.stats.record_move("Team 1/Nino", 4);
+        state.stats.record_exit("Team 1/Paul", 12);
+
+        let snapshot = Stats::snapshot(&state);
+        assert_eq!(snapshot["total_games"], 1);
+        assert_eq!(snapshot["teams"][0]["name"], "Team 1");
+
+        let players = &snapshot["teams"][0]["players"];
+        assert_eq!(players[0]["name"], "Nino");
+        assert_eq!(players[0]["moves"], 4);
+        assert_eq!(players[0]["found_exit"], false);
+        assert_eq!(players[1]["name"], "Paul");
+        assert_eq!(players[1]["moves"], 12);
+        assert_eq!(players[1]["exit_moves"], 12);
+
+        assert_eq!(snapshot["exits"][0]["player"], "Team 1/Paul");
+        assert_eq!(snapshot["exits"][0]["moves"], 12);
+    }
+
+    #[test]
+    fn test_map_direction_from_str() {
+        assert_eq!("north".parse(), Ok(MapDirection::North));
+        assert_eq!("S".parse(), Ok(MapDirection::South));
+        assert_eq!("East".parse(), Ok(MapDirection::East));
+        assert_eq!("w".parse(), Ok(MapDirection::West));
+
+        let error = "up".parse::<MapDirection>().unwrap_err();
+        assert!(error.contains("unknown direction 'up'"));
+    }
+
+    /// Builds a game state with a single player "Team 1/Nino" in the given labyrinth.
+    fn state_with_player(
+        labyrinth: Labyrinth,
+        position: (usize, usize),
+        direction: MapDirection,
+    ) -> ServerState {
+        let mut state = ServerState::new(labyrinth, ServerConfig::default());
+        state.next_player_id = 1;
+        state.players.insert(
+            "Team 1/Nino".to_string(),
+            Player {
+                id: 0,
+                name: "Nino".to_string(),
+                team_name: "Team 1".to_string(),
+                position,
+                direction,
+                moves: 0,
+                visited: HashSet::from([position]),
+                solves: 0,
+                rng: player_rng(None, 0),
+                secret: 0,
+                challenge: None,
+                hints: Vec::new(),
+            },
+        );
+        state
+    }
+
+    #[test]
+    fn test_moves_leave_a_trail() {
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1100"],
+                &["0001", "0000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (0, 0),
+        );
+        let mut state = state_with_player(labyrinth, (0, 1), MapDirection::East);
+
+        // Two steps east, then turn right (south)
+        state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Right).unwrap();
+        assert_eq!(outcome.new_position, (2, 2));
+
+        let visited = &state.players["Team 1/Nino"].visited;
+        assert_eq!(*visited, HashSet::from([(0, 1), (1, 1), (2, 1), (2, 2)]));
+
+        // Bumping into a wall does not extend the trail
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        assert!(outcome.hit_wall);
+        assert_eq!(state.players["Team 1/Nino"].visited.len(), 4);
+    }
+
+    #[test]
+    fn test_cell_collapses_after_player_left() {
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1100"],
+                &["0001", "0000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (0, 0),
+        );
+        let mut state = state_with_player(labyrinth, (0, 1), MapDirection::East);
+        state.config.collapse_after = Some(2);
+
+        // Tick 1: leave (0, 1), it collapses at tick 3
+        state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        // Tick 2: still standing
+        state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        assert!(!state.labyrinth.collapsed_cells.contains(&(0, 1)));
+
+        // Tick 3: step back to (1, 1), (0, 1) collapses
+        state.apply_move("Team 1/Nino", &Direction::Back).unwrap();
+        assert!(state.labyrinth.collapsed_cells.contains(&(0, 1)));
+        assert_eq!(encode_cell(&state.labyrinth, 0, 1), 0b1100);
+
+        // Tick 4: the collapsed cell cannot be entered anymore
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Back).unwrap();
+        assert!(outcome.hit_wall);
+        assert_eq!(outcome.new_position, (1, 1));
+
+        // (1, 1) was entered again before its timer ran out, so it still stands
+        assert!(!state.labyrinth.collapsed_cells.contains(&(1, 1)));
+    }
+
+    #[test]
+    fn test_validate_message() {
+        let parse = |json: &str| serde_json::from_str::<Message>(json).unwrap();
+        let validate_message = |message: &Message| validate_message(message, 64);
+
+        assert_eq!(
+            validate_message(&parse(r#"{"RegisterTeam":{"name":"Team 1"}}"#)),
+            Ok(())
+        );
+        assert_eq!(
+            validate_message(&parse(r#"{"RegisterTeam":{"name":"  "}}"#)),
+            Err("Team name must not be empty".to_string())
+        );
+
+        assert_eq!(
+            validate_message(&parse(
+                r#"{"SubscribePlayer":{"name":"Nino","registration_token":"0123456789ABCDEF"}}"#
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            validate_message(&parse(
+                r#"{"SubscribePlayer":{"name":"","registration_token":"0123456789ABCDEF"}}"#
+            )),
+            Err("Player name must not be empty".to_string())
+        );
+        assert_eq!(
+            validate_message(&parse(
+                r#"{"SubscribePlayer":{"name":"Nino","registration_token":"0123"}}"#
+            )),
+            Err("Registration token must be 16 hexadecimal characters, got '0123'".to_string())
+        );
+        assert_eq!(
+            validate_message(&parse(
+                r#"{"SubscribePlayer":{"name":"Nino","registration_token":"0123456789ABCDEG"}}"#
+            )),
+            Err(
+                "Registration token must be 16 hexadecimal characters, got '0123456789ABCDEG'"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate_name_length() {
+        let register_team = |name: &str| {
+            Message::RegisterTeam(RegisterTeam {
+                name: name.to_string(),
+                expected_players: None,
+                protocol_version: None,
+            })
+        };
+        let subscribe_player = |name: &str| {
+            Message::SubscribePlayer(SubscribePlayer {
+                name: name.to_string(),
+                registration_token: "0123456789abcdef".to_string(),
+            })
+        };
+        let max_name_length = ServerConfig::default().max_name_length();
+        assert_eq!(max_name_length, 64);
+
+        assert_eq!(
+            validate_message(&register_team(&"T".repeat(64)), max_name_length),
+            Ok(())
+        );
+        assert_eq!(
+            validate_message(&register_team(&"T".repeat(65)), max_name_length),
+            Err("Team name must be at most 64 characters, got 65".to_string())
+        );
+        assert_eq!(
+            validate_message(&register_team(""), max_name_length),
+            Err("Team name must not be empty".to_string())
+        );
+        assert_eq!(
+            validate_message(&subscribe_player("Nino"), 3),
+            Err("Player name must be at most 3 characters, got 4".to_string())
+        );
+        assert_eq!(
+            validate_message(&subscribe_player(""), max_name_length),
+            Err("Player name must not be empty".to_string())
+        );
+    }
+
+    #[test]
+    fn test_radar_noise_produces_error_passages() {
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1100"],
+                &["0001", "0000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (2, 2),
+        );
+        let clean = radar_view_bytes((1, 1), MapDirection::North, &labyrinth, false, None, None);
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // Without noise nothing changes
+        let mut data = clean;
+        assert!(!add_radar_noise(&mut data, 0.0, &mut rng));
+        assert_eq!(data, clean);
+
+        let mut error_passages = 0;
+        for _ in 0..50 {
+            let mut data = clean;
+            assert!(add_radar_noise(&mut data, 1.0, &mut rng));
+
+            // Exactly one passage bit flipped, cells untouched
+            let flipped: u32 = (0..11).map(|i| (data[i] ^ clean[i]).count_ones()).sum();
+            assert_eq!(flipped, 1);
+            assert_eq!(data[6..], clean[6..]);
+
+            let passages = [data[0], data[1], data[2], data[3], data[4], data[5]];
+            error_passages += passages
+                .iter()
+                .flat_map(|byte| (0..4).map(move |i| (byte >> (i * 2)) & 0b11))
+                .filter(|&bits| bits == 0b11)
+                .count();
+        }
+        assert!(error_passages > 0);
+    }
+
+    /// send_message and receive_message log every message, so their log must be set up.
+    fn init_test_logging() {
+        let log_dir = std::env::temp_dir().join("sqp-server-test-log");
+        logger::init_logging(log_dir.to_str().unwrap(), &["server_message"]).unwrap();
+    }
+
+    #[test]
+    fn test_arenas_are_independent() {
+        init_test_logging();
+        let first_state = new_arena_state(5, 5, ServerConfig::default());
+        let second_state = new_arena_state(5, 5, ServerConfig::default());
+
+        let mut addresses = Vec::new();
+        for state in [&first_state, &second_state] {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            addresses.push(listener.local_addr().unwrap());
+            let state = Arc::clone(state);
+            thread::spawn(move || serve_arena(listener, state));
+        }
+
+        // Register a team on the first arena only
+        let mut stream = TcpStream::connect(addresses[0]).unwrap();
+        let register_team = json!({ "RegisterTeam": { "name": "Team 1" } });
+        send_message(&mut stream, &register_team).unwrap();
+        let response = receive_message(&mut stream).unwrap();
+        let token = parse_token_from_response(&response).unwrap();
+
+        assert!(first_state.lock().unwrap().teams.contains_key("Team 1"));
+        assert!(second_state.lock().unwrap().teams.is_empty());
+
+        // The token of the first arena is unknown to the second one
+        let mut stream = TcpStream::connect(addresses[1]).unwrap();
+        let subscribe_player = json!({
+            "SubscribePlayer": { "name": "Nino", "registration_token": token }
+        });
+        send_message(&mut stream, &subscribe_player).unwrap();
+        let response = receive_message(&mut stream).unwrap();
+        assert!(response.contains("Invalid registration token"));
+    }
+
+    #[test]
+    fn test_subscriptions_beyond_expected_players_are_refused() {
+        init_test_logging();
+        let state = new_arena_state(5, 5, ServerConfig::default());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_arena(listener, state));
+
+        let register = |register_team: serde_json::Value| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            send_message(&mut stream, &register_team).unwrap();
+            let response = receive_message(&mut stream).unwrap();
+            (parse_token_from_response(&response).unwrap(), response)
+        };
+        // Subscribed players stay connected, or their slot would be freed
+        let mut players = Vec::new();
+        let mut subscribe = |name: &str, token: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let subscribe_player = json!({
+                "SubscribePlayer": { "name": name, "registration_token": token }
+            });
+            send_message(&mut stream, &subscribe_player).unwrap();
+            let response = receive_message(&mut stream).unwrap();
+            players.push(stream);
+            response
+        };
+
+        // A team of two for a two-player harness
+        let (token, response) =
+            register(json!({ "RegisterTeam": { "name": "Pair", "expected_players": 2 } }));
+        assert!(response.contains(r#""expected_players":2"#));
+        assert!(subscribe("Nino", &token).contains("Ok"));
+        assert!(subscribe("Paul", &token).contains("Ok"));
+        assert_eq!(
+            subscribe("Lea", &token),
+            r#"{"SubscribePlayerResult":{"Error":"team full"}}"#
+        );
+
+        // Without expected_players, a team still expects 3 players
+        let (token, response) = register(json!({ "RegisterTeam": { "name": "Trio" } }));
+        assert!(response.contains(r#""expected_players":3"#));
+        for name in ["Nino", "Paul", "Lea"] {
+            assert!(subscribe(name, &token).contains("Ok"));
+        }
+        assert!(subscribe("Max", &token).contains("team full"));
+    }
+
+    #[test]
+    fn test_action_seq_is_echoed_in_radar_view() {
+        init_test_logging();
+        let state = new_arena_state(5, 5, ServerConfig::default());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_arena(listener, state));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let register_team = json!({ "RegisterTeam": { "name": "Team 1" } });
+        send_message(&mut stream, &register_team).unwrap();
+        let token = parse_token_from_response(&receive_message(&mut stream).unwrap()).unwrap();
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let subscribe_player = json!({
+            "SubscribePlayer": { "name": "Nino", "registration_token": token }
+        });
+        send_message(&mut stream, &subscribe_player).unwrap();
+        assert!(receive_message(&mut stream).unwrap().contains("Ok"));
+        assert!(next_radar_view(&mut stream).get("seq").is_none());
+
+        // Reads responses until the next radar view comes in
+        fn next_radar_view(stream: &mut TcpStream) -> serde_json::Value {
+            loop {
+                let response: serde_json::Value =
+                    serde_json::from_str(&receive_message(stream).unwrap()).unwrap();
+                if response.get("RadarView").is_some() {
+                    return response;
+                }
+            }
+        }
+
+        let action = json!({ "Action": { "MoveTo": "Front" }, "seq": 7 });
+        send_message(&mut stream, &action).unwrap();
+        assert_eq!(next_radar_view(&mut stream)["seq"], 7);
+
+        // Actions without seq are still accepted and get no seq back
+        send_message(&mut stream, &json!({ "Action": { "MoveTo": "Front" } })).unwrap();
+        assert!(next_radar_view(&mut stream).get("seq").is_none());
+    }
+
+    fn empty_team(expected_players: usize) -> Team {
+        Team {
+            name: "Team 1".to_string(),
+            registration_token: "0123456789abcdef".to_string(),
+            expected_players,
+            players: Vec::new(),
+            secrets: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_team_accepts_players_up_to_capacity() {
+        let mut team = empty_team(3);
+        for name in ["Nino", "Paul", "Lea"] {
+            assert!(!team.is_full());
+            assert_eq!(team.add_player(name), Ok(()));
+        }
+        assert!(team.is_full());
+        assert_eq!(team.players, vec!["Nino", "Paul", "Lea"]);
+    }
+
+    #[test]
+    fn test_team_rejects_overflow() {
+        let mut team = empty_team(1);
+        team.add_player("Nino").unwrap();
+        assert!(team.add_player("Paul").is_err());
+        assert_eq!(team.players, vec!["Nino"]);
+    }
+
+    #[test]
+    fn test_team_rejects_duplicate_names() {
+        let mut team = empty_team(3);
+        team.add_player("Nino").unwrap();
+        assert!(team.add_player("Nino").is_err());
+        assert_eq!(team.players, vec!["Nino"]);
+        assert!(!team.is_full());
+    }
+
+    #[test]
+    fn test_first_players_of_each_team_spawn_alike() {
+        let state = new_arena_state(5, 5, ServerConfig::default());
+        let mut state = state.lock().unwrap();
+        for team_name in ["Team 1", "Team 2"] {
+            let mut team = empty_team(3);
+            team.name = team_name.to_string();
+            state.teams.insert(team_name.to_string(), team);
+        }
+
+        let nino = state.add_player("Team 1", "Nino").unwrap();
+        let paul = state.add_player("Team 1", "Paul").unwrap();
+        let lea = state.add_player("Team 2", "Lea").unwrap();
+
+        let spawn = |key: &String| (state.players[key].position, state.players[key].direction);
+        assert_eq!(spawn(&nino), spawn(&lea));
+        assert_eq!(spawn(&nino), spawn_point(0, None, (5, 5)));
+        assert_eq!(spawn(&paul), spawn_point(1, None, (5, 5)));
+        assert_ne!(spawn(&nino), spawn(&paul));
+        assert_eq!(state.teams["Team 2"].players, vec!["Lea"]);
+    }
+
+    #[test]
+    fn test_status_page_reports_team_count() {
+        let state = new_arena_state(5, 5, ServerConfig::default());
+        state
+            .lock()
+            .unwrap()
+            .teams
+            .insert("Team 1".to_string(), empty_team(3));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_status(listener, vec![state], Instant::now()));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let status: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(status["teams"], 1);
+        assert_eq!(status["players"], 0);
+        assert_eq!(status["moves_processed"], 0);
+    }
+
+    #[test]
+    fn test_status_page_is_not_blocked_by_a_silent_client() {
+        let state = new_arena_state(5, 5, ServerConfig::default());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_status(listener, vec![state], Instant::now()));
+
+        // Connects and never sends its request
+        let _silent = TcpStream::connect(address).unwrap();
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(std::time::Duration::from_secs(10)))
+            .unwrap();
+        stream
+            .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn test_parse_maze_dimensions() {
+        assert_eq!(parse_maze_dimensions("7,5"), Some((7, 5)));
+        assert_eq!(parse_maze_dimensions(" 7, 5 "), Some((7, 5)));
+        for invalid in ["7", "7,5,3", "a,5", "7,-5", "0,5", "7,0", ""] {
+            assert_eq!(parse_maze_dimensions(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_maze_id_recreates_the_arena() {
+        let config = ServerConfig {
+            combine_hint_with_radar: true,
+            start_facing: Some(MapDirection::South),
+            collapse_after: Some(4),
+            radar_noise: Some(0.25),
+            seed: Some(1234),
+            ..ServerConfig::default()
+        };
+
+        let id = maze_id(7, 5, &config);
+        assert_eq!(parse_maze_id(&id), Ok((7, 5, config.clone())));
+
+        let first = generate_labyrinth(7, 5, config.seed.unwrap(), None, false);
+        let second = generate_labyrinth(7, 5, config.seed.unwrap(), None, false);
+        assert_connected(&first);
+        assert!(first.cells == second.cells);
+        assert_eq!(first.exit_position, second.exit_position);
+
+        assert!(parse_maze_id("not a maze id").is_err());
+        let without_seed = ServerConfig::default();
+        assert!(parse_maze_id(&maze_id(7, 5, &without_seed)).is_err());
+    }
+
+    #[test]
+    fn test_seed_option_serves_the_same_maze() {
+        let config = ServerConfig {
+            seed: Some(2009),
+            ..ServerConfig::default()
+        };
+        let first = new_arena_state(9, 6, config.clone());
+        let second = new_arena_state(9, 6, config);
+        let (first, second) = (first.lock().unwrap(), second.lock().unwrap());
+
+        // Walls, hints and exit are all the same
+        assert!(first.labyrinth.cells == second.labyrinth.cells);
+        assert!(first
+            .labyrinth
+            .cells
+            .iter()
+            .flatten()
+            .any(|cell| cell.has_hint));
+        assert_eq!(
+            first.labyrinth.exit_position,
+            second.labyrinth.exit_position
+        );
+        assert_eq!(first.config.seed, Some(2009));
+    }
+
+    #[test]
+    fn test_saved_labyrinth_loads_back_the_same() {
+        let path = std::env::temp_dir().join(format!("sqp-saved-maze-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let config = ServerConfig {
+            seed: Some(2010),
+            save_maze: Some(path.to_string()),
+            ..ServerConfig::default()
+        };
+        let state = new_arena_state(6, 4, config);
+        let mut saved = lock_state(&state).labyrinth.clone();
+
+        let loaded = load_labyrinth(path).unwrap();
+        assert_eq!((loaded.width, loaded.height), (6, 4));
+        assert!(loaded.cells == saved.cells);
+        assert_eq!(loaded.exit_position, saved.exit_position);
+        assert!(loaded.collapsed_cells.is_empty());
+
+        // A labyrinth whose exit is outside its cells is refused
+        saved.exit_position = (6, 0);
+        save_labyrinth(&saved, path).unwrap();
+        assert!(load_labyrinth(path).is_err());
+        std::fs::remove_file(path).unwrap();
+        assert!(load_labyrinth(path).is_err());
+    }
+
+    #[test]
+    fn test_count_only_stats_describe_the_seeded_maze() {
+        let maze = generate_arena_maze(8, 6, 42, None, false);
+        let stats = maze_stats(&labyrinth_from_maze(&maze), maze.exit_distance);
+
+        let hints = maze
+            .cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.has_hint)
+            .count();
+        assert_eq!(stats["exit_distance"], maze.exit_distance);
+        assert_eq!(stats["hints"], hints);
+        // A perfect maze has exactly one passage less than cells
+        assert_eq!(stats["open_passages"], 8 * 6 - 1);
+
+        // East-west corridor of three cells with a dead end at each side
+        let corridor = labyrinth_from_masks(&[&["1011", "1010", "1110"]], (2, 0));
+        let stats = maze_stats(&corridor, 0);
+        assert_eq!(stats["dead_ends"], 2);
+        assert_eq!(stats["open_passages"], 2);
+    }
+
+    #[test]
+    fn test_radar_passages_round_trip_through_the_decoder() {
+        // Corridor going east and west through the center cell
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1010", "1100"],
+                &["0011", "1010", "0110"],
+                &["1011", "1010", "1110"],
+            ],
+            (2, 2),
+        );
+        let (o, w) = (Passage::Open as u8, Passage::Wall as u8);
+
+        // Facing north: walls in front and behind, open on both sides.
+        // The ring passages follow the cells around, with walls along the border.
+        let encoded = encode_radar_view((1, 1), MapDirection::North, &labyrinth);
+        assert_eq!(encoded, "QPQPLPAwaaaaaia");
+        let data = encoder::decode(&encoded).unwrap();
+        assert_eq!(
+            passage_codes(&data[0..3]),
+            [w, w, w, o, w, o, w, w, w, w, w, w]
+        );
+        assert_eq!(
+            passage_codes(&data[3..6]),
+            [w, o, o, w, w, o, o, w, w, o, o, w]
+        );
+
+        // Facing east: the corridor is in front and behind
+        let data =
+            encoder::decode(&encode_radar_view((1, 1), MapDirection::East, &labyrinth)).unwrap();
+        assert_eq!(
+            passage_codes(&data[0..3]),
+            [w, w, w, o, o, o, o, o, o, w, w, w]
+        );
+        assert_eq!(
+            passage_codes(&data[3..6]),
+            [w, o, w, w, w, w, w, w, w, o, w, w]
+        );
+    }
+
+    #[test]
+    fn test_radar_dead_end_open_to_the_north() {
+        // The center cell only opens on the cell above it, every other cell is closed
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1111", "1101", "1111"],
+                &["1111", "0111", "1111"],
+                &["1111", "1111", "1111"],
+            ],
+            (1, 0),
+        );
+        let (o, w) = (Passage::Open as u8, Passage::Wall as u8);
+
+        // Facing north, the passage to the north is the front one
+        let data =
+            encoder::decode(&encode_radar_view((1, 1), MapDirection::North, &labyrinth)).unwrap();
+        let horizontal = passage_codes(&data[0..3]);
+        let vertical = passage_codes(&data[3..6]);
+        assert_eq!(horizontal[FRONT_PASSAGE], o);
+        assert_eq!(horizontal[BACK_PASSAGE], w);
+        assert_eq!(vertical[LEFT_PASSAGE], w);
+        assert_eq!(vertical[RIGHT_PASSAGE], w);
+        for (index, code) in horizontal.iter().enumerate() {
+            if index != FRONT_PASSAGE {
+                assert_eq!(*code, w, "horizontal passage {}", index);
+            }
+        }
+        assert_eq!(vertical, [w; 12]);
+
+        // Facing east, the same passage is on the left
+        let data =
+            encoder::decode(&encode_radar_view((1, 1), MapDirection::East, &labyrinth)).unwrap();
+        let horizontal = passage_codes(&data[0..3]);
+        let vertical = passage_codes(&data[3..6]);
+        assert_eq!(horizontal, [w; 12]);
+        assert_eq!(vertical[LEFT_PASSAGE], o);
+        for (index, code) in vertical.iter().enumerate() {
+            if index != LEFT_PASSAGE {
+                assert_eq!(*code, w, "vertical passage {}", index);
+            }
+        }
+    }
+
+    #[test]
+    fn test_radar_ascii_matches_decoded_radar() {
+        let mut labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1010", "1100"],
+                &["0001", "1000", "0110"],
+                &["0011", "0010", "1110"],
+            ],
+            (0, 2),
+        );
+        labyrinth.cells[0][2].has_hint = true;
+
+        let data = radar_view_bytes((1, 1), MapDirection::North, &labyrinth, false, None, None);
+        let sent = render_radar_bytes(&data).unwrap();
+        assert!(sent.contains('G') && sent.contains('H'));
+
+        // The client decodes the encoded string and draws it with the same renderer
+        let encoded = encode_radar_view((1, 1), MapDirection::North, &labyrinth);
+        let decoded = encoder::decode(&encoded).unwrap();
+        assert_eq!(render_radar_bytes(&decoded).unwrap(), sent);
+    }
+
+    #[test]
+    fn test_sense_byte_gives_the_bearing_of_a_nearby_exit() {
+        // Exit two cells east of the player, just out of the radar view
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1000", "1000", "1100"],
+                &["0011", "0010", "0010", "0010", "0110"],
+            ],
+            (3, 1),
+        );
+        let mut state = state_with_player(labyrinth, (1, 1), MapDirection::North);
+        let radar_bytes = |state: &ServerState| {
+            let player = &state.players["Team 1/Nino"];
+            encoder::decode(&encode_player_radar_view(state, player)).unwrap()
+        };
+
+        // Without --sense-radius the radar view keeps its 11 bytes
+        assert_eq!(radar_bytes(&state).len(), 11);
+
+        // Facing north, east is a quarter turn clockwise: 2 eighths
+        state.config.sense_radius = Some(2);
+        let data = radar_bytes(&state);
+        assert_eq!(data.len(), 12);
+        assert_eq!(data[11], 2);
+        let view = radar_view_bytes(
+            (1, 1),
+            MapDirection::North,
+            &state.labyrinth,
+            false,
+            None,
+            None,
+        );
+        assert_eq!(data[..11], view);
+
+        // Facing east, the exit is straight ahead
+        state.players.get_mut("Team 1/Nino").unwrap().direction = MapDirection::East;
+        assert_eq!(radar_bytes(&state)[11], 0);
+
+        // Out of the sense radius, or in view, there is nothing to sense
+        state.players.get_mut("Team 1/Nino").unwrap().position = (0, 1);
+        assert_eq!(radar_bytes(&state).len(), 11);
+        state.players.get_mut("Team 1/Nino").unwrap().position = (2, 1);
+        assert_eq!(radar_bytes(&state).len(), 11);
+    }
+
+    #[test]
+    fn test_moves_cross_the_middle_of_a_large_maze() {
+        assert_eq!(
+            process_move(&MapDirection::East, &Direction::Front),
+            (1, 0, MapDirection::East)
+        );
+
+        let mut labyrinth = generate_labyrinth(10, 10, 42, None, false);
+        labyrinth.cells[2][4].east_wall = false;
+        labyrinth.cells[2][5].west_wall = false;
+        labyrinth.cells[9][9].south_wall = false;
+        let mut state = state_with_player(labyrinth, (4, 2), MapDirection::East);
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        assert!(!outcome.hit_wall);
+        assert_eq!(outcome.new_position, (5, 2));
+
+        // The far edge still holds, even without a wall on it
+        state.players.get_mut("Team 1/Nino").unwrap().position = (9, 9);
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Right).unwrap();
+        assert!(outcome.hit_wall);
+        assert_eq!(outcome.new_position, (9, 9));
+    }
+
+    #[test]
+    fn test_apply_move_is_blocked_by_walls() {
+        // Corridor going east, with no wall on the south edge of its first cell
+        let labyrinth = labyrinth_from_masks(&[&["1001", "1010", "1110"]], (2, 0));
+        let mut state = state_with_player(labyrinth, (1, 0), MapDirection::North);
+
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Back).unwrap();
+        assert!(outcome.hit_wall);
+        assert_eq!(outcome.new_position, (1, 0));
+        assert_eq!(state.players["Team 1/Nino"].moves, 1);
+
+        // Leaving the labyrinth is blocked as well, and a blocked turn does not turn the player
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Left).unwrap();
+        assert_eq!(outcome.new_position, (0, 0));
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Left).unwrap();
+        assert!(outcome.hit_wall);
+        assert_eq!(outcome.new_position, (0, 0));
+        assert_eq!(outcome.new_direction, MapDirection::West);
+
+        assert!(state.apply_move("Team 1/Paul", &Direction::Front).is_err());
+    }
+
+    #[test]
+    fn test_apply_move_detects_the_exit() {
+        let labyrinth = labyrinth_from_masks(&[&["1011", "1010", "1110"]], (2, 0));
+        let mut state = state_with_player(labyrinth, (0, 0), MapDirection::East);
+
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        assert!(!outcome.found_exit);
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        assert!(outcome.found_exit);
+        assert_eq!(outcome.new_position, (2, 0));
+        assert_eq!(outcome.moves, 2);
+        assert_eq!(Stats::snapshot(&state)["exits"][0]["moves"], 2);
+    }
+
+    #[test]
+    fn test_register_and_subscribe() {
+        let mut state = ServerState::new(
+            labyrinth_from_masks(&[&["1111"]], (0, 0)),
+            ServerConfig::default(),
+        );
+        let (first, _) = state.register_team("Team 1", None).unwrap();
+        let (second, _) = state.register_team("Team 2", None).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            state.register_team("Team 1", None),
+            Err("AlreadyRegistered".to_string())
+        );
+        assert_eq!(state.teams["Team 1"].registration_token, first);
+
+        assert_eq!(
+            state.subscribe_player("unknown", "Nino"),
+            Err("Invalid registration token".to_string())
+        );
+        let key = state.subscribe_player(&second, "Nino").unwrap();
+        assert_eq!(key, "Team 2/Nino");
+        // Spawn points stay inside a labyrinth smaller than 5x5
+        assert_eq!(state.players[&key].position, (0, 0));
+        assert!(state.subscribe_player(&second, "Nino").is_err());
+    }
+
+    #[test]
+    fn test_register_team_writes_response_bytes() {
+        init_test_logging();
+        let state = Arc::new(Mutex::new(ServerState::new(
+            labyrinth_from_masks(&[&["1111"]], (0, 0)),
+            ServerConfig::default(),
+        )));
+        let message = RegisterTeam {
+            name: "Team 1".to_string(),
+            expected_players: None,
+            protocol_version: None,
+        };
+
+        let mut sink: Vec<u8> = Vec::new();
+        handle_register_team(&mut sink, &message, Arc::clone(&state)).unwrap();
+
+        let token = state.lock().unwrap().teams["Team 1"]
+            .registration_token
+            .clone();
+        let json = format!(
+            r#"{{"RegisterTeamResult":{{"Ok":{{"expected_players":3,"registration_token":"{}"}}}}}}"#,
+            token
+        );
+        let mut expected = (json.len() as u32).to_le_bytes().to_vec();
+        expected.extend_from_slice(json.as_bytes());
+        assert_eq!(sink, expected);
+
+        // The same bytes are read back as one message
+        let received = receive_message(&mut std::io::Cursor::new(&sink)).unwrap();
+        assert_eq!(received, json);
+
+        // Registering the name again answers with an error
+        let mut sink: Vec<u8> = Vec::new();
+        handle_register_team(&mut sink, &message, state).unwrap();
+        assert_eq!(
+            &sink[4..],
+            br#"{"RegisterTeamResult":{"Error":"AlreadyRegistered"}}"#
+        );
+    }
+
+    #[test]
+    fn test_register_team_refuses_another_protocol_version() {
+        init_test_logging();
+        let state = Arc::new(Mutex::new(ServerState::new(
+            labyrinth_from_masks(&[&["1111"]], (0, 0)),
+            ServerConfig::default(),
+        )));
+        let register = |name: &str, protocol_version| {
+            let message = RegisterTeam {
+                name: name.to_string(),
+                expected_players: None,
+                protocol_version,
+            };
+            let mut sink: Vec<u8> = Vec::new();
+            handle_register_team(&mut sink, &message, Arc::clone(&state)).unwrap();
+            serde_json::from_str::<serde_json::Value>(
+                &receive_message(&mut std::io::Cursor::new(&sink)).unwrap(),
+            )
+            .unwrap()
+        };
+
+        let refused = register("Team 1", Some(PROTOCOL_VERSION + 1));
+        assert_eq!(
+            refused,
+            json!({
+                "RegisterTeamResult": { "Error": "VersionMismatch" },
+                "protocol_version": PROTOCOL_VERSION
+            })
+        );
+        assert!(lock_state(&state).teams.is_empty());
+
+        // The same version is accepted and echoed, no version at all is the reference protocol
+        let accepted = register("Team 1", Some(PROTOCOL_VERSION));
+        assert!(accepted["RegisterTeamResult"]["Ok"].is_object());
+        assert_eq!(accepted["protocol_version"], PROTOCOL_VERSION);
+        let reference = register("Team 2", None);
+        assert!(reference["RegisterTeamResult"]["Ok"].is_object());
+        assert!(reference.get("protocol_version").is_none());
+    }
+
+    #[test]
+    fn test_max_players_is_shared_and_freed_on_disconnect() {
+        let config = ServerConfig {
+            max_players: Some(2),
+            ..ServerConfig::default()
+        };
+        let labyrinth = || labyrinth_from_masks(&[&["1111"]], (0, 0));
+        let mut first = ServerState::new(labyrinth(), config.clone());
+        let mut second = ServerState::new(labyrinth(), config);
+        second.active_players = Arc::clone(&first.active_players);
+
+        let (first_token, _) = first.register_team("Team 1", None).unwrap();
+        let (second_token, _) = second.register_team("Team 2", None).unwrap();
+        let nino = first.subscribe_player(&first_token, "Nino").unwrap();
+        second.subscribe_player(&second_token, "Lea").unwrap();
+
+        // The cap counts the players of both arenas
+        assert_eq!(
+            first.subscribe_player(&first_token, "Paul"),
+            Err("server at capacity".to_string())
+        );
+        assert!(!first.players.contains_key("Team 1/Paul"));
+        assert_eq!(first.teams["Team 1"].players, vec!["Nino"]);
+
+        // A refused subscription does not take a slot
+        assert!(first.subscribe_player(&first_token, "Nino").is_err());
+        assert_eq!(first.active_players.load(Ordering::SeqCst), 2);
+
+        // Once a player disconnects, someone else can join
+        assert!(first.remove_player(&nino));
+        assert!(!first.remove_player(&nino));
+        assert!(first.subscribe_player(&first_token, "Paul").is_ok());
+    }
+
+    #[test]
+    fn test_noop_sends_radar_without_moving() {
+        init_test_logging();
+        let labyrinth = labyrinth_from_masks(&[&["1001", "1010", "1110"]], (2, 0));
+        let state = Arc::new(Mutex::new(state_with_player(
+            labyrinth,
+            (1, 0),
+            MapDirection::East,
+        )));
+
+        let mut sink: Vec<u8> = Vec::new();
+        handle_action(
+            &mut sink,
+            &Action::Noop,
+            Some(7),
+            Arc::clone(&state),
+            "127.0.0.1:1".parse().unwrap(),
+            Some("Team 1/Nino".to_string()),
+        )
+        .unwrap();
+
+        let response: serde_json::Value =
+            serde_json::from_str(&receive_message(&mut std::io::Cursor::new(&sink)).unwrap())
+                .unwrap();
+        let state = state.lock().unwrap();
+        let player = &state.players["Team 1/Nino"];
+        assert_eq!(
+            response["RadarView"],
+            encode_player_radar_view(&state, player)
+        );
+        assert_eq!(response["seq"], 7);
+        assert_eq!(player.moves, 0);
+        assert_eq!(player.position, (1, 0));
+        assert_eq!(state.tick, 0);
+    }
+
+    #[test]
+    fn test_every_fourth_hint_gives_the_player_secret() {
+        init_test_logging();
+        let labyrinth = labyrinth_from_masks(&[&["1011", "1010", "1110"]], (2, 0));
+        let mut state = state_with_player(labyrinth, (0, 0), MapDirection::East);
+        let player = state.players.get_mut("Team 1/Nino").unwrap();
+        player.secret = 1234;
+        player.moves = SECRET_HINT_EVERY - 1;
+        let state = Arc::new(Mutex::new(state));
+
+        let mut sink: Vec<u8> = Vec::new();
+        handle_action(
+            &mut sink,
+            &Action::MoveTo(Direction::Front),
+            None,
+            Arc::clone(&state),
+            "127.0.0.1:1".parse().unwrap(),
+            Some("Team 1/Nino".to_string()),
+        )
+        .unwrap();
+
+        let mut responses = std::io::Cursor::new(&sink);
+        let mut next = || -> serde_json::Value {
+            serde_json::from_str(&receive_message(&mut responses).unwrap()).unwrap()
+        };
+        assert_eq!(next(), json!({ "Hint": { "Secret": 1234 } }));
+        // The secret replaces the compass of this hint
+        let radar_view = next();
+        assert!(radar_view.get("RadarView").is_some());
+        assert!(radar_view.get("Hint").is_none());
+    }
+
+    #[test]
+    fn test_get_hints_returns_the_hints_sent_in_order() {
+        init_test_logging();
+        let labyrinth = labyrinth_from_masks(&[&["1011", "1010", "1010", "1110"]], (3, 0));
+        let mut state = state_with_player(labyrinth, (0, 0), MapDirection::East);
+        let player = state.players.get_mut("Team 1/Nino").unwrap();
+        player.secret = 1234;
+        player.moves = 7;
+        let state = Arc::new(Mutex::new(state));
+        let act = |action: Action, sink: &mut Vec<u8>| {
+            handle_action(
+                sink,
+                &action,
+                None,
+                Arc::clone(&state),
+                "127.0.0.1:1".parse().unwrap(),
+                Some("Team 1/Nino".to_string()),
+            )
+            .unwrap();
+        };
+
+        // A compass on the 8th move, then the secret on the 32nd
+        let mut sink: Vec<u8> = Vec::new();
+        act(Action::MoveTo(Direction::Front), &mut sink);
+        lock_state(&state)
+            .players
+            .get_mut("Team 1/Nino")
+            .unwrap()
+            .moves = SECRET_HINT_EVERY - 1;
+        act(Action::MoveTo(Direction::Front), &mut sink);
+
+        let mut sink: Vec<u8> = Vec::new();
+        act(Action::GetHints, &mut sink);
+        let response: serde_json::Value =
+            serde_json::from_str(&receive_message(&mut std::io::Cursor::new(&sink)).unwrap())
+                .unwrap();
+        assert_eq!(
+            response,
+            json!({ "HintHistory": [
+                { "RelativeCompass": { "angle": 0.0 } },
+                { "Secret": 1234 }
+            ] })
+        );
+        assert_eq!(
+            lock_state(&state).players["Team 1/Nino"].moves,
+            SECRET_HINT_EVERY
+        );
+    }
+
+    #[test]
+    fn test_lock_state_recovers_from_a_poisoned_mutex() {
+        init_test_logging();
+        let state = new_arena_state(5, 5, ServerConfig::default());
+
+        // A player thread panicking while it holds the lock poisons the mutex
+        let poisoner = Arc::clone(&state);
+        let panicked = thread::spawn(move || {
+            let _state = poisoner.lock().unwrap();
+            panic!("player thread failed");
+        })
+        .join();
+        assert!(panicked.is_err());
+        assert!(state.is_poisoned());
+
+        lock_state(&state).tick += 1;
+        assert!(!state.is_poisoned());
+        assert_eq!(lock_state(&state).tick, 1);
+    }
+
+    #[test]
+    fn test_challenge_modulo_is_in_range() {
+        let mut rng = player_rng(Some(42), 0);
+        for _ in 0..200 {
+            let challenge =
+                PendingChallenge::secret_sum_modulo(&mut rng, [u64::MAX, 7, 3].into_iter());
+            assert!(CHALLENGE_MODULO_RANGE.contains(&challenge.modulo));
+            let sum = u128::from(u64::MAX) + 10;
+            assert_eq!(
+                u128::from(challenge.answer),
+                sum % u128::from(challenge.modulo)
+            );
+        }
+    }
+
+    #[test]
+    fn test_moves_wait_for_the_challenge_to_be_solved() {
+        init_test_logging();
+        let labyrinth = labyrinth_from_masks(&[&["1011", "1010", "1110"]], (2, 0));
+        let mut state = state_with_player(labyrinth, (0, 0), MapDirection::East);
+        state.config.challenge_every = Some(1);
+        state.teams.insert("Team 1".to_string(), empty_team(3));
+        state.teams.get_mut("Team 1").unwrap().secrets =
+            HashMap::from([("Nino".to_string(), 10), ("Paul".to_string(), 15)]);
+        let state = Arc::new(Mutex::new(state));
+
+        let act = |action: Action| {
+            let mut sink: Vec<u8> = Vec::new();
+            handle_action(
+                &mut sink,
+                &action,
+                None,
+                Arc::clone(&state),
+                "127.0.0.1:1".parse().unwrap(),
+                Some("Team 1/Nino".to_string()),
+            )
+            .unwrap();
+            let response: serde_json::Value =
+                serde_json::from_str(&receive_message(&mut std::io::Cursor::new(&sink)).unwrap())
+                    .unwrap();
+            response
+        };
+        let answer = |answer: u64| {
+            Action::SolveChallenge(wire::Answer {
+                answer: answer.to_string(),
+            })
+        };
+
+        let challenge = act(Action::MoveTo(Direction::Front));
+        let modulo = challenge["Challenge"]["SecretSumModulo"].as_u64().unwrap();
+        assert!(CHALLENGE_MODULO_RANGE.contains(&modulo));
+
+        // Moving again only repeats the challenge
+        assert_eq!(act(Action::MoveTo(Direction::Front)), challenge);
+        assert_eq!(
+            state.lock().unwrap().players["Team 1/Nino"].position,
+            (1, 0)
+        );
+
+        assert_eq!(act(answer(25 % modulo + 1)), challenge);
+        let radar_view = act(answer(25 % modulo));
+        assert!(radar_view.get("RadarView").is_some());
+        assert!(state.lock().unwrap().players["Team 1/Nino"]
+            .challenge
+            .is_none());
+    }
+
+    #[test]
+    fn test_player_list_is_sorted() {
+        let mut state = ServerState::new(
+            labyrinth_from_masks(&[&["1001", "1010", "1110"]], (2, 0)),
+            ServerConfig::default(),
+        );
+        state.teams.insert("Team 1".to_string(), empty_team(3));
+        for name in ["Paul", "Lea", "Nino"] {
+            state.add_player("Team 1", name).unwrap();
+        }
+
+        let list = player_list(&state);
+        let keys: Vec<&str> = list
+            .lines()
+            .map(|line| line.trim_start().split(" at ").next().unwrap())
+            .collect();
+        assert_eq!(keys, vec!["Team 1/Lea", "Team 1/Nino", "Team 1/Paul"]);
+    }
+
+    #[test]
+    fn test_compare_starts_inside_small_mazes() {
+        for (width, height) in [(3, 3), (1, 4), (6, 2)] {
+            let labyrinth = generate_labyrinth(width, height, 1, None, false);
+            let (start, facing) = spawn_point(0, None, (width, height));
+            assert!(start.0 < width && start.1 < height, "{:?}", start);
+
+            let results = compare_strategies(&labyrinth, start, facing);
+            assert!(
+                matches!(
+                    results[&solver::Strategy::Bfs],
+                    solver::SolveResult::Exit { .. }
+                ),
+                "{}x{}: {:?}",
+                width,
+                height,
+                results
+            );
+        }
+    }
+
+    #[test]
+    fn test_bfs_uses_no_more_moves_than_right_hand() {
+        for seed in 0..10 {
+            let labyrinth = generate_labyrinth(5, 5, seed, None, false);
+            assert_connected(&labyrinth);
+            let (start, facing) = spawn_point(0, None, (5, 5));
+            let results = compare_strategies(&labyrinth, start, facing);
+
+            // Generated labyrinths are perfect mazes, so the wall follower always gets out too
+            let (
+                solver::SolveResult::Exit { moves: bfs },
+                solver::SolveResult::Exit { moves: right_hand },
+            ) = (
+                results[&solver::Strategy::Bfs],
+                results[&solver::Strategy::RightHand],
+            )
+            else {
+                panic!("seed {}: {:?}", seed, results);
+            };
+            assert!(bfs <= right_hand, "seed {}: {} > {}", seed, bfs, right_hand);
+        }
+
+        let labyrinth = generate_labyrinth(5, 5, 42, None, false);
+        let table = comparison_table(&compare_strategies(&labyrinth, (3, 4), MapDirection::North));
+        assert!(table.starts_with("Strategy"));
+        assert_eq!(table.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_line_of_sight_hides_cells_behind_walls() {
+        // The center (1, 1) has a wall to the north, (0, 1) a wall to the north and
+        // (1, 0) a wall to the west: the top-left corner cannot be reached within the view
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1011", "1001", "1100"],
+                &["1001", "1000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (2, 2),
+        );
+
+        let cells = radar_cell_values((1, 1), MapDirection::North, &labyrinth, true, None, None);
+        assert_eq!(cells[0], 0xF);
+        // Seen through the open east side, then north and west
+        assert_eq!(cells[1], 0b0000);
+        assert_eq!(cells[2], 0b0000);
+        // The exit in the bottom-right corner is visible
+        assert_eq!(cells[8], 0b1000);
+
+        let cells = radar_cell_values((1, 1), MapDirection::North, &labyrinth, false, None, None);
+        assert_eq!(cells[0], 0b0000);
+    }
+
+    #[test]
+    fn test_moves_to_exit_distribution() {
+        use stats::MovesToExit;
+        assert_eq!(MovesToExit::from_moves([]), None);
+
+        let state = new_arena_state(5, 5, ServerConfig::default());
+        {
+            let mut state = state.lock().unwrap();
+            let finishes = [12, 4, 30, 8, 6, 20, 10, 16, 14, 40];
+            for (i, moves) in finishes.into_iter().enumerate() {
+                let player_key = format!("Team 1/Player {}", i);
+                state.stats.record_exit(&player_key, moves);
+            }
+        }
+
+        let moves_to_exit = MovesToExit::from_moves(state.lock().unwrap().stats.exit_moves());
+        assert_eq!(
+            moves_to_exit,
+            Some(MovesToExit {
+                average: 16.0,
+                p50: 12,
+                p90: 30,
+            })
+        );
+
+        let status = status::status_json(&[state], std::time::Duration::from_secs(1));
+        assert_eq!(status["avg_moves_to_exit"], 16.0);
+        assert_eq!(status["p50_moves_to_exit"], 12);
+        assert_eq!(status["p90_moves_to_exit"], 30);
+    }
+
+    #[test]
+    fn test_map_direction_serializes_as_string() {
+        assert_eq!(
+            serde_json::to_string(&MapDirection::East).unwrap(),
+            "\"East\""
+        );
+        assert_eq!(
+            serde_json::from_str::<MapDirection>("\"West\"").unwrap(),
+            MapDirection::West
+        );
+
+        let response = wire::RadarViewResponse {
+            radar_view: "ieysGjGO8papd/a".to_string(),
+            seq: None,
+            facing: Some(MapDirection::North),
+            orientation: None,
+            pushed: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"RadarView":"ieysGjGO8papd/a","facing":"North"}"#
+        );
+    }
+
+    #[test]
+    fn test_teleport_to_a_corner() {
+        init_test_logging();
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1100"],
+                &["0001", "0000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (2, 2),
+        );
+        let mut state = state_with_player(labyrinth, (1, 1), MapDirection::East);
+        let teleport = |x, y| Teleport {
+            x,
+            y,
+            facing: MapDirection::North,
+        };
+
+        // Refused without --allow-cheats, or outside the labyrinth
+        let north = MapDirection::North;
+        assert!(state.teleport("Team 1/Nino", (0, 0), north).is_err());
+        state.config.allow_cheats = true;
+        assert!(state.teleport("Team 1/Nino", (-1, 0), north).is_err());
+        assert!(state.teleport("Team 1/Nino", (0, 3), north).is_err());
+        assert_eq!(state.players["Team 1/Nino"].position, (1, 1));
+
+        let state = Arc::new(Mutex::new(state));
+        let mut sink: Vec<u8> = Vec::new();
+        let player_key = Some("Team 1/Nino".to_string());
+        handle_teleport(&mut sink, &teleport(0, 0), Arc::clone(&state), player_key).unwrap();
+
+        let response: serde_json::Value =
+            serde_json::from_str(&receive_message(&mut std::io::Cursor::new(&sink)).unwrap())
+                .unwrap();
+        let state = state.lock().unwrap();
+        assert_eq!(state.players["Team 1/Nino"].position, (0, 0));
+        assert_eq!(response["facing"], "North");
+
+        // The radar is the one of the corner: walls to the north and west, open to the east
+        let data = encoder::decode(response["RadarView"].as_str().unwrap()).unwrap();
+        let corner = radar_view_bytes(
+            (0, 0),
+            MapDirection::North,
+            &state.labyrinth,
+            false,
+            None,
+            None,
+        );
+        assert_eq!(data, corner);
+        let horizontal = passage_codes(&data[0..3]);
+        let vertical = passage_codes(&data[3..6]);
+        assert_eq!(horizontal[FRONT_PASSAGE], Passage::Wall as u8);
+        assert_eq!(vertical[LEFT_PASSAGE], Passage::Wall as u8);
+        assert_eq!(vertical[RIGHT_PASSAGE], Passage::Open as u8);
+    }
+
+    #[test]
+    fn test_strict_exit_requires_facing_the_exit_passage() {
+        // Dead end exit, open to the west only
+        let labyrinth = labyrinth_from_masks(&[&["1011", "1010", "1110"]], (2, 0));
+        assert_eq!(labyrinth.exit_passage(), Some(MapDirection::West));
+
+        // Walking in faces the east wall: no win, but the radar view is still sent
+        let mut state = state_with_player(labyrinth.clone(), (0, 0), MapDirection::East);
+        state.config.strict_exit = true;
+        state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        assert_eq!(outcome.new_position, (2, 0));
+        assert!(!outcome.found_exit);
+        assert!(!outcome.encoded_view.is_empty());
+
+        // Backing in keeps facing west, toward the open passage
+        let mut state = state_with_player(labyrinth, (0, 0), MapDirection::West);
+        state.config.strict_exit = true;
+        state.apply_move("Team 1/Nino", &Direction::Back).unwrap();
+        let outcome = state.apply_move("Team 1/Nino", &Direction::Back).unwrap();
+        assert_eq!(outcome.new_position, (2, 0));
+        assert!(outcome.found_exit);
+    }
+
+    #[test]
+    fn test_generated_labyrinths_are_connected() {
+        for seed in 0..20 {
+            assert_connected(&generate_labyrinth(9, 7, seed, None, false));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cell (1, 0) cannot be reached from (0, 0)")]
+    fn test_assert_connected_reports_walled_off_cell() {
+        // Second column walled off from the first
+        let labyrinth = labyrinth_from_masks(&[&["1100", "1011"], &["0110", "1011"]], (1, 1));
+        assert_connected(&labyrinth);
+    }
+
+    #[test]
+    fn test_mark_visited_encodes_cells_already_stood_on() {
+        let labyrinth = labyrinth_from_masks(&[&["1001", "1000", "1100"]], (2, 0));
+        let mut state = state_with_player(labyrinth, (0, 0), MapDirection::East);
+        state.config.mark_visited = true;
+
+        state.apply_move("Team 1/Nino", &Direction::Front).unwrap();
+        let player = &state.players["Team 1/Nino"];
+        let cells = radar_cell_values(
+            player.position,
+            MapDirection::North,
+            &state.labyrinth,
+            false,
+            Some(&player.visited),
+            None,
+        );
+        // West of the player is where they started, east is the exit
+        assert_eq!(cells[3], 0b1101);
+        assert_eq!(cells[4], 0b0000);
+        assert_eq!(cells[5], 0b1000);
+
+        state.config.mark_visited = false;
+        let player = &state.players["Team 1/Nino"];
+        let unmarked = encode_player_radar_view(&state, player);
+        state.config.mark_visited = true;
+        let player = &state.players["Team 1/Nino"];
+        assert_ne!(encode_player_radar_view(&state, player), unmarked);
+    }
+
+    #[test]
+    fn test_compass_angle_to_exit_for_each_facing() {
+        // Exit two cells north, east, south and west of (2, 2)
+        let exits = [(2, 0), (4, 2), (2, 4), (0, 2)];
+        let facings = [
+            MapDirection::North,
+            MapDirection::East,
+            MapDirection::South,
+            MapDirection::West,
+        ];
+        for (turns, facing) in facings.into_iter().enumerate() {
+            for (side, exit) in exits.into_iter().enumerate() {
+                let expected = ((side + 4 - turns) % 4) as f64 * 90.0;
+                assert_eq!(
+                    compass_angle_to_exit((2, 2), facing, exit),
+                    expected,
+                    "exit at {:?} facing {:?}",
+                    exit,
+                    facing
+                );
+            }
+        }
+        // Straight ahead, to the right, behind and to the left when facing north
+        assert_eq!(
+            compass_angle_to_exit((2, 2), MapDirection::North, (2, 0)),
+            0.0
+        );
+        assert_eq!(
+            compass_angle_to_exit((2, 2), MapDirection::North, (4, 2)),
+            90.0
+        );
+        assert_eq!(
+            compass_angle_to_exit((2, 2), MapDirection::North, (2, 4)),
+            180.0
+        );
+        assert_eq!(
+            compass_angle_to_exit((2, 2), MapDirection::North, (0, 2)),
+            270.0
+        );
+    }
+
+    #[test]
+    fn test_compass_noise_depends_on_distance_to_exit() {
+        // East-west corridor, exit at the east end, 4 moves from the west end
+        let labyrinth = labyrinth_from_masks(&[&["1011", "1010", "1010", "1010", "1110"]], (4, 0));
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // Without a noise model the compass is exact
+        for _ in 0..10 {
+            let angle = compass_angle(&labyrinth, (0, 0), MapDirection::North, None, &mut rng);
+            assert_eq!(angle, 90.0);
+        }
+        let deviations = |model, rng: &mut StdRng| {
+            (0..50)
+                .map(|_| {
+                    let angle = compass_angle(&labyrinth, (0, 0), MapDirection::North, model, rng);
+                    assert!((0.0..360.0).contains(&angle));
+                    (angle - 90.0).abs()
+                })
+                .collect::<Vec<f64>>()
+        };
+
+        // 4 moves away: up to 90 / 5 = 18 degrees near, 10 * 3 = 30 degrees far
+        let near = deviations(Some(CompassNoiseModel::Near), &mut rng);
+        let far = deviations(Some(CompassNoiseModel::Far), &mut rng);
+        assert!(near.iter().all(|&d| d <= 18.0));
+        assert!(far.iter().all(|&d| d <= 30.0));
+        assert!(far.iter().any(|&d| d > 18.0));
+
+        // Next to the exit the far model is exact
+        let angle = compass_angle(
+            &labyrinth,
+            (3, 0),
+            MapDirection::West,
+            Some(CompassNoiseModel::Far),
+            &mut rng,
+        );
+        assert_eq!(angle, 180.0);
+    }
+
+    #[test]
+    fn test_absolute_radar_views_do_not_depend_on_the_facing() {
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1010", "1100"],
+                &["0011", "1010", "0110"],
+                &["1011", "1010", "1110"],
+            ],
+            (2, 2),
+        );
+        let mut state = ServerState::new(labyrinth, ServerConfig::default());
+        state.teams.insert("Team 1".to_string(), empty_team(3));
+        let nino = state.add_player("Team 1", "Nino").unwrap();
+        let paul = state.add_player("Team 1", "Paul").unwrap();
+        for (key, direction) in [(&nino, MapDirection::North), (&paul, MapDirection::East)] {
+            let player = state.players.get_mut(key).unwrap();
+            player.position = (1, 1);
+            player.direction = direction;
+        }
+        let views = |state: &ServerState| {
+            (
+                encode_player_radar_view(state, &state.players[&nino]),
+                encode_player_radar_view(state, &state.players[&paul]),
+            )
+        };
+
+        let (north, east) = views(&state);
+        assert_ne!(north, east);
+
+        state.config.radar_orientation = Some(RadarOrientation::Absolute);
+        let (north_up, east_up) = views(&state);
+        assert_eq!(north_up, east_up);
+        assert_eq!(north_up, north);
+    }
+
+    #[test]
+    fn test_get_open_directions_matches_walls() {
+        init_test_logging();
+        // Middle cell of an east-west corridor: walls to the north and south
+        let labyrinth = labyrinth_from_masks(&[&["1001", "1010", "1110"]], (2, 0));
+        let state = Arc::new(Mutex::new(state_with_player(
+            labyrinth,
+            (1, 0),
+            MapDirection::East,
+        )));
+
+        let mut sink: Vec<u8> = Vec::new();
+        handle_action(
+            &mut sink,
+            &Action::GetOpenDirections,
+            None,
+            Arc::clone(&state),
+            "127.0.0.1:1".parse().unwrap(),
+            Some("Team 1/Nino".to_string()),
+        )
+        .unwrap();
+
+        let response: serde_json::Value =
+            serde_json::from_str(&receive_message(&mut std::io::Cursor::new(&sink)).unwrap())
+                .unwrap();
+        assert_eq!(
+            response,
+            serde_json::json!({
+                "OpenDirections": {"front": true, "right": false, "back": true, "left": false}
+            })
+        );
+        let state = state.lock().unwrap();
+        assert_eq!(state.players["Team 1/Nino"].moves, 0);
+
+        // Facing south, the corridor is on the sides
+        assert_eq!(
+            open_directions(&state.labyrinth, (1, 0), MapDirection::South),
+            wire::OpenDirections {
+                front: false,
+                right: true,
+                back: false,
+                left: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_shared_writer_never_interleaves_frames() {
+        init_test_logging();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_side = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut client_side, _) = listener.accept().unwrap();
+        let writer = SharedWriter::new(server_side);
+
+        // Two threads write long frames to the same connection at once
+        let senders: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|letter| {
+                let mut writer = writer.clone();
+                thread::spawn(move || {
+                    let message = json!({ "Secret": letter.repeat(100_000) });
+                    for _ in 0..20 {
+                        send_message(&mut writer, &message).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for _ in 0..40 {
+            let received = receive_message(&mut client_side).unwrap();
+            let secret: serde_json::Value = serde_json::from_str(&received).unwrap();
+            let secret = secret["Secret"].as_str().unwrap();
+            assert!(secret == "a".repeat(100_000) || secret == "b".repeat(100_000));
+        }
+        for sender in senders {
+            sender.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_toggle_wall_pushes_a_tagged_radar_view() {
+        init_test_logging();
+        let mut state = ServerState::new(
+            labyrinth_from_masks(&[&["1001", "1010", "1110"]], (2, 0)),
+            ServerConfig::default(),
+        );
+        state.config.allow_cheats = true;
+        state.teams.insert("Team 1".to_string(), empty_team(1));
+        let nino = state.add_player("Team 1", "Nino").unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_side = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut client_side, _) = listener.accept().unwrap();
+        state
+            .player_streams
+            .insert(nino, SharedWriter::new(server_side));
+        let state = Arc::new(Mutex::new(state));
+
+        let message = ToggleWall {
+            x: 0,
+            y: 0,
+            side: MapDirection::East,
+        };
+        let mut answer = Vec::new();
+        handle_toggle_wall(&mut answer, &message, Arc::clone(&state)).unwrap();
+        assert_eq!(
+            receive_message(&mut answer.as_slice()).unwrap(),
+            r#"{"ToggleWallResult":"Ok"}"#
+        );
+
+        let pushed: serde_json::Value =
+            serde_json::from_str(&receive_message(&mut client_side).unwrap()).unwrap();
+        assert!(pushed.get("RadarView").is_some());
+        assert_eq!(pushed["pushed"], true);
+        assert!(pushed.get("seq").is_none());
+    }
+
+    #[test]
+    fn test_refused_toggle_wall_is_answered_with_its_reason() {
+        init_test_logging();
+        let state = Arc::new(Mutex::new(ServerState::new(
+            labyrinth_from_masks(&[&["1001", "1010", "1110"]], (2, 0)),
+            ServerConfig::default(),
+        )));
+        let toggle = |x: usize, side: MapDirection| {
+            let mut answer = Vec::new();
+            let message = ToggleWall { x, y: 0, side };
+            handle_toggle_wall(&mut answer, &message, Arc::clone(&state)).unwrap();
+            receive_message(&mut answer.as_slice()).unwrap()
+        };
+
+        assert_eq!(
+            toggle(0, MapDirection::East),
+            r#"{"ToggleWallResult":{"Error":"The server was not started with --allow-cheats"}}"#
+        );
+        lock_state(&state).config.allow_cheats = true;
+        assert_eq!(
+            toggle(0, MapDirection::North),
+            r#"{"ToggleWallResult":{"Error":"No inner wall on the North side of (0, 0)"}}"#
+        );
+        assert_eq!(
+            toggle(0, MapDirection::East),
+            r#"{"ToggleWallResult":"Ok"}"#
+        );
+    }
+
+    #[test]
+    fn test_share_secret_is_relayed_to_teammates_only() {
+        init_test_logging();
+        let mut state = ServerState::new(
+            labyrinth_from_masks(&[&["1001", "1010", "1110"]], (2, 0)),
+            ServerConfig::default(),
+        );
+        state.teams.insert("Team 1".to_string(), empty_team(3));
+        state.teams.insert("Team 2".to_string(), empty_team(3));
+        let nino = state.add_player("Team 1", "Nino").unwrap();
+        let paul = state.add_player("Team 1", "Paul").unwrap();
+        let lea = state.add_player("Team 2", "Lea").unwrap();
+
+        // Connection of each player, the server side being kept by the state
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut clients = HashMap::new();
+        for key in [&nino, &paul, &lea] {
+            let server_side = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            let (client_side, _) = listener.accept().unwrap();
+            client_side.set_nonblocking(true).unwrap();
+            state
+                .player_streams
+                .insert(key.clone(), SharedWriter::new(server_side));
+            clients.insert(key.clone(), client_side);
+        }
+        let state = Arc::new(Mutex::new(state));
+
+        let message = ShareSecret {
+            player: "Someone else".to_string(),
+            secret: 42,
+        };
+        handle_share_secret(&message, Arc::clone(&state), Some(nino.clone())).unwrap();
+
+        let paul_stream = clients.get_mut(&paul).unwrap();
+        paul_stream.set_nonblocking(false).unwrap();
+        assert_eq!(
+            receive_message(paul_stream).unwrap(),
+            r#"{"ShareSecret":{"player":"Nino","secret":42}}"#
+        );
+        for key in [&nino, &lea] {
+            let mut buffer = [0; 1];
+            let error = clients.get_mut(key).unwrap().read(&mut buffer).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::WouldBlock);
+        }
+    }
+
+    #[test]
+    fn test_player_perspective_reveals_only_the_radius() {
+        let labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1000", "1100"],
+                &["0001", "0000", "0000", "0100"],
+                &["0001", "0000", "0000", "0100"],
+                &["0011", "0010", "0010", "0110"],
+            ],
+            (3, 3),
+        );
+        let state = state_with_player(labyrinth, (1, 1), MapDirection::North);
+        let key = "Team 1/Nino";
+
+        let map = render_from_player_perspective(&state, key, 1).unwrap();
+        // Cell contents are at columns 4, 8, 12, 16 of the lines 2, 4, 6, 8
+        let lines: Vec<&str> = map.lines().collect();
+        let content = |x: usize, y: usize| lines[2 + 2 * y].chars().nth(4 + 4 * x).unwrap();
+        for y in 0..4 {
+            for x in 0..4 {
+                if x <= 2 && y <= 2 {
+                    assert_ne!(content(x, y), '?', "({}, {}) should be revealed", x, y);
+                } else {
+                    assert_eq!(content(x, y), '?', "({}, {}) should be fogged", x, y);
+                }
+            }
+        }
+        assert_eq!(content(1, 1), '^');
+
+        // The whole labyrinth, exit included, once the radius covers it
+        let map = render_from_player_perspective(&state, key, 3).unwrap();
+        assert!(!map.contains('?'));
+        assert!(map.contains('X'));
+        assert!(render_from_player_perspective(&state, "Team 1/Nobody", 1).is_none());
+    }
+
+    #[test]
+    fn test_endless_mode_moves_the_exit_and_counts_solves() {
+        init_test_logging();
+        // East-west corridor, the player one step west of the exit
+        let labyrinth = labyrinth_from_masks(&[&["1011", "1010", "1010", "1110"]], (1, 0));
+        let mut state = state_with_player(labyrinth, (0, 0), MapDirection::East);
+        state.config.endless = true;
+        let state = Arc::new(Mutex::new(state));
+
+        let mut sink: Vec<u8> = Vec::new();
+        handle_action(
+            &mut sink,
+            &Action::MoveTo(Direction::Front),
+            None,
+            Arc::clone(&state),
+            "127.0.0.1:1".parse().unwrap(),
+            Some("Team 1/Nino".to_string()),
+        )
+        .unwrap();
+
+        let mut responses = std::io::Cursor::new(&sink);
+        let found_exit = receive_message(&mut responses).unwrap();
+        assert_eq!(found_exit, r#"{"FoundExit":true}"#);
+        let radar: serde_json::Value =
+            serde_json::from_str(&receive_message(&mut responses).unwrap()).unwrap();
+
+        // The exit went to the far end of the corridor, and the radar view was encoded after
+        let state = state.lock().unwrap();
+        assert_eq!(state.labyrinth.exit_position, (3, 0));
+        assert!(state.labyrinth.cells[0][3].has_exit);
+        assert!(!state.labyrinth.cells[0][1].has_exit);
+        assert_eq!(
+            radar["RadarView"],
+            encode_player_radar_view(&state, &state.players["Team 1/Nino"])
+        );
+        assert_eq!(state.players["Team 1/Nino"].solves, 1);
+    }
+
+    #[test]
+    fn test_radar_encodes_item_and_entity_bits_together() {
+        // 3x3 open room with a hint east of the center
+        let mut labyrinth = labyrinth_from_masks(
+            &[
+                &["1001", "1000", "1100"],
+                &["0001", "0000", "0100"],
+                &["0011", "0010", "0110"],
+            ],
+            (0, 0),
+        );
+        labyrinth.cells[1][2].has_hint = true;
+        let mut state = state_with_player(labyrinth, (1, 1), MapDirection::North);
+        state.teams.insert("Team 1".to_string(), empty_team(3));
+        state.teams.insert("Team 2".to_string(), empty_team(3));
+        let lea = state.add_player("Team 1", "Lea").unwrap();
+        let paul = state.add_player("Team 2", "Paul").unwrap();
+        state.players.get_mut(&lea).unwrap().position = (2, 1);
+        state.players.get_mut(&paul).unwrap().position = (1, 2);
+
+        let nino = &state.players["Team 1/Nino"];
+        let entities = player_entities(&state, nino);
+        let cells = radar_cell_values(
+            (1, 1),
+            MapDirection::North,
+            &state.labyrinth,
+            false,
+            None,
+            Some(&entities),
+        );
+
+        // Hint (item 0b01) with an ally (entity 0b01) on the east cell
+        assert_eq!(cells[5], 0b0101);
+        assert_eq!((cells[5] >> 2, cells[5] & 0b11), (0b01, 0b01));
+        // An enemy south, the exit with nobody on it in the corner, the player not shown
+        assert_eq!(cells[7], 0b0010);
+        assert_eq!(cells[0], 0b1000);
+        assert_eq!(cells[4], 0b0000);
+    }
+
+    #[test]
+    fn test_warmup_reports_positive_throughput() {
+        init_test_logging();
+        let state = new_arena_state(5, 5, ServerConfig::default());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_arena(listener, state));
+
+        let report = run_warmup(address, 2, Duration::from_millis(300)).unwrap();
+
+        assert_eq!(report.clients, 2);
+        assert!(report.moves > 0);
+        assert!(report.moves_per_sec() > 0.0);
+        assert!(report.p99_latency > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_player_hints_do_not_depend_on_other_players() {
+        let config = ServerConfig {
+            seed: Some(42),
+            ..ServerConfig::default()
+        };
+        let first_hint_angle = |other_player_first: bool| {
+            let state = new_arena_state(5, 5, config.clone());
+            let mut state = state.lock().unwrap();
+            state.teams.insert("Team 1".to_string(), empty_team(3));
+            let nino = state.add_player("Team 1", "Nino").unwrap();
+            let paul = state.add_player("Team 1", "Paul").unwrap();
+            let state = &mut *state;
+            let mut angle = |key: &str| {
+                let player = state.players.get_mut(key).unwrap();
+                compass_angle(
+                    &state.labyrinth,
+                    player.position,
+                    player.direction,
+                    Some(CompassNoiseModel::Far),
+                    &mut player.rng,
+                )
+            };
+            if other_player_first {
+                angle(&paul);
+            }
+            angle(&nino)
+        };
+
+        assert_eq!(first_hint_angle(false), first_hint_angle(false));
+        assert_eq!(first_hint_angle(false), first_hint_angle(true));
+    }
+
+    #[test]
+    fn test_requested_team_size_is_clamped_to_the_server_max() {
+        init_test_logging();
+        let config = ServerConfig {
+            max_team_size: Some(3),
+            ..ServerConfig::default()
+        };
+        let state = Arc::new(Mutex::new(ServerState::new(
+            labyrinth_from_masks(&[&["1111"]], (0, 0)),
+            config,
+        )));
+        let message = RegisterTeam {
+            name: "Team 1".to_string(),
+            expected_players: Some(5),
+            protocol_version: None,
+        };
+
+        let mut sink: Vec<u8> = Vec::new();
+        handle_register_team(&mut sink, &message, Arc::clone(&state)).unwrap();
+        let response: serde_json::Value =
+            serde_json::from_str(&receive_message(&mut std::io::Cursor::new(&sink)).unwrap())
+                .unwrap();
+        assert_eq!(response["RegisterTeamResult"]["Ok"]["expected_players"], 3);
+        assert_eq!(state.lock().unwrap().teams["Team 1"].expected_players, 3);
+
+        // Smaller teams are granted as asked
+        let mut state = state.lock().unwrap();
+        assert_eq!(state.register_team("Team 2", Some(2)).unwrap().1, 2);
+        assert_eq!(state.register_team("Team 3", Some(0)).unwrap().1, 1);
+        assert_eq!(state.register_team("Team 4", None).unwrap().1, 3);
+    }
+
+    #[test]
+    fn test_view_radius_is_clamped_to_the_maze() {
+        let (radius, warning) = fit_view_radius(2, 3, 3);
+        assert_eq!(radius, 1);
+        assert_eq!(
+            warning.as_deref(),
+            Some("radius 2 needs a maze of at least 5x5 cells, the maze is 3x3: using radius 1")
+        );
+
+        assert_eq!(fit_view_radius(2, 5, 7), (2, None));
+        assert_eq!(fit_view_radius(RADAR_RADIUS, 3, 3), (1, None));
+        assert!(fit_view_radius(RADAR_RADIUS, 2, 8).1.is_some());
     }
 }
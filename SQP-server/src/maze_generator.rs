@@ -1,5 +1,9 @@
+use log::warn;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct Cell {
@@ -31,8 +35,38 @@ pub struct Maze {
     pub height: usize,
     pub cells: Vec<Vec<Cell>>,
     pub exit_position: (usize, usize),
+    /// Cell the generation started from, the exit is the farthest cell from it
+    pub start_position: (usize, usize),
+    /// Number of moves from the start position to the exit
+    pub exit_distance: usize,
+    /// Time spent in each phase of the generation, only measured when profiling (--profile)
+    pub profile: Option<MazeProfile>,
 }
 
+/// Duration of each phase of a maze generation.
+#[derive(Clone, Copy, Debug)]
+pub struct MazeProfile {
+    /// Carving the passages, the recursive backtracking itself
+    pub generate_maze: Duration,
+    pub find_farthest_point: Duration,
+    pub place_hints: Duration,
+}
+
+impl fmt::Display for MazeProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "generate_maze {:.3} ms, find_farthest_point {:.3} ms, place_hints {:.3} ms",
+            self.generate_maze.as_secs_f64() * 1000.0,
+            self.find_farthest_point.as_secs_f64() * 1000.0,
+            self.place_hints.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Number of mazes generated at most to reach a minimum exit distance
+pub const MAX_GENERATION_ATTEMPTS: u64 = 20;
+
 // Directions used for maze generation
 #[derive(Clone, Copy, Debug)]
 enum Direction {
@@ -42,6 +76,14 @@ enum Direction {
     West,
 }
 
+/// The order neighbors are listed in, so that a seeded rng always picks the same one
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
 impl Direction {
     fn opposite(&self) -> Direction {
         match self {
@@ -55,15 +97,24 @@ impl Direction {
 
 /// Generate a maze using the Recursive Backtracking algorithm
 /// This ensures all cells are reachable and there are no isolated sections
-pub fn generate_maze(width: usize, height: usize) -> Maze {
-    let mut rng = rand::thread_rng();
+/// The same seed and dimensions always give the same maze
+/// A maze 1 cell wide or tall is a straight corridor from (0, 0), with the exit at the far end
+/// With `profile`, each phase of the generation is timed in `Maze::profile`; the clock is not
+/// read at all otherwise
+pub fn generate_maze(width: usize, height: usize, seed: u64, profile: bool) -> Maze {
+    let started = profile.then(Instant::now);
+    let mut rng = StdRng::seed_from_u64(seed);
 
     // Initialize cells with all walls
     let mut cells = vec![vec![Cell::new(); width]; height];
 
-    // Start at a random position
-    let start_x = rng.gen_range(0..width);
-    let start_y = rng.gen_range(0..height);
+    // Start at a random position, or at one end of a corridor maze (1 cell wide or tall):
+    // the only way to carve it is then a straight line, with the exit at the other end
+    let (start_x, start_y) = if is_corridor(width, height) {
+        (0, 0)
+    } else {
+        (rng.gen_range(0..width), rng.gen_range(0..height))
+    };
 
     // Stack for recursive backtracking
     let mut stack = Vec::new();
@@ -73,31 +124,11 @@ pub fn generate_maze(width: usize, height: usize) -> Maze {
     while !stack.is_empty() {
         let (current_x, current_y) = *stack.last().unwrap();
 
-        let mut neighbors = Vec::new();
-
-        // Check North
-        if current_y > 0 && !cells[current_y - 1][current_x].visited {
-            neighbors.push((current_x, current_y - 1, Direction::North));
-        }
-
-        // Check East
-        if current_x < width - 1 && !cells[current_y][current_x + 1].visited {
-            neighbors.push((current_x + 1, current_y, Direction::East));
-        }
-
-        // Check South
-        if current_y < height - 1 && !cells[current_y + 1][current_x].visited {
-            neighbors.push((current_x, current_y + 1, Direction::South));
-        }
-
-        // Check West
-        if current_x > 0 && !cells[current_y][current_x - 1].visited {
-            neighbors.push((current_x - 1, current_y, Direction::West));
-        }
+        let neighbors = unvisited_neighbors(&cells, current_x, current_y, width, height);
 
         if !neighbors.is_empty() {
             // Choose a random unvisited neighbor
-            let (next_x, next_y, direction) = neighbors.choose(&mut rng).unwrap().clone();
+            let (next_x, next_y, direction) = *neighbors.choose(&mut rng).unwrap();
 
             // Remove the wall between current cell and chosen cell
             match direction {
@@ -129,11 +160,22 @@ pub fn generate_maze(width: usize, height: usize) -> Maze {
     }
 
     // Place the exit at a position far from the start
-    let (exit_x, exit_y) = find_farthest_point(&cells, start_x, start_y, width, height);
+    let carved = profile.then(Instant::now);
+    let ((exit_x, exit_y), exit_distance) =
+        find_farthest_point(&cells, start_x, start_y, width, height);
     cells[exit_y][exit_x].has_exit = true;
 
     // Place hints
-    place_hints(&mut cells, width, height, (exit_x, exit_y));
+    let exit_placed = profile.then(Instant::now);
+    place_hints(&mut cells, width, height, (exit_x, exit_y), &mut rng);
+    let profile = started
+        .zip(carved)
+        .zip(exit_placed)
+        .map(|((started, carved), exit_placed)| MazeProfile {
+            generate_maze: carved - started,
+            find_farthest_point: exit_placed - carved,
+            place_hints: exit_placed.elapsed(),
+        });
 
     // Remove the 'visited' flag for all cells
     for row in &mut cells {
@@ -147,17 +189,79 @@ pub fn generate_maze(width: usize, height: usize) -> Maze {
         height,
         cells,
         exit_position: (exit_x, exit_y),
+        start_position: (start_x, start_y),
+        exit_distance,
+        profile,
+    }
+}
+
+/// Whether a maze of these dimensions is a single line of cells
+fn is_corridor(width: usize, height: usize) -> bool {
+    width == 1 || height == 1
+}
+
+/// Generate mazes until the exit is at least `min_exit_distance` moves away from the start
+/// The attempts use the seeds following `seed`, so the same seed still gives the same maze
+/// When no attempt is long enough, the maze with the farthest exit is kept
+/// With `profile`, the maze kept carries the timings of its own generation
+pub fn generate_maze_with_min_exit_distance(
+    width: usize,
+    height: usize,
+    seed: u64,
+    min_exit_distance: usize,
+    profile: bool,
+) -> Maze {
+    let mut best = generate_maze(width, height, seed, profile);
+    for attempt in 1..MAX_GENERATION_ATTEMPTS {
+        if best.exit_distance >= min_exit_distance {
+            return best;
+        }
+        let maze = generate_maze(width, height, seed.wrapping_add(attempt), profile);
+        if maze.exit_distance > best.exit_distance {
+            best = maze;
+        }
     }
+
+    if best.exit_distance < min_exit_distance {
+        warn!(
+            "No {}x{} maze with an exit {} moves away after {} attempts, keeping one at {} moves",
+            width, height, min_exit_distance, MAX_GENERATION_ATTEMPTS, best.exit_distance
+        );
+    }
+    best
 }
 
-/// Find the point farthest from the start
+/// List the unvisited neighbors of a cell, in the fixed order of `DIRECTIONS`
+fn unvisited_neighbors(
+    cells: &[Vec<Cell>],
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> Vec<(usize, usize, Direction)> {
+    DIRECTIONS
+        .iter()
+        .filter_map(|&direction| {
+            let (next_x, next_y) = match direction {
+                Direction::North if y > 0 => (x, y - 1),
+                Direction::East if x < width - 1 => (x + 1, y),
+                Direction::South if y < height - 1 => (x, y + 1),
+                Direction::West if x > 0 => (x - 1, y),
+                _ => return None,
+            };
+            (!cells[next_y][next_x].visited).then_some((next_x, next_y, direction))
+        })
+        .collect()
+}
+
+/// Find the point farthest from the start, with its distance
 fn find_farthest_point(
     cells: &Vec<Vec<Cell>>,
     start_x: usize,
     start_y: usize,
     width: usize,
     height: usize,
-) -> (usize, usize) {
+) -> ((usize, usize), usize) {
     let mut distances = vec![vec![None; width]; height];
     let mut queue = std::collections::VecDeque::new();
 
@@ -200,28 +304,189 @@ fn find_farthest_point(
         }
     }
 
-    farthest_point
+    (farthest_point, max_distance)
 }
 
 /// Place hints in the maze to guide players toward the exit
-fn place_hints(cells: &mut Vec<Vec<Cell>>, width: usize, height: usize, exit_pos: (usize, usize)) {
-    let mut rng = rand::thread_rng();
+fn place_hints(
+    cells: &mut [Vec<Cell>],
+    width: usize,
+    height: usize,
+    exit_pos: (usize, usize),
+    rng: &mut StdRng,
+) {
     let num_hints = (width.min(height) / 2).max(1);
 
-    for _ in 0..num_hints {
-        let mut hint_x;
-        let mut hint_y;
+    // Any cell but the exit, each one at most once
+    let mut candidates: Vec<(usize, usize)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&position| position != exit_pos)
+        .collect();
+    seeded_shuffle(&mut candidates, rng);
 
-        // Ensure we don't place a hint at the exit
-        loop {
-            hint_x = rng.gen_range(0..width);
-            hint_y = rng.gen_range(0..height);
+    for &(hint_x, hint_y) in candidates.iter().take(num_hints) {
+        cells[hint_y][hint_x].has_hint = true;
+    }
+}
 
-            if (hint_x != exit_pos.0 || hint_y != exit_pos.1) && !cells[hint_y][hint_x].has_hint {
-                break;
-            }
+/// Shuffles `items` in place with the generator's seeded rng, so that the same seed always
+/// gives the same order. Generator features picking several distinct cells go through it.
+pub(crate) fn seeded_shuffle<T>(items: &mut [T], rng: &mut StdRng) {
+    items.shuffle(rng);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn walls(maze: &Maze) -> Vec<Vec<[bool; 4]>> {
+        maze.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        [
+                            cell.north_wall,
+                            cell.east_wall,
+                            cell.south_wall,
+                            cell.west_wall,
+                        ]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unvisited_neighbors_are_listed_north_east_south_west() {
+        let mut cells = vec![vec![Cell::new(); 3]; 3];
+        let directions = |cells: &[Vec<Cell>]| -> Vec<String> {
+            unvisited_neighbors(cells, 1, 1, 3, 3)
+                .iter()
+                .map(|(_, _, direction)| format!("{:?}", direction))
+                .collect()
+        };
+        assert_eq!(directions(&cells), ["North", "East", "South", "West"]);
+
+        cells[1][2].visited = true;
+        assert_eq!(directions(&cells), ["North", "South", "West"]);
+        assert_eq!(
+            unvisited_neighbors(&cells, 0, 0, 3, 3)
+                .iter()
+                .map(|&(x, y, _)| (x, y))
+                .collect::<Vec<_>>(),
+            [(1, 0), (0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_walls() {
+        for seed in [0, 42, 1234] {
+            let first = generate_maze(8, 6, seed, false);
+            let second = generate_maze(8, 6, seed, false);
+            assert_eq!(walls(&first), walls(&second), "seed {}", seed);
+            assert_eq!(first.exit_position, second.exit_position);
         }
+    }
 
-        cells[hint_y][hint_x].has_hint = true;
+    #[test]
+    fn test_corridor_mazes_are_straight_lines_to_the_far_end() {
+        let maze = generate_maze(1, 5, 7, false);
+        assert_eq!(maze.start_position, (0, 0));
+        assert_eq!(maze.exit_position, (0, 4));
+        assert_eq!(maze.exit_distance, 4);
+        assert!(maze.cells[4][0].has_exit);
+        for (y, row) in maze.cells.iter().enumerate() {
+            let cell = &row[0];
+            assert!(cell.east_wall && cell.west_wall);
+            assert_eq!(cell.north_wall, y == 0, "north wall of row {}", y);
+            assert_eq!(cell.south_wall, y == 4, "south wall of row {}", y);
+        }
+
+        let maze = generate_maze(6, 1, 7, false);
+        assert_eq!(maze.exit_position, (5, 0));
+        assert_eq!(maze.exit_distance, 5);
+        for (x, cell) in maze.cells[0].iter().enumerate() {
+            assert!(cell.north_wall && cell.south_wall);
+            assert_eq!(cell.west_wall, x == 0, "west wall of column {}", x);
+            assert_eq!(cell.east_wall, x == 5, "east wall of column {}", x);
+        }
+
+        // A single cell is its own exit
+        let maze = generate_maze(1, 1, 7, false);
+        assert_eq!(maze.exit_position, (0, 0));
+        assert_eq!(maze.exit_distance, 0);
+    }
+
+    #[test]
+    fn test_min_exit_distance_is_met() {
+        for seed in 0..10 {
+            let maze = generate_maze_with_min_exit_distance(6, 6, seed, 20, false);
+            assert!(
+                maze.exit_distance >= 20,
+                "seed {}: {}",
+                seed,
+                maze.exit_distance
+            );
+
+            let (start_x, start_y) = maze.start_position;
+            let (exit, distance) = find_farthest_point(&maze.cells, start_x, start_y, 6, 6);
+            assert_eq!(exit, maze.exit_position);
+            assert_eq!(distance, maze.exit_distance);
+        }
+
+        // Out of reach in a 3x3 maze: the farthest exit found is kept
+        let maze = generate_maze_with_min_exit_distance(3, 3, 7, 100, false);
+        assert!(maze.exit_distance <= 8);
+        assert!(maze.exit_distance >= generate_maze(3, 3, 7, false).exit_distance);
+    }
+
+    #[test]
+    fn test_seeded_shuffle_is_reproducible() {
+        let shuffled = |seed: u64| {
+            let mut items: Vec<usize> = (0..20).collect();
+            seeded_shuffle(&mut items, &mut StdRng::seed_from_u64(seed));
+            items
+        };
+
+        assert_eq!(shuffled(7), shuffled(7));
+        assert_ne!(shuffled(7), shuffled(8));
+        let mut sorted = shuffled(7);
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_hints_are_distinct_and_never_on_the_exit() {
+        let maze = generate_maze(6, 4, 3, false);
+        let hints: Vec<(usize, usize)> = (0..4)
+            .flat_map(|y| (0..6).map(move |x| (x, y)))
+            .filter(|&(x, y)| maze.cells[y][x].has_hint)
+            .collect();
+        assert_eq!(hints.len(), 2);
+        assert!(!hints.contains(&maze.exit_position));
+    }
+
+    #[test]
+    fn test_profile_records_each_generation_phase() {
+        assert!(generate_maze(6, 4, 3, false).profile.is_none());
+
+        let maze = generate_maze(30, 30, 3, true);
+        let profile = maze.profile.expect("profiling was on");
+        let phases = [
+            profile.generate_maze,
+            profile.find_farthest_point,
+            profile.place_hints,
+        ];
+        assert!(phases.iter().all(|phase| *phase > Duration::ZERO));
+        let report = profile.to_string();
+        for name in ["generate_maze", "find_farthest_point", "place_hints"] {
+            assert!(report.contains(name), "{}", report);
+        }
+
+        // Profiling does not change the maze
+        let plain = generate_maze(30, 30, 3, false);
+        assert_eq!(walls(&maze), walls(&plain));
+        assert_eq!(maze.exit_position, plain.exit_position);
     }
 }
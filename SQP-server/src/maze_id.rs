@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::ServerConfig;
+use crate::encoder::{decode, encode};
+
+/// Algorithm used to generate labyrinths, stored in maze ids so that an id made for
+/// another algorithm is refused instead of silently giving a different labyrinth.
+pub(crate) const MAZE_ALGORITHM: &str = "recursive-backtracking";
+
+/// Everything needed to recreate an arena: generator, dimensions, seed and game options.
+#[derive(Serialize, Deserialize)]
+struct MazeIdContent {
+    algorithm: String,
+    width: usize,
+    height: usize,
+    config: ServerConfig,
+}
+
+/// Builds the maze id of an arena: its settings as JSON, encoded with the radar base64 alphabet.
+/// The config must hold the seed the labyrinth was generated with.
+pub(crate) fn maze_id(width: usize, height: usize, config: &ServerConfig) -> String {
+    let content = MazeIdContent {
+        algorithm: MAZE_ALGORITHM.to_string(),
+        width,
+        height,
+        config: config.clone(),
+    };
+    encode(&serde_json::to_vec(&content).expect("Maze id settings are always serializable"))
+}
+
+/// Reads a maze id given with --maze-id back into the dimensions and config of the arena.
+pub(crate) fn parse_maze_id(id: &str) -> Result<(usize, usize, ServerConfig), String> {
+    let bytes = decode(id.trim()).map_err(|e| format!("Invalid maze id encoding: {:?}", e))?;
+    let content: MazeIdContent =
+        serde_json::from_slice(&bytes).map_err(|e| format!("Invalid maze id content: {}", e))?;
+
+    if content.algorithm != MAZE_ALGORITHM {
+        return Err(format!(
+            "Unsupported maze algorithm '{}', expected '{}'",
+            content.algorithm, MAZE_ALGORITHM
+        ));
+    }
+    if content.config.seed.is_none() {
+        return Err("Maze id has no seed".to_string());
+    }
+    if content.width == 0 || content.height == 0 {
+        return Err("Maze id has empty dimensions".to_string());
+    }
+
+    Ok((content.width, content.height, content.config))
+}
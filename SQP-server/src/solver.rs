@@ -0,0 +1,246 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::config::ServerConfig;
+use crate::wire::Direction;
+use crate::{player_rng, Labyrinth, MapDirection, Player, ServerState};
+
+/// Ways of looking for the exit that the comparison harness can run without a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum Strategy {
+    /// Wall follower: take the first open passage on the right, then front, left and back.
+    RightHand,
+    /// Shortest path found by a breadth-first search over the whole labyrinth,
+    /// the fewest moves any strategy can reach.
+    Bfs,
+}
+
+impl Strategy {
+    pub(crate) const ALL: [Strategy; 2] = [Strategy::RightHand, Strategy::Bfs];
+}
+
+/// How a strategy ended on a labyrinth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SolveResult {
+    /// The exit was reached after this many moves.
+    Exit { moves: usize },
+    /// The exit was not reached within the move limit (or cannot be reached).
+    Failed { moves: usize },
+}
+
+const SOLVER_KEY: &str = "Solver/Solver";
+
+/// Runs every strategy on its own copy of the labyrinth, from the same start and facing,
+/// moving through ServerState::apply_move exactly as a connected player would.
+pub(crate) fn compare_strategies(
+    labyrinth: &Labyrinth,
+    start: (usize, usize),
+    facing: MapDirection,
+) -> HashMap<Strategy, SolveResult> {
+    Strategy::ALL
+        .iter()
+        .map(|&strategy| (strategy, solve(labyrinth, start, facing, strategy)))
+        .collect()
+}
+
+/// Formats the results of compare_strategies as a table, one line per strategy.
+pub(crate) fn comparison_table(results: &HashMap<Strategy, SolveResult>) -> String {
+    let mut strategies: Vec<&Strategy> = results.keys().collect();
+    strategies.sort();
+
+    let mut table = format!("{:<10} {:<7} {:>6}\n", "Strategy", "Result", "Moves");
+    for strategy in strategies {
+        let (result, moves) = match results[strategy] {
+            SolveResult::Exit { moves } => ("exit", moves),
+            SolveResult::Failed { moves } => ("failed", moves),
+        };
+        table += &format!(
+            "{:<10} {:<7} {:>6}\n",
+            format!("{:?}", strategy),
+            result,
+            moves
+        );
+    }
+    table
+}
+
+fn solve(
+    labyrinth: &Labyrinth,
+    start: (usize, usize),
+    facing: MapDirection,
+    strategy: Strategy,
+) -> SolveResult {
+    let mut state = ServerState::new(labyrinth.clone(), ServerConfig::default());
+    state.players.insert(
+        SOLVER_KEY.to_string(),
+        Player {
+            id: 0,
+            name: "Solver".to_string(),
+            team_name: "Solver".to_string(),
+            position: start,
+            direction: facing,
+            moves: 0,
+            visited: HashSet::from([start]),
+            solves: 0,
+            rng: player_rng(None, 0),
+            secret: 0,
+            challenge: None,
+            hints: Vec::new(),
+        },
+    );
+
+    // Every cell can be entered from each side at most once by the wall follower
+    let max_moves = labyrinth.width * labyrinth.height * 4;
+    let mut path = match strategy {
+        Strategy::RightHand => VecDeque::new(),
+        Strategy::Bfs => match shortest_path(labyrinth, start) {
+            Some(path) => path,
+            None => return SolveResult::Failed { moves: 0 },
+        },
+    };
+
+    // Direction of the last move: a Back move does not turn the player around,
+    // so it can differ from the facing kept by the server
+    let mut heading = facing;
+    let mut moves = 0;
+    while moves < max_moves {
+        let player = &state.players[SOLVER_KEY];
+        let (position, facing) = (player.position, player.direction);
+        if position == labyrinth.exit_position {
+            return SolveResult::Exit { moves };
+        }
+
+        let target = match strategy {
+            Strategy::RightHand => {
+                let Some(target) = [
+                    Direction::Right,
+                    Direction::Front,
+                    Direction::Left,
+                    Direction::Back,
+                ]
+                .into_iter()
+                .map(|direction| absolute(heading, &direction))
+                .find(|&target| labyrinth.is_open(position, target)) else {
+                    return SolveResult::Failed { moves };
+                };
+                target
+            }
+            Strategy::Bfs => match path.pop_front() {
+                Some(target) => target,
+                None => return SolveResult::Failed { moves },
+            },
+        };
+        let direction = relative(facing, target);
+        heading = target;
+
+        let Ok(outcome) = state.apply_move(SOLVER_KEY, &direction) else {
+            return SolveResult::Failed { moves };
+        };
+        moves = outcome.moves;
+        if outcome.found_exit {
+            return SolveResult::Exit { moves };
+        }
+    }
+    SolveResult::Failed { moves }
+}
+
+/// Fewest moves from a cell to the exit, None if it cannot be reached.
+pub(crate) fn distance_to_exit(labyrinth: &Labyrinth, start: (usize, usize)) -> Option<usize> {
+    shortest_path(labyrinth, start).map(|path| path.len())
+}
+
+/// Reachable cell with the most moves from the start (the first found among the farthest).
+pub(crate) fn farthest_cell(labyrinth: &Labyrinth, start: (usize, usize)) -> (usize, usize) {
+    let mut queue = VecDeque::from([start]);
+    let mut seen = HashSet::from([start]);
+    let mut farthest = start;
+
+    while let Some(position) = queue.pop_front() {
+        farthest = position;
+        for direction in CLOCKWISE {
+            if labyrinth.is_open(position, direction) {
+                let next = step(position, direction);
+                if seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    farthest
+}
+
+/// Absolute directions to follow from the start to the exit, None if it cannot be reached.
+fn shortest_path(labyrinth: &Labyrinth, start: (usize, usize)) -> Option<VecDeque<MapDirection>> {
+    let mut previous: HashMap<(usize, usize), ((usize, usize), MapDirection)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    let mut seen = HashSet::from([start]);
+
+    while let Some(position) = queue.pop_front() {
+        if position == labyrinth.exit_position {
+            let mut path = VecDeque::new();
+            let mut current = position;
+            while let Some(&(from, direction)) = previous.get(&current) {
+                path.push_front(direction);
+                current = from;
+            }
+            return Some(path);
+        }
+        for direction in [
+            MapDirection::North,
+            MapDirection::East,
+            MapDirection::South,
+            MapDirection::West,
+        ] {
+            if !labyrinth.is_open(position, direction) {
+                continue;
+            }
+            let next = step(position, direction);
+            if seen.insert(next) {
+                previous.insert(next, (position, direction));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Neighbor of a cell, which must not be on the edge the direction points to.
+fn step((x, y): (usize, usize), direction: MapDirection) -> (usize, usize) {
+    match direction {
+        MapDirection::North => (x, y - 1),
+        MapDirection::East => (x + 1, y),
+        MapDirection::South => (x, y + 1),
+        MapDirection::West => (x - 1, y),
+    }
+}
+
+const CLOCKWISE: [MapDirection; 4] = [
+    MapDirection::North,
+    MapDirection::East,
+    MapDirection::South,
+    MapDirection::West,
+];
+
+/// Absolute direction of a move relative to the facing, as process_move applies it.
+pub(crate) fn absolute(facing: MapDirection, direction: &Direction) -> MapDirection {
+    let index = CLOCKWISE.iter().position(|&d| d == facing).unwrap();
+    let quarter_turns = match direction {
+        Direction::Front => 0,
+        Direction::Right => 1,
+        Direction::Back => 2,
+        Direction::Left => 3,
+    };
+    CLOCKWISE[(index + quarter_turns) % 4]
+}
+
+/// Relative move that goes toward an absolute direction from the facing.
+fn relative(facing: MapDirection, target: MapDirection) -> Direction {
+    [
+        Direction::Front,
+        Direction::Right,
+        Direction::Back,
+        Direction::Left,
+    ]
+    .into_iter()
+    .find(|direction| absolute(facing, direction) == target)
+    .unwrap()
+}
@@ -0,0 +1,155 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::{Labyrinth, MapDirection, ServerState};
+
+/// Game results kept for the whole server run.
+/// Players are removed from the state when they disconnect, so their moves are copied here.
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    /// Moves made by each player, keyed by "team/player".
+    player_moves: HashMap<String, usize>,
+    /// Players who found the exit, with the number of moves it took, in order of arrival.
+    exits: Vec<(String, usize)>,
+}
+
+impl Stats {
+    /// Remembers the number of moves made so far by a player.
+    pub(crate) fn record_move(&mut self, player_key: &str, moves: usize) {
+        self.player_moves.insert(player_key.to_string(), moves);
+    }
+
+    /// Remembers that a player found the exit after the given number of moves.
+    pub(crate) fn record_exit(&mut self, player_key: &str, moves: usize) {
+        self.record_move(player_key, moves);
+        self.exits.push((player_key.to_string(), moves));
+    }
+
+    /// Number of moves each player who found the exit needed, in order of arrival.
+    pub(crate) fn exit_moves(&self) -> impl Iterator<Item = usize> + '_ {
+        self.exits.iter().map(|(_, moves)| *moves)
+    }
+
+    /// Builds the JSON summary of the games played on this server:
+    /// every team with its players' moves, who found the exit and the number of games.
+    pub(crate) fn snapshot(state: &ServerState) -> Value {
+        let mut team_names: Vec<&String> = state.teams.keys().collect();
+        team_names.sort();
+
+        let teams: Vec<Value> = team_names
+            .into_iter()
+            .map(|team_name| {
+                let team = &state.teams[team_name];
+                let players: Vec<Value> = team
+                    .players
+                    .iter()
+                    .map(|player_name| {
+                        let player_key = format!("{}/{}", team_name, player_name);
+                        let moves = state
+                            .players
+                            .get(&player_key)
+                            .map(|player| player.moves)
+                            .or_else(|| state.stats.player_moves.get(&player_key).copied())
+                            .unwrap_or(0);
+                        let exit_moves = state
+                            .stats
+                            .exits
+                            .iter()
+                            .find(|(key, _)| *key == player_key)
+                            .map(|(_, moves)| *moves);
+                        json!({
+                            "name": player_name,
+                            "moves": moves,
+                            "found_exit": exit_moves.is_some(),
+                            "exit_moves": exit_moves,
+                        })
+                    })
+                    .collect();
+                json!({ "name": team_name, "players": players })
+            })
+            .collect();
+
+        let exits: Vec<Value> = state
+            .stats
+            .exits
+            .iter()
+            .map(|(player_key, moves)| json!({ "player": player_key, "moves": moves }))
+            .collect();
+
+        json!({
+            "total_games": state.teams.len(),
+            "teams": teams,
+            "exits": exits,
+        })
+    }
+}
+
+/// Distribution of the moves players needed to find the exit, used to tune maze difficulty.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MovesToExit {
+    pub(crate) average: f64,
+    /// Median, by nearest rank.
+    pub(crate) p50: usize,
+    /// 90th percentile, by nearest rank.
+    pub(crate) p90: usize,
+}
+
+impl MovesToExit {
+    /// Summarizes the moves of every finished player, None if nobody found the exit yet.
+    pub(crate) fn from_moves(moves: impl IntoIterator<Item = usize>) -> Option<MovesToExit> {
+        let mut moves: Vec<usize> = moves.into_iter().collect();
+        if moves.is_empty() {
+            return None;
+        }
+        moves.sort_unstable();
+
+        let percentile = |p: usize| moves[(moves.len() * p).div_ceil(100) - 1];
+        Some(MovesToExit {
+            average: moves.iter().sum::<usize>() as f64 / moves.len() as f64,
+            p50: percentile(50),
+            p90: percentile(90),
+        })
+    }
+}
+
+/// Statistics of a generated labyrinth, printed by --count-only to tune the generator:
+/// moves from the generation start to the exit, dead ends (cells with a single way out),
+/// hints, and open passages between two cells.
+pub(crate) fn maze_stats(labyrinth: &Labyrinth, exit_distance: usize) -> Value {
+    let cells = (0..labyrinth.height).flat_map(|y| (0..labyrinth.width).map(move |x| (x, y)));
+    let ways_out = |cell| {
+        [
+            MapDirection::North,
+            MapDirection::East,
+            MapDirection::South,
+            MapDirection::West,
+        ]
+        .into_iter()
+        .filter(|&direction| labyrinth.is_open(cell, direction))
+        .count()
+    };
+
+    let dead_ends = cells.clone().filter(|&cell| ways_out(cell) == 1).count();
+    let hints = cells
+        .clone()
+        .filter(|&(x, y)| labyrinth.cells[y][x].has_hint)
+        .count();
+    // Each passage is counted from its west or north cell only
+    let open_passages: usize = cells
+        .map(|cell| {
+            [MapDirection::East, MapDirection::South]
+                .into_iter()
+                .filter(|&direction| labyrinth.is_open(cell, direction))
+                .count()
+        })
+        .sum();
+
+    json!({
+        "width": labyrinth.width,
+        "height": labyrinth.height,
+        "exit_distance": exit_distance,
+        "dead_ends": dead_ends,
+        "hints": hints,
+        "open_passages": open_passages,
+    })
+}
@@ -0,0 +1,98 @@
+use log::{debug, error};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::stats::MovesToExit;
+use crate::{lock_state, ServerState};
+
+/// Builds the JSON served on the status page (--http-status):
+/// uptime, number of teams and players, and moves processed, summed over every arena,
+/// and the average, median and 90th percentile of the moves finished players needed to exit
+/// (null until someone finds the exit).
+pub(crate) fn status_json(arenas: &[Arc<Mutex<ServerState>>], uptime: Duration) -> Value {
+    let (mut teams, mut players, mut moves) = (0, 0, 0);
+    let mut exit_moves = Vec::new();
+    for state in arenas {
+        let state = lock_state(state);
+        teams += state.teams.len();
+        players += state.players.len();
+        // Every MoveTo action advances the tick, wall hits included
+        moves += state.tick;
+        exit_moves.extend(state.stats.exit_moves());
+    }
+
+    let moves_to_exit = MovesToExit::from_moves(exit_moves);
+    json!({
+        "uptime_secs": uptime.as_secs(),
+        "teams": teams,
+        "players": players,
+        "moves_processed": moves,
+        "avg_moves_to_exit": moves_to_exit.as_ref().map(|m| m.average),
+        "p50_moves_to_exit": moves_to_exit.as_ref().map(|m| m.p50),
+        "p90_moves_to_exit": moves_to_exit.as_ref().map(|m| m.p90),
+    })
+}
+
+/// How long a status client may take to send its request. Requests are answered one at a
+/// time, so a client that connects and sends nothing must not block the page for the others.
+const STATUS_READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Answers every HTTP request on the listener with the status JSON.
+pub(crate) fn serve_status(
+    listener: TcpListener,
+    arenas: Vec<Arc<Mutex<ServerState>>>,
+    started: Instant,
+) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_status_request(stream, &arenas, started) {
+                    error!("Failed to answer status request: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to accept status connection: {}", e),
+        }
+    }
+}
+
+/// Reads the request line and headers, then writes the response.
+/// Only GET is supported, whatever the path.
+fn handle_status_request(
+    stream: TcpStream,
+    arenas: &[Arc<Mutex<ServerState>>],
+    started: Instant,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(STATUS_READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    debug!("Status request: {}", request_line.trim_end());
+
+    // Skip the headers, up to the empty line
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let (status, body) = if request_line.starts_with("GET ") {
+        ("200 OK", status_json(arenas, started.elapsed()).to_string())
+    } else {
+        (
+            "405 Method Not Allowed",
+            json!({ "error": "Only GET is supported" }).to_string(),
+        )
+    };
+
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
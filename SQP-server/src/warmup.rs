@@ -0,0 +1,134 @@
+use log::debug;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use std::fmt;
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use SQP_common::server_utils::{parse_token_from_response, receive_message, send_message};
+
+/// How long the mock clients of a warmup run (--warmup) keep moving.
+pub(crate) const WARMUP_DURATION: Duration = Duration::from_secs(5);
+
+/// Throughput and latency measured by a warmup run.
+#[derive(Debug)]
+pub(crate) struct WarmupReport {
+    pub(crate) clients: usize,
+    /// Moves answered with a radar view, over every client.
+    pub(crate) moves: usize,
+    pub(crate) elapsed: Duration,
+    /// 99th percentile of the time between sending a move and receiving its radar view.
+    pub(crate) p99_latency: Duration,
+}
+
+impl WarmupReport {
+    /// Moves answered per second, over every client.
+    pub(crate) fn moves_per_sec(&self) -> f64 {
+        self.moves as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl fmt::Display for WarmupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Warmup: {} clients made {} moves in {:.2}s, {:.1} moves/s, p99 latency {:.2} ms",
+            self.clients,
+            self.moves,
+            self.elapsed.as_secs_f64(),
+            self.moves_per_sec(),
+            self.p99_latency.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Runs `clients` mock clients against the arena served on `address`: each registers its own
+/// team, subscribes a player and makes random moves for `duration`.
+/// Returns the number of moves answered and the p99 latency of the answers.
+pub(crate) fn run_warmup(
+    address: SocketAddr,
+    clients: usize,
+    duration: Duration,
+) -> Result<WarmupReport, String> {
+    let started = Instant::now();
+    let deadline = started + duration;
+    let handles: Vec<_> = (0..clients)
+        .map(|index| thread::spawn(move || mock_client(address, index, deadline)))
+        .collect();
+
+    let mut latencies = Vec::new();
+    for handle in handles {
+        let client_latencies = handle
+            .join()
+            .map_err(|_| "A warmup client panicked".to_string())??;
+        latencies.extend(client_latencies);
+    }
+    let elapsed = started.elapsed();
+
+    latencies.sort();
+    let p99_latency = match latencies.len() {
+        0 => Duration::ZERO,
+        count => latencies[(count * 99).div_ceil(100) - 1],
+    };
+    Ok(WarmupReport {
+        clients,
+        moves: latencies.len(),
+        elapsed,
+        p99_latency,
+    })
+}
+
+/// One mock client: registers "Warmup <index>", subscribes its player and moves at random
+/// until the deadline. Returns the latency of every move.
+fn mock_client(
+    address: SocketAddr,
+    index: usize,
+    deadline: Instant,
+) -> Result<Vec<Duration>, String> {
+    let connect = || TcpStream::connect(address).map_err(|e| e.to_string());
+    let mut team_stream = connect()?;
+    let register_team = json!({ "RegisterTeam": { "name": format!("Warmup {}", index) } });
+    send_message(&mut team_stream, &register_team).map_err(|e| e.to_string())?;
+    let response = receive_message(&mut team_stream).map_err(|e| e.to_string())?;
+    let token = parse_token_from_response(&response).map_err(|e| e.to_string())?;
+
+    let mut stream = connect()?;
+    let subscribe_player = json!({
+        "SubscribePlayer": { "name": "Bot", "registration_token": token }
+    });
+    send_message(&mut stream, &subscribe_player).map_err(|e| e.to_string())?;
+    let response = receive_message(&mut stream).map_err(|e| e.to_string())?;
+    if !response.contains("Ok") {
+        return Err(format!(
+            "Warmup {} could not subscribe: {}",
+            index, response
+        ));
+    }
+    next_radar_view(&mut stream)?;
+
+    let mut rng = rand::thread_rng();
+    let mut latencies = Vec::new();
+    while Instant::now() < deadline {
+        let direction = ["Front", "Right", "Back", "Left"].choose(&mut rng).unwrap();
+        let sent = Instant::now();
+        send_message(&mut stream, &json!({ "Action": { "MoveTo": direction } }))
+            .map_err(|e| e.to_string())?;
+        next_radar_view(&mut stream)?;
+        latencies.push(sent.elapsed());
+    }
+    debug!("Warmup {} made {} moves", index, latencies.len());
+    Ok(latencies)
+}
+
+/// Reads the responses of the server (walls, hints, exits) until a radar view comes in.
+fn next_radar_view(stream: &mut TcpStream) -> Result<Value, String> {
+    loop {
+        let response: Value =
+            serde_json::from_str(&receive_message(stream).map_err(|e| e.to_string())?)
+                .map_err(|e| e.to_string())?;
+        if response.get("RadarView").is_some() {
+            return Ok(response);
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! Wire models: the serde structs of the messages exchanged with clients, and nothing else.
+//! Fields are named in snake_case and renamed to the keys of the protocol's JSON.
+//! The game state (players, teams, labyrinth) lives in main.rs and is converted with `From`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{MapDirection, RadarOrientation};
+
+/**
+ * The RegisterTeam struct represents the content of the RegisterTeam message.
+ * It contains the team name.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct RegisterTeam {
+    pub(crate) name: String,
+    /// Team size asked by the client, clamped to the server's --max-team-size.
+    /// The reference protocol has no such field: the server's size is granted when None.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expected_players: Option<usize>,
+    /// Protocol version of the client, checked against PROTOCOL_VERSION. Reference clients
+    /// send none and are always accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) protocol_version: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct SubscribePlayer {
+    pub(crate) name: String,
+    pub(crate) registration_token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Answer {
+    pub(crate) answer: String,
+}
+
+/**
+ * The ToggleWall struct represents the content of the admin ToggleWall message.
+ * It flips the wall on the given side of the cell at (x, y).
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ToggleWall {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) side: MapDirection,
+}
+
+/**
+ * The ShareSecret struct represents a secret found by a player.
+ * The server relays it to the other players of the team, so that teams playing
+ * from several processes can all answer SecretSumModulo challenges.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ShareSecret {
+    pub(crate) player: String,
+    pub(crate) secret: u64,
+}
+
+/**
+ * The Teleport struct represents the content of the admin Teleport message.
+ * It moves the player of the connection to (x, y), facing the given direction.
+ * Coordinates are signed so that a position outside the labyrinth is refused, not unreadable.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Teleport {
+    pub(crate) x: i64,
+    pub(crate) y: i64,
+    pub(crate) facing: MapDirection,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) enum Action {
+    MoveTo(Direction),
+    SolveChallenge(Answer),
+    /// Keep-alive: the server answers with the current radar view, without moving nor counting a move.
+    Noop,
+    /// Asks which relative directions are open from the player's cell, without moving.
+    GetOpenDirections,
+    /// Asks for the hints the player received so far, oldest first.
+    GetHints,
+}
+
+/**
+ * The message enum represents the different types of messages that can be sent to the server.
+ * Each message type is represented by a struct.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) enum Message {
+    #[serde(rename_all = "camelCase")]
+    RegisterTeam(RegisterTeam),
+    SubscribePlayer(SubscribePlayer),
+    Action(Action),
+    ToggleWall(ToggleWall),
+    Teleport(Teleport),
+    ShareSecret(ShareSecret),
+}
+
+/**
+ * The IncomingMessage struct wraps a message read from a client.
+ * Actions may carry an optional `seq` next to the message, e.g.
+ * `{"Action":{"MoveTo":"Front"},"seq":7}`, which is echoed back in the radar view response.
+ */
+#[derive(Deserialize, Debug)]
+pub(crate) struct IncomingMessage {
+    #[serde(flatten)]
+    pub(crate) message: Message,
+    #[serde(default)]
+    pub(crate) seq: Option<u64>,
+}
+
+// Direction enum
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) enum Direction {
+    Front,
+    Back,
+    Left,
+    Right,
+}
+
+// Response models
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RegisterTeamResponseOk {
+    pub(crate) expected_players: usize,
+    pub(crate) registration_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum RegisterTeamResponseResult {
+    Ok(RegisterTeamResponseOk),
+    Error(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RegisterTeamResponse {
+    #[serde(rename = "RegisterTeamResult")]
+    pub(crate) result: RegisterTeamResponseResult,
+    /// Protocol version of the server, only sent to clients that sent theirs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) protocol_version: Option<u32>,
+}
+
+impl From<Result<RegisterTeamResponseOk, String>> for RegisterTeamResponse {
+    fn from(registration: Result<RegisterTeamResponseOk, String>) -> Self {
+        RegisterTeamResponse {
+            result: match registration {
+                Ok(ok) => RegisterTeamResponseResult::Ok(ok),
+                Err(reason) => RegisterTeamResponseResult::Error(reason),
+            },
+            protocol_version: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum SubscribePlayerResponseResult {
+    Ok,
+    Error(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SubscribePlayerResponse {
+    #[serde(rename = "SubscribePlayerResult")]
+    pub(crate) result: SubscribePlayerResponseResult,
+}
+
+impl From<Result<(), String>> for SubscribePlayerResponse {
+    fn from(subscription: Result<(), String>) -> Self {
+        SubscribePlayerResponse {
+            result: match subscription {
+                Ok(()) => SubscribePlayerResponseResult::Ok,
+                Err(reason) => SubscribePlayerResponseResult::Error(reason),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum ToggleWallResponseResult {
+    Ok,
+    Error(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ToggleWallResponse {
+    #[serde(rename = "ToggleWallResult")]
+    pub(crate) result: ToggleWallResponseResult,
+}
+
+impl From<Result<(), String>> for ToggleWallResponse {
+    fn from(toggle: Result<(), String>) -> Self {
+        ToggleWallResponse {
+            result: match toggle {
+                Ok(()) => ToggleWallResponseResult::Ok,
+                Err(reason) => ToggleWallResponseResult::Error(reason),
+            },
+        }
+    }
+}
+
+// New response type for radar view
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RadarViewResponse {
+    #[serde(rename = "RadarView")]
+    pub(crate) radar_view: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) seq: Option<u64>,
+    /// Direction the player faces, as a plain string ("North")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) facing: Option<MapDirection>,
+    /// How the radar view is turned, when given with --radar-orientation ("Absolute")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) orientation: Option<RadarOrientation>,
+    /// Set on a view the player did not ask for (a ToggleWall next to them), which clients
+    /// skip: it answers none of their actions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pushed: Option<bool>,
+}
+
+// New response type for found exit
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct FoundExitResponse {
+    #[serde(rename = "FoundExit")]
+    pub(crate) found_exit: bool,
+}
+
+// New response type for wall notification
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CannotPassThroughWallResponse {
+    #[serde(rename = "CannotPassThroughWall")]
+    pub(crate) cannot_pass_through_wall: bool,
+}
+
+// New response type for hint with compass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CompassData {
+    pub(crate) angle: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RelativeCompassResponse {
+    #[serde(rename = "RelativeCompass")]
+    pub(crate) relative_compass: CompassData,
+}
+
+/// A compass hint pointing `angle` degrees clockwise from the player's facing.
+impl From<f64> for RelativeCompassResponse {
+    fn from(angle: f64) -> Self {
+        RelativeCompassResponse {
+            relative_compass: CompassData { angle },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct HintResponse {
+    #[serde(rename = "Hint")]
+    pub(crate) hint: RelativeCompassResponse,
+}
+
+// Hint giving the player's secret, for SecretSumModulo challenges
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SecretHint {
+    #[serde(rename = "Secret")]
+    pub(crate) secret: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SecretHintResponse {
+    #[serde(rename = "Hint")]
+    pub(crate) hint: SecretHint,
+}
+
+impl From<u64> for SecretHintResponse {
+    fn from(secret: u64) -> Self {
+        SecretHintResponse {
+            hint: SecretHint { secret },
+        }
+    }
+}
+
+// A hint a player received, written as in its Hint message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) enum SentHint {
+    RelativeCompass(CompassData),
+    Secret(u64),
+}
+
+// Response to GetHints: the hints of the player, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct HintHistoryResponse {
+    #[serde(rename = "HintHistory")]
+    pub(crate) hints: Vec<SentHint>,
+}
+
+// Response to GetOpenDirections: whether each relative direction has no wall
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct OpenDirections {
+    pub(crate) front: bool,
+    pub(crate) right: bool,
+    pub(crate) back: bool,
+    pub(crate) left: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct OpenDirectionsResponse {
+    #[serde(rename = "OpenDirections")]
+    pub(crate) open_directions: OpenDirections,
+}
+
+/**
+ * The Challenge enum represents the challenges a player must solve before moving again.
+ * SecretSumModulo asks for the sum of the secrets of the team modulo the given number.
+ */
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Challenge {
+    SecretSumModulo(u64),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ChallengeResponse {
+    #[serde(rename = "Challenge")]
+    pub(crate) challenge: Challenge,
+}
+
+// Radar view and hint sent together to save a round-trip (--combined-hint)
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RadarViewWithHintResponse {
+    #[serde(rename = "RadarView")]
+    pub(crate) radar_view: String,
+    #[serde(rename = "Hint")]
+    pub(crate) hint: RelativeCompassResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) seq: Option<u64>,
+    /// Direction the player faces, as a plain string ("North")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) facing: Option<MapDirection>,
+    /// How the radar view is turned, when given with --radar-orientation ("Absolute")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) orientation: Option<RadarOrientation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn to_json(response: &impl Serialize) -> Value {
+        serde_json::to_value(response).unwrap()
+    }
+
+    #[test]
+    fn test_responses_serialize_to_the_protocol_keys() {
+        let registered = RegisterTeamResponse::from(Ok(RegisterTeamResponseOk {
+            expected_players: 3,
+            registration_token: "0123456789abcdef".to_string(),
+        }));
+        assert_eq!(
+            to_json(&registered),
+            json!({ "RegisterTeamResult": { "Ok": {
+                "expected_players": 3, "registration_token": "0123456789abcdef"
+            } } })
+        );
+        let refused = RegisterTeamResponse::from(Err("AlreadyRegistered".to_string()));
+        assert_eq!(
+            to_json(&refused),
+            json!({ "RegisterTeamResult": { "Error": "AlreadyRegistered" } })
+        );
+
+        assert_eq!(
+            to_json(&SubscribePlayerResponse::from(Ok(()))),
+            json!({ "SubscribePlayerResult": "Ok" })
+        );
+        assert_eq!(
+            to_json(&SubscribePlayerResponse::from(Err("team full".to_string()))),
+            json!({ "SubscribePlayerResult": { "Error": "team full" } })
+        );
+        assert_eq!(
+            to_json(&ToggleWallResponse::from(Ok(()))),
+            json!({ "ToggleWallResult": "Ok" })
+        );
+        assert_eq!(
+            to_json(&ToggleWallResponse::from(Err("no wall".to_string()))),
+            json!({ "ToggleWallResult": { "Error": "no wall" } })
+        );
+
+        let radar_view = RadarViewResponse {
+            radar_view: "aeeaabqaaaaaaia".to_string(),
+            seq: Some(7),
+            facing: Some(MapDirection::East),
+            orientation: None,
+            pushed: None,
+        };
+        assert_eq!(
+            to_json(&radar_view),
+            json!({ "RadarView": "aeeaabqaaaaaaia", "seq": 7, "facing": "East" })
+        );
+        let absolute_radar_view = RadarViewResponse {
+            orientation: Some(RadarOrientation::Absolute),
+            ..radar_view
+        };
+        assert_eq!(
+            to_json(&absolute_radar_view),
+            json!({
+                "RadarView": "aeeaabqaaaaaaia", "seq": 7, "facing": "East", "orientation": "Absolute"
+            })
+        );
+        let pushed_radar_view = RadarViewResponse {
+            seq: None,
+            pushed: Some(true),
+            ..absolute_radar_view
+        };
+        assert_eq!(
+            to_json(&pushed_radar_view),
+            json!({
+                "RadarView": "aeeaabqaaaaaaia", "facing": "East", "orientation": "Absolute", "pushed": true
+            })
+        );
+        let radar_view_with_hint = RadarViewWithHintResponse {
+            radar_view: "aeeaabqaaaaaaia".to_string(),
+            hint: RelativeCompassResponse::from(90.0),
+            seq: None,
+            facing: None,
+            orientation: None,
+        };
+        assert_eq!(
+            to_json(&radar_view_with_hint),
+            json!({
+                "RadarView": "aeeaabqaaaaaaia",
+                "Hint": { "RelativeCompass": { "angle": 90.0 } }
+            })
+        );
+        let hint = HintResponse {
+            hint: RelativeCompassResponse::from(45.0),
+        };
+        assert_eq!(
+            to_json(&hint),
+            json!({ "Hint": { "RelativeCompass": { "angle": 45.0 } } })
+        );
+        assert_eq!(
+            to_json(&SecretHintResponse::from(42)),
+            json!({ "Hint": { "Secret": 42 } })
+        );
+        let history = HintHistoryResponse {
+            hints: vec![
+                SentHint::RelativeCompass(CompassData { angle: 45.0 }),
+                SentHint::Secret(42),
+            ],
+        };
+        assert_eq!(
+            to_json(&history),
+            json!({ "HintHistory": [{ "RelativeCompass": { "angle": 45.0 } }, { "Secret": 42 }] })
+        );
+
+        assert_eq!(
+            to_json(&FoundExitResponse { found_exit: true }),
+            json!({ "FoundExit": true })
+        );
+        let wall = CannotPassThroughWallResponse {
+            cannot_pass_through_wall: true,
+        };
+        assert_eq!(to_json(&wall), json!({ "CannotPassThroughWall": true }));
+        let open_directions = OpenDirectionsResponse {
+            open_directions: OpenDirections {
+                front: true,
+                right: false,
+                back: true,
+                left: false,
+            },
+        };
+        assert_eq!(
+            to_json(&open_directions),
+            json!({ "OpenDirections": {
+                "front": true, "right": false, "back": true, "left": false
+            } })
+        );
+
+        let challenge = ChallengeResponse {
+            challenge: Challenge::SecretSumModulo(7),
+        };
+        assert_eq!(
+            to_json(&challenge),
+            json!({ "Challenge": { "SecretSumModulo": 7 } })
+        );
+    }
+
+    #[test]
+    fn test_client_messages_read_the_protocol_keys() {
+        let read = |message: Value| serde_json::from_value::<IncomingMessage>(message).unwrap();
+
+        let incoming = read(json!({ "RegisterTeam": { "name": "Team 1" } }));
+        assert!(
+            matches!(incoming.message, Message::RegisterTeam(team) if team.name == "Team 1" && team.expected_players.is_none())
+        );
+        let incoming = read(json!({
+            "SubscribePlayer": { "name": "Nino", "registration_token": "0123456789abcdef" }
+        }));
+        assert!(
+            matches!(incoming.message, Message::SubscribePlayer(player) if player.registration_token == "0123456789abcdef")
+        );
+        let incoming = read(json!({ "Action": { "MoveTo": "Front" }, "seq": 7 }));
+        assert!(matches!(
+            incoming.message,
+            Message::Action(Action::MoveTo(Direction::Front))
+        ));
+        assert_eq!(incoming.seq, Some(7));
+    }
+}